@@ -0,0 +1,59 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use minhook_detours_rs::{
+    bench::{bench_batching, bench_call_overhead, bench_hook_operations},
+    test_support::{add_c, divide_f64, multiply_system, return_number, sum_four, sum_six},
+};
+use std::{
+    hint::black_box,
+    os::raw::c_void,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+static ADD_C_ORIGINAL: AtomicPtr<c_void> = AtomicPtr::new(std::ptr::null_mut());
+
+extern "C" fn add_c_hook(x: i32, y: i32) -> i64 {
+    let original: extern "C" fn(i32, i32) -> i64 =
+        unsafe { std::mem::transmute(ADD_C_ORIGINAL.load(Ordering::Relaxed)) };
+    original(x, y)
+}
+
+fn return_number_hook() -> u32 {
+    1337
+}
+
+fn operations(c: &mut Criterion) {
+    bench_hook_operations(c, "return_number", return_number as _, return_number_hook as _);
+}
+
+fn batching(c: &mut Criterion) {
+    // Detours are never called, as the targets aren't.
+    let detour = return_number_hook as *mut c_void;
+
+    bench_batching(
+        c,
+        &[
+            (return_number as _, detour),
+            (add_c as _, detour),
+            (multiply_system as _, detour),
+            (sum_four as _, detour),
+            (sum_six as _, detour),
+            (divide_f64 as _, detour),
+        ],
+    );
+}
+
+fn call_overhead(c: &mut Criterion) {
+    bench_call_overhead(
+        c,
+        "add_c",
+        add_c as _,
+        add_c_hook as _,
+        |original| ADD_C_ORIGINAL.store(original, Ordering::Relaxed),
+        || {
+            black_box(add_c(black_box(2), black_box(2)));
+        },
+    );
+}
+
+criterion_group!(benches, operations, batching, call_overhead);
+criterion_main!(benches);
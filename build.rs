@@ -0,0 +1,33 @@
+fn main() {
+    // Keep the C ABI header in sync with the Rust declarations.
+    #[cfg(feature = "capi-header")]
+    generate_capi_header();
+
+    // The delay-load import tests hook an import of their own, which only MSVC's linker can delay-load.
+    if std::env::var("CARGO_CFG_TARGET_ENV").as_deref() == Ok("msvc") {
+        println!("cargo:rustc-link-arg-tests=/DELAYLOAD:version.dll");
+        println!("cargo:rustc-link-arg-tests=delayimp.lib");
+    }
+}
+
+#[cfg(feature = "capi-header")]
+fn generate_capi_header() {
+    use std::path::PathBuf;
+
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+
+    // Never write into the sources, which may be read-only, unless explicitly asked to.
+    let header_dir = std::env::var_os("MINHOOK_DETOURS_HEADER_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(std::env::var_os("OUT_DIR").unwrap()).join("include"));
+
+    std::fs::create_dir_all(&header_dir).expect("Unable to create the C ABI header directory");
+
+    cbindgen::generate(&crate_dir)
+        .expect("Unable to generate the C ABI header")
+        .write_to_file(header_dir.join("minhook_detours.h"));
+
+    println!("cargo:rerun-if-changed=src/capi");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-env-changed=MINHOOK_DETOURS_HEADER_DIR");
+}
@@ -0,0 +1,75 @@
+//! Architecture helpers.
+//!
+//! Responsible for the constraints that depend on the architecture of the code being patched, e.g. ARM64
+//! code living inside of an Arm64EC process, which the x64 engine can't patch.
+
+use std::os::raw::c_void;
+#[cfg(any(target_arch = "x86_64", target_arch = "arm64ec"))]
+use std::{mem::transmute, sync::OnceLock};
+
+#[cfg(any(target_arch = "x86_64", target_arch = "arm64ec"))]
+use crate::pe::Module;
+
+/// Whether `address` is native ARM64 code of an Arm64EC process, as opposed to x64 code.
+///
+/// Always `false` outside of Arm64EC processes, and on versions of Windows that predate them.
+#[cfg(any(target_arch = "x86_64", target_arch = "arm64ec"))]
+pub(crate) fn is_arm64ec_code(address: *const c_void) -> bool {
+    type RtlIsEcCode = unsafe extern "system" fn(*const c_void) -> u8;
+
+    static RTL_IS_EC_CODE: OnceLock<Option<RtlIsEcCode>> = OnceLock::new();
+
+    let is_ec_code = RTL_IS_EC_CODE.get_or_init(|| {
+        let export = Module::from_name("ntdll.dll")
+            .ok()?
+            .export("RtlIsEcCode")
+            .ok()?;
+
+        Some(unsafe { transmute::<*mut c_void, RtlIsEcCode>(export) })
+    });
+
+    is_ec_code.is_some_and(|is_ec_code| unsafe { is_ec_code(address) } != 0)
+}
+
+/// Whether `address` is native ARM64 code of an Arm64EC process, as opposed to x64 code.
+#[cfg(not(any(target_arch = "x86_64", target_arch = "arm64ec")))]
+pub(crate) fn is_arm64ec_code(_address: *const c_void) -> bool {
+    false
+}
+
+/// The Process Environment Block of the current process, read out of the Thread Environment Block.
+///
+/// Null on architectures whose TEB isn't reachable, e.g. Arm64EC.
+pub(crate) fn peb() -> *const u8 {
+    #[allow(unused_assignments, unused_mut)]
+    let mut peb: *const u8 = std::ptr::null();
+
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        std::arch::asm!(
+            "mov {}, gs:[0x60]",
+            out(reg) peb,
+            options(nostack, readonly, preserves_flags)
+        );
+    }
+
+    #[cfg(target_arch = "x86")]
+    unsafe {
+        std::arch::asm!(
+            "mov {}, fs:[0x30]",
+            out(reg) peb,
+            options(nostack, readonly, preserves_flags)
+        );
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        std::arch::asm!(
+            "ldr {}, [x18, #0x60]",
+            out(reg) peb,
+            options(nostack, readonly, preserves_flags)
+        );
+    }
+
+    peb
+}
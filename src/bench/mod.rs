@@ -0,0 +1,127 @@
+//! Benchmark harness.
+//!
+//! Responsible for measuring the latency of hook operations with Criterion, against any function the
+//! caller can resolve, so that the cost of hooking can be weighed on the targets that matter.
+//!
+//! ```ignore
+//! fn benches(c: &mut Criterion) {
+//!     let target = Target::export("kernel32.dll", "GetTickCount").resolve().unwrap();
+//!     bench_hook_operations(c, "GetTickCount", target, get_tick_count_hook as _);
+//! }
+//! ```
+//!
+//! Every function creates a [`DetourGuard`] of its own, so none may be alive while benchmarking.
+
+use criterion::{BatchSize, BenchmarkId, Criterion};
+use std::os::raw::c_void;
+
+use crate::guard::{DetourGuard, HookConfig};
+
+/// Measure creating and removing, and enabling and disabling a hook for `target`.
+///
+/// # Arguments
+///
+/// * `c` - The Criterion instance.
+/// * `name` - The name of the target, in the benchmark IDs.
+/// * `target` - The function to be hooked, which may be called while it's being patched.
+/// * `detour` - The place where the function jumps to, while hooked.
+pub fn bench_hook_operations(c: &mut Criterion, name: &str, target: *mut c_void, detour: *mut c_void) {
+    let mut group = c.benchmark_group("hook_operations");
+
+    // Removed hooks stay in the registry of their guard, so every iteration gets a guard of its own, whose
+    // construction, and drop aren't measured, instead of growing a single registry for the whole run.
+    group.bench_function(BenchmarkId::new("create_remove", name), |b| {
+        b.iter_batched_ref(
+            || DetourGuard::new().expect("failed initializing the engine"),
+            |guard| {
+                let _ = guard.create_hook::<*mut c_void>(target, detour).unwrap();
+                guard.remove_hook(target).unwrap();
+            },
+            BatchSize::PerIteration,
+        )
+    });
+
+    let mut guard = DetourGuard::new().expect("failed initializing the engine");
+    let _ = guard.create_hook::<*mut c_void>(target, detour).unwrap();
+
+    group.bench_function(BenchmarkId::new("enable_disable", name), |b| {
+        b.iter(|| {
+            guard.enable_hook(target).unwrap();
+            guard.disable_hook(target).unwrap();
+        })
+    });
+
+    group.finish();
+}
+
+/// Measure enabling, and disabling every hook of `hooks` one by one, against doing so in a single queued
+/// transaction.
+///
+/// # Arguments
+///
+/// * `c` - The Criterion instance.
+/// * `hooks` - The targets to be hooked, along with their detours.
+pub fn bench_batching(c: &mut Criterion, hooks: &[(*mut c_void, *mut c_void)]) {
+    let mut guard = DetourGuard::new().expect("failed initializing the engine");
+    let mut group = c.benchmark_group("batching");
+
+    for (index, (target, detour)) in hooks.iter().enumerate() {
+        let _ = guard.create_hook::<*mut c_void>(*target, *detour).unwrap();
+        guard.set_hook_name(*target, index.to_string()).unwrap();
+    }
+
+    let enabled = (0..hooks.len()).map(|index| (index.to_string(), true)).collect::<HookConfig>();
+    let disabled = (0..hooks.len()).map(|index| (index.to_string(), false)).collect::<HookConfig>();
+
+    group.bench_function(BenchmarkId::new("individual", hooks.len()), |b| {
+        b.iter(|| {
+            for (target, _) in hooks {
+                guard.enable_hook(*target).unwrap();
+            }
+            for (target, _) in hooks {
+                guard.disable_hook(*target).unwrap();
+            }
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("queued", hooks.len()), |b| {
+        b.iter(|| {
+            guard.apply_config(&enabled).unwrap();
+            guard.apply_config(&disabled).unwrap();
+        })
+    });
+
+    group.finish();
+}
+
+/// Measure the overhead a hook adds to every call of `call`, by running it before, and after enabling a hook
+/// of `target` whose detour forwards to the original function.
+///
+/// # Arguments
+///
+/// * `c` - The Criterion instance.
+/// * `name` - The name of the target, in the benchmark IDs.
+/// * `target` - The function to be hooked.
+/// * `detour` - A detour forwarding to the original function, which it finds through `original`.
+/// * `original` - Receives the pointer to the original function, before the hook is enabled.
+/// * `call` - Calls `target`.
+pub fn bench_call_overhead(
+    c: &mut Criterion,
+    name: &str,
+    target: *mut c_void,
+    detour: *mut c_void,
+    original: impl FnOnce(*mut c_void),
+    mut call: impl FnMut(),
+) {
+    let mut guard = DetourGuard::new().expect("failed initializing the engine");
+    let mut group = c.benchmark_group("call_overhead");
+
+    group.bench_function(BenchmarkId::new("unhooked", name), |b| b.iter(&mut call));
+
+    original(*guard.create_hook::<*mut c_void>(target, detour).unwrap());
+    guard.enable_hook(target).unwrap();
+
+    group.bench_function(BenchmarkId::new("hooked", name), |b| b.iter(&mut call));
+
+    group.finish();
+}
@@ -0,0 +1,157 @@
+//! C ABI.
+//!
+//! Responsible for exposing a process-wide [`DetourGuard`] to non-Rust components, so that they drive hooks
+//! through the same engine instance. Build the crate with `cargo rustc --features capi --crate-type cdylib`
+//! to produce a linkable library. With the `capi-header` feature, the matching header is generated into
+//! `$OUT_DIR/include/minhook_detours.h`, or into the directory named by the `MINHOOK_DETOURS_HEADER_DIR`
+//! environment variable.
+//!
+//! Every function returns `MH_OK` on success, the matching `MH_STATUS` for MinHook-native errors, and
+//! [`MHD_ERROR_UNKNOWN`] for the rest.
+
+use minhook_detours_sys::{
+    MH_ERROR_ALREADY_INITIALIZED, MH_ERROR_NOT_INITIALIZED, MH_FREEZE_METHOD_FAST_UNDOCUMENTED,
+    MH_FREEZE_METHOD_NONE_UNSAFE, MH_FREEZE_METHOD_ORIGINAL, MH_OK, MH_THREAD_FREEZE_METHOD,
+};
+use std::{
+    os::raw::{c_int, c_void},
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use crate::{
+    error::Result,
+    guard::{DetourGuard, ThreadFreezeMethod},
+};
+
+/// Returned for errors that have no MinHook-native equivalent.
+pub const MHD_ERROR_UNKNOWN: c_int = -1;
+
+/// The process-wide guard, driven by the C ABI.
+static GUARD: Mutex<Option<DetourGuard<'static>>> = Mutex::new(None);
+
+fn lock() -> MutexGuard<'static, Option<DetourGuard<'static>>> {
+    // A panic can't leave the guard in a state that is worse than the engine's, so keep going.
+    GUARD.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Run `operation` on the process-wide guard, if it is initialized.
+fn with_guard(operation: impl FnOnce(&mut DetourGuard<'static>) -> Result<()>) -> c_int {
+    match lock().as_mut() {
+        Some(guard) => status(operation(guard)),
+        None => MH_ERROR_NOT_INITIALIZED as c_int,
+    }
+}
+
+/// Convert `result` to the status reported through the C ABI.
+fn status(result: Result<()>) -> c_int {
+    match result {
+        Ok(()) => MH_OK as c_int,
+        Err(error) => error
+            .raw_status()
+            .map_or(MHD_ERROR_UNKNOWN, |status| status as c_int),
+    }
+}
+
+/// Initialize the process-wide engine instance.
+#[unsafe(no_mangle)]
+pub extern "C" fn mhd_init() -> c_int {
+    let mut guard = lock();
+
+    if guard.is_some() {
+        return MH_ERROR_ALREADY_INITIALIZED as c_int;
+    }
+
+    match DetourGuard::new() {
+        Ok(created) => {
+            *guard = Some(created);
+            MH_OK as c_int
+        }
+        Err(error) => status(Err(error)),
+    }
+}
+
+/// Uninitialize the process-wide engine instance, disabling, and removing every hook.
+#[unsafe(no_mangle)]
+pub extern "C" fn mhd_uninit() -> c_int {
+    let mut guard = lock();
+
+    let Some(current) = guard.as_mut() else {
+        return MH_ERROR_NOT_INITIALIZED as c_int;
+    };
+
+    // Keep the guard around if closing fails, so that it can be retried.
+    if let Err(error) = current.try_close() {
+        return status(Err(error));
+    }
+
+    // Make sure destructor doesn't run.
+    std::mem::forget(guard.take());
+    MH_OK as c_int
+}
+
+/// Register a hook for `target`, without enabling it.
+///
+/// # Safety
+///
+/// `original` must either be null, or valid for writes. Upon success, it receives the pointer to the
+/// original function.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mhd_create_hook(
+    target: *mut c_void,
+    detour: *mut c_void,
+    original: *mut *mut c_void,
+) -> c_int {
+    with_guard(|guard| {
+        let created = guard.create_hook::<*mut c_void>(target, detour)?;
+
+        if !original.is_null() {
+            unsafe { original.write(*created) };
+        }
+
+        Ok(())
+    })
+}
+
+/// Remove the hook attached to `target`, disabling it first if needed.
+#[unsafe(no_mangle)]
+pub extern "C" fn mhd_remove_hook(target: *mut c_void) -> c_int {
+    with_guard(|guard| guard.remove_hook(target))
+}
+
+/// Enable the hook attached to `target`.
+#[unsafe(no_mangle)]
+pub extern "C" fn mhd_enable(target: *mut c_void) -> c_int {
+    with_guard(|guard| guard.enable_hook(target))
+}
+
+/// Disable the hook attached to `target`.
+#[unsafe(no_mangle)]
+pub extern "C" fn mhd_disable(target: *mut c_void) -> c_int {
+    with_guard(|guard| guard.disable_hook(target))
+}
+
+/// Enable every registered hook.
+#[unsafe(no_mangle)]
+pub extern "C" fn mhd_enable_all() -> c_int {
+    with_guard(|guard| guard.enable_all_hooks())
+}
+
+/// Disable every registered hook.
+#[unsafe(no_mangle)]
+pub extern "C" fn mhd_disable_all() -> c_int {
+    with_guard(|guard| guard.disable_all_hooks())
+}
+
+/// Set the thread freezing method, as one of the `MH_FREEZE_METHOD_*` values.
+#[unsafe(no_mangle)]
+pub extern "C" fn mhd_set_thread_freeze_method(method: MH_THREAD_FREEZE_METHOD) -> c_int {
+    // Reject values the conversion below doesn't know about, rather than panicking across the boundary.
+    if !matches!(
+        method,
+        MH_FREEZE_METHOD_ORIGINAL | MH_FREEZE_METHOD_FAST_UNDOCUMENTED | MH_FREEZE_METHOD_NONE_UNSAFE
+    ) {
+        return MHD_ERROR_UNKNOWN;
+    }
+
+    with_guard(|guard| guard.set_thread_freeze_method(ThreadFreezeMethod::from(method)))
+}
@@ -0,0 +1,200 @@
+//! `minhook` compatibility.
+//!
+//! Mirrors the functions of the `minhook` crate's [`MinHook`], implemented on top of a process-wide
+//! [`DetourGuard`] created upon first use, so that call sites can migrate one at a time.
+//!
+//! Failures are reported as [`Error`] rather than as `MH_STATUS`, and only one [`DetourGuard`] may exist at a
+//! time, so the shim can't be used alongside a [`DetourGuard`] of your own until [`MinHook::uninitialize`].
+
+use std::{os::raw::c_void, sync::Mutex};
+
+use crate::{
+    error::{Error, Result},
+    guard::DetourGuard,
+    target::Target,
+};
+
+/// The process-wide guard, along with the operations queued through [`MinHook::queue_enable_hook`], and
+/// [`MinHook::queue_disable_hook`].
+struct Global {
+    guard: DetourGuard<'static>,
+    enable: Vec<*mut c_void>,
+    disable: Vec<*mut c_void>,
+}
+
+// The [`DetourGuard`] is [`Send`], and the queued targets are only compared.
+unsafe impl Send for Global {}
+
+static GLOBAL: Mutex<Option<Global>> = Mutex::new(None);
+
+/// Runs `operation` on the process-wide guard, creating it if needed.
+fn with_global<R>(operation: impl FnOnce(&mut Global) -> Result<R>) -> Result<R> {
+    let mut global = GLOBAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    if global.is_none() {
+        *global = Some(Global {
+            guard: DetourGuard::new()?,
+            enable: Vec::new(),
+            disable: Vec::new(),
+        });
+    }
+
+    operation(global.as_mut().unwrap())
+}
+
+/// Counterpart of the `minhook` crate's `MinHook`.
+#[derive(Debug, Clone, Copy)]
+pub struct MinHook;
+
+impl MinHook {
+    /// Registers a hook for `target`, detouring it to `detour`, see [`DetourGuard::create_hook`].
+    ///
+    /// # Safety
+    ///
+    /// `target` must be safe to patch, and `detour` must stay valid for as long as the hook is enabled.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(*mut c_void)` with the original function if the hook was succesfully registered.
+    /// - `Err(minhook_detours_rs::error::Error)` if the operation failed.
+    pub unsafe fn create_hook(target: *mut c_void, detour: *mut c_void) -> Result<*mut c_void> {
+        with_global(|global| Ok(*global.guard.create_hook::<*mut c_void>(target, detour)?))
+    }
+
+    /// Registers a hook for the export `proc_name` of the loaded module `module_name`, detouring it to
+    /// `detour`, see [`DetourGuard::create_hook_at`].
+    ///
+    /// # Safety
+    ///
+    /// Same as [`MinHook::create_hook`].
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(*mut c_void)` with the original function if the hook was succesfully registered.
+    /// - `Err(minhook_detours_rs::error::Error)` if the resolution, or the operation failed.
+    pub unsafe fn create_hook_api<T: AsRef<str>>(
+        module_name: T,
+        proc_name: T,
+        detour: *mut c_void,
+    ) -> Result<*mut c_void> {
+        let target = Target::export(module_name.as_ref(), proc_name.as_ref());
+
+        with_global(|global| {
+            let (_, original) = global.guard.create_hook_at::<*mut c_void>(&target, detour)?;
+            Ok(*original)
+        })
+    }
+
+    /// Enables the hook for `target`, see [`DetourGuard::enable_hook`].
+    ///
+    /// # Safety
+    ///
+    /// The detour must be ready to be called from any thread.
+    pub unsafe fn enable_hook(target: *mut c_void) -> Result<()> {
+        with_global(|global| global.guard.enable_hook(target))
+    }
+
+    /// Disables the hook for `target`, see [`DetourGuard::disable_hook`].
+    ///
+    /// # Safety
+    ///
+    /// Nothing may rely on the hook being enabled anymore.
+    pub unsafe fn disable_hook(target: *mut c_void) -> Result<()> {
+        with_global(|global| global.guard.disable_hook(target))
+    }
+
+    /// Removes the hook for `target`, see [`DetourGuard::remove_hook`].
+    ///
+    /// # Safety
+    ///
+    /// Same as [`MinHook::disable_hook`].
+    pub unsafe fn remove_hook(target: *mut c_void) -> Result<()> {
+        with_global(|global| global.guard.remove_hook(target))
+    }
+
+    /// Enables every hook, see [`DetourGuard::enable_all_hooks`].
+    ///
+    /// # Safety
+    ///
+    /// Same as [`MinHook::enable_hook`].
+    pub unsafe fn enable_all_hooks() -> Result<()> {
+        with_global(|global| global.guard.enable_all_hooks())
+    }
+
+    /// Disables every hook, see [`DetourGuard::disable_all_hooks`].
+    ///
+    /// # Safety
+    ///
+    /// Same as [`MinHook::disable_hook`].
+    pub unsafe fn disable_all_hooks() -> Result<()> {
+        with_global(|global| global.guard.disable_all_hooks())
+    }
+
+    /// Queues enabling the hook for `target`, until [`MinHook::apply_queued`].
+    ///
+    /// # Safety
+    ///
+    /// Same as [`MinHook::enable_hook`].
+    pub unsafe fn queue_enable_hook(target: *mut c_void) -> Result<()> {
+        with_global(|global| {
+            if global.guard.hook_info(target).is_none() {
+                return Err(Error::NotCreated);
+            }
+
+            global.disable.retain(|queued| *queued != target);
+            global.enable.push(target);
+            Ok(())
+        })
+    }
+
+    /// Queues disabling the hook for `target`, until [`MinHook::apply_queued`].
+    ///
+    /// # Safety
+    ///
+    /// Same as [`MinHook::disable_hook`].
+    pub unsafe fn queue_disable_hook(target: *mut c_void) -> Result<()> {
+        with_global(|global| {
+            if global.guard.hook_info(target).is_none() {
+                return Err(Error::NotCreated);
+            }
+
+            global.enable.retain(|queued| *queued != target);
+            global.disable.push(target);
+            Ok(())
+        })
+    }
+
+    /// Applies the queued operations in a single transaction, see [`DetourGuard::enable_hooks`].
+    ///
+    /// # Safety
+    ///
+    /// Same as [`MinHook::enable_hook`], and [`MinHook::disable_hook`].
+    pub unsafe fn apply_queued() -> Result<()> {
+        with_global(|global| {
+            let enable = std::mem::take(&mut global.enable);
+            let disable = std::mem::take(&mut global.disable);
+
+            global.guard.apply_queued(&enable, &disable)
+        })
+    }
+
+    /// Closes the process-wide [`DetourGuard`], disabling, and removing every hook created through the shim.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if the [`DetourGuard`] was succesfully closed, or never created.
+    /// - `Err(minhook_detours_rs::error::Error)` if the deinitialization didn't succeed, in which case the
+    ///   [`DetourGuard`] is kept for a later attempt.
+    pub fn uninitialize() -> Result<()> {
+        let mut global = GLOBAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let Some(current) = global.as_mut() else {
+            return Ok(());
+        };
+
+        current.guard.try_close()?;
+        *global = None;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,9 @@
+//! Compatibility layers.
+//!
+//! Responsible for bridging the APIs of other hooking crates onto [`crate::guard::DetourGuard`], so that code
+//! written against them can migrate incrementally, each behind the feature named after the crate.
+
+#[cfg(feature = "minhook-rs")]
+pub mod minhook_rs;
+#[cfg(feature = "retour")]
+pub mod retour;
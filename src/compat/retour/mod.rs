@@ -0,0 +1,138 @@
+//! `retour` compatibility.
+//!
+//! Hooks are typed through [`Function`], and [`HookableWith`], which `retour` implements for every function
+//! pointer type, so that the typed declarations written against `retour` keep type-checking detours, and
+//! originals once moved to the engine.
+
+use std::marker::PhantomData;
+
+pub use retour::{Function, HookableWith};
+
+use crate::{
+    error::{Error, Result},
+    guard::{DetourGuard, HookHandle},
+};
+
+/// Counterpart of `retour::GenericDetour`, whose operations go through the [`DetourGuard`] the hook was
+/// registered in, rather than through a global engine.
+#[derive(Debug, Clone, Copy)]
+pub struct GenericDetour<T: Function> {
+    handle: HookHandle,
+    _function: PhantomData<T>,
+}
+
+impl<T: Function> GenericDetour<T> {
+    /// Registers a hook for `target` in `guard`, detouring it to `detour`. Refer to
+    /// [`DetourGuard::create_hook`] for further explaination.
+    ///
+    /// # Arguments
+    ///
+    /// * `guard` - The guard the hook is registered in.
+    /// * `target` - The function to be hooked.
+    /// * `detour` - The place where the function will jump to, while hooked.
+    ///
+    /// # Safety
+    ///
+    /// Same as `retour::GenericDetour::new`: `target` must be safe to patch, and `detour` must stay valid
+    /// for as long as the hook is enabled.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(GenericDetour)` if the hook was succesfully registered.
+    /// - `Err(minhook_detours_rs::error::Error)` if the operation failed.
+    pub unsafe fn new<D>(guard: &mut DetourGuard<'_>, target: T, detour: D) -> Result<Self>
+    where
+        T: HookableWith<D>,
+        D: Function,
+    {
+        let target = target.to_ptr() as *mut _;
+        let _ = guard.create_hook::<T>(target, detour.to_ptr() as *mut _)?;
+
+        Ok(Self {
+            handle: guard.handle(target).ok_or(Error::NotCreated)?,
+            _function: PhantomData,
+        })
+    }
+
+    /// Enables the hook, see [`DetourGuard::enable_hook`].
+    ///
+    /// # Safety
+    ///
+    /// Same as `retour::GenericDetour::enable`.
+    pub unsafe fn enable(&self, guard: &mut DetourGuard<'_>) -> Result<()> {
+        guard.enable_hook(self.handle.target())
+    }
+
+    /// Disables the hook, see [`DetourGuard::disable_hook`].
+    ///
+    /// # Safety
+    ///
+    /// Same as `retour::GenericDetour::disable`.
+    pub unsafe fn disable(&self, guard: &mut DetourGuard<'_>) -> Result<()> {
+        guard.disable_hook(self.handle.target())
+    }
+
+    /// Whether the hook is currently enabled in `guard`.
+    pub fn is_enabled(&self, guard: &DetourGuard<'_>) -> bool {
+        guard
+            .hook_info(self.handle.target())
+            .is_some_and(|hook| hook.is_enabled())
+    }
+
+    /// The original function, typed as the hooked one, which `retour` calls the trampoline.
+    ///
+    /// Looked up through `guard`, as the trampoline is freed along with the hook, so it must only be called
+    /// while the hook is registered in `guard`.
+    ///
+    /// # Arguments
+    ///
+    /// * `guard` - The guard the hook was registered in.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(T)` if the hook is registered in `guard`.
+    /// - `Err(minhook_detours_rs::error::Error::NotCreated)` if the hook was removed meanwhile.
+    pub fn trampoline(&self, guard: &DetourGuard<'_>) -> Result<T> {
+        guard
+            .hook_info(self.handle.target())
+            .ok_or(Error::NotCreated)?;
+
+        Ok(unsafe { T::from_ptr(self.handle.original() as *const ()) })
+    }
+
+    /// The underlying handle, e.g. for the untyped operations of the [`DetourGuard`].
+    pub fn handle(&self) -> HookHandle {
+        self.handle
+    }
+}
+
+impl<'a> DetourGuard<'a> {
+    /// Registers, and enables a hook for `target`, detouring it to `detour`, type-checked as with `retour`.
+    /// Refer to [`DetourGuard::create_and_enable_hook`] for further explaination.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The function to be hooked.
+    /// * `detour` - The place where the function will jump to, while hooked.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`GenericDetour::new`].
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(T)` with the original function if the hook was succesfully applied.
+    /// - `Err(minhook_detours_rs::error::Error)` if the operation failed.
+    pub unsafe fn hook_function<T, D>(&mut self, target: T, detour: D) -> Result<T>
+    where
+        T: Function + HookableWith<D>,
+        D: Function,
+    {
+        let original = self.create_and_enable_hook::<T>(
+            target.to_ptr() as *mut _,
+            detour.to_ptr() as *mut _,
+        )?;
+
+        Ok(*original)
+    }
+}
@@ -0,0 +1,19 @@
+use crate::detour::Args;
+
+/// What a detour declared through [`crate::filter_detour`] does with a call of the hooked function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookAction<R> {
+    /// Forward the arguments, as possibly modified, to the original function, returning what it returns.
+    CallOriginal,
+    /// Return the value right away, without calling the original function.
+    Return(R),
+}
+
+/// Hands the arguments to the filter of a [`crate::filter_detour`], on behalf of its shim.
+#[doc(hidden)]
+pub fn filter<T, R>(
+    args: &mut Args<T>,
+    filter: impl FnOnce(&mut Args<T>) -> HookAction<R>,
+) -> HookAction<R> {
+    filter(args)
+}
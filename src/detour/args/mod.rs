@@ -0,0 +1,105 @@
+/// The arguments of a call of the hooked function, as a tuple, see [`crate::intercept_detour`].
+///
+/// ```ignore
+/// // Clamp the third argument.
+/// args.set::<2>(args.get::<2>().min(16));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Args<T>(T);
+
+impl<T> Args<T> {
+    #[doc(hidden)]
+    pub fn new(args: T) -> Self {
+        Self(args)
+    }
+
+    /// The `N`th argument, counting from zero.
+    pub fn get<const N: usize>(&self) -> T::Type
+    where
+        T: Arg<N>,
+        T::Type: Copy,
+    {
+        *self.0.arg()
+    }
+
+    /// Mutable access to the `N`th argument, counting from zero.
+    pub fn get_mut<const N: usize>(&mut self) -> &mut T::Type
+    where
+        T: Arg<N>,
+    {
+        self.0.arg_mut()
+    }
+
+    /// Replaces the `N`th argument, counting from zero, with `value`.
+    pub fn set<const N: usize>(&mut self, value: T::Type)
+    where
+        T: Arg<N>,
+    {
+        *self.0.arg_mut() = value;
+    }
+
+    /// The arguments, as passed on to the original function.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// Access to the `N`th element of a tuple, implemented for tuples of up to 12 elements.
+pub trait Arg<const N: usize> {
+    type Type;
+
+    fn arg(&self) -> &Self::Type;
+
+    fn arg_mut(&mut self) -> &mut Self::Type;
+}
+
+macro_rules! impl_arg {
+    ($($index:tt => $ty:ident),+; $all:tt) => {
+        $(impl_arg!(@one $index $ty $all);)+
+    };
+    (@one $index:tt $ty:ident ($($all:ident),+)) => {
+        impl<$($all),+> Arg<$index> for ($($all,)+) {
+            type Type = $ty;
+
+            fn arg(&self) -> &$ty {
+                &self.$index
+            }
+
+            fn arg_mut(&mut self) -> &mut $ty {
+                &mut self.$index
+            }
+        }
+    };
+}
+
+impl_arg!(0 => A; (A));
+impl_arg!(0 => A, 1 => B; (A, B));
+impl_arg!(0 => A, 1 => B, 2 => C; (A, B, C));
+impl_arg!(0 => A, 1 => B, 2 => C, 3 => D; (A, B, C, D));
+impl_arg!(0 => A, 1 => B, 2 => C, 3 => D, 4 => E; (A, B, C, D, E));
+impl_arg!(0 => A, 1 => B, 2 => C, 3 => D, 4 => E, 5 => F; (A, B, C, D, E, F));
+impl_arg!(0 => A, 1 => B, 2 => C, 3 => D, 4 => E, 5 => F, 6 => G; (A, B, C, D, E, F, G));
+impl_arg!(0 => A, 1 => B, 2 => C, 3 => D, 4 => E, 5 => F, 6 => G, 7 => H; (A, B, C, D, E, F, G, H));
+impl_arg!(
+    0 => A, 1 => B, 2 => C, 3 => D, 4 => E, 5 => F, 6 => G, 7 => H, 8 => I;
+    (A, B, C, D, E, F, G, H, I)
+);
+impl_arg!(
+    0 => A, 1 => B, 2 => C, 3 => D, 4 => E, 5 => F, 6 => G, 7 => H, 8 => I, 9 => J;
+    (A, B, C, D, E, F, G, H, I, J)
+);
+impl_arg!(
+    0 => A, 1 => B, 2 => C, 3 => D, 4 => E, 5 => F, 6 => G, 7 => H, 8 => I, 9 => J, 10 => K;
+    (A, B, C, D, E, F, G, H, I, J, K)
+);
+impl_arg!(
+    0 => A, 1 => B, 2 => C, 3 => D, 4 => E, 5 => F, 6 => G, 7 => H, 8 => I, 9 => J, 10 => K,
+    11 => L;
+    (A, B, C, D, E, F, G, H, I, J, K, L)
+);
+
+/// Hands the arguments to the interceptor of a [`crate::intercept_detour`], on behalf of its shim.
+#[doc(hidden)]
+pub fn intercept<T>(args: &mut Args<T>, interceptor: impl FnOnce(&mut Args<T>)) {
+    interceptor(args);
+}
@@ -0,0 +1,59 @@
+use std::{any::Any, cell::Cell, ptr::null, sync::Arc};
+
+use crate::detour::snapshot::{Loaded, Snapshot};
+
+/// The user data attached to a [`crate::detour::StaticDetour`].
+pub(crate) type Context = Arc<dyn Any + Send + Sync>;
+
+/// The user data of a hook, read by its shim upon every call, and detached by the guard once the hook is
+/// removed.
+pub(crate) type ContextSlot = Snapshot<Context>;
+
+thread_local! {
+    // Points into the `ContextSlot` of the innermost detour, which keeps it alive for as long as it's current.
+    static CURRENT: Cell<*const Context> = const { Cell::new(null()) };
+}
+
+/// The user data attached to the hook whose detour the current thread is inside of, see
+/// [`crate::detour::StaticDetour::create_with_context`].
+///
+/// Inside of nested detours, e.g. when a detour calls another hooked function, the innermost one's.
+///
+/// # Returns
+///
+/// - `Some(Arc<T>)` if the hook has user data of type `T`.
+/// - `None` if the current thread isn't inside of a detour, or the hook has no user data, or of another type.
+pub fn current_hook_context<T: Any + Send + Sync>() -> Option<Arc<T>> {
+    let current = CURRENT.try_with(Cell::get).ok()?;
+    let context = unsafe { current.as_ref()? }.clone();
+
+    context.downcast().ok()
+}
+
+/// The user data of a detour being called on the current thread, until dropped.
+pub(crate) struct ContextScope<'s> {
+    _context: Loaded<'s, Context>,
+    previous: *const Context,
+}
+
+impl<'s> ContextScope<'s> {
+    /// Makes the user data in `slot` the current thread's, returning `None` if there's none, or the thread is
+    /// being torn down.
+    pub(crate) fn enter(slot: &'s ContextSlot) -> Option<Self> {
+        let context = slot.load()?;
+        let previous = CURRENT
+            .try_with(|current| current.replace(&*context))
+            .ok()?;
+
+        Some(Self {
+            _context: context,
+            previous,
+        })
+    }
+}
+
+impl Drop for ContextScope<'_> {
+    fn drop(&mut self) {
+        let _ = CURRENT.try_with(|current| current.set(self.previous));
+    }
+}
@@ -0,0 +1,782 @@
+//! Static detours.
+//!
+//! Responsible for generating the dispatch shim that the engine jumps to, in front of a detour, so that the
+//! crate can keep per-hook state without the detour having to take part in it. Declared through
+//! [`crate::static_detour`].
+//!
+//! Structs of function pointers, e.g. vtables, can be hooked as a whole through [`crate::hook_struct`].
+//!
+//! Detours that may end up calling the function they hook, e.g. of allocators, or locks, are declared through
+//! [`crate::non_reentrant_detour`] instead.
+//!
+//! Hooks shared by several components, e.g. plugins, are declared through [`crate::pipeline_detour`], which
+//! runs every subscriber in priority order before calling the original function once.
+//!
+//! Calls made from inside of [`bypass_hooks_for_current_thread`] skip the detours of the current thread.
+//!
+//! User data attached to a hook, e.g. instead of statics keyed by target, is retrieved from inside of its
+//! detour through [`current_hook_context`].
+//!
+//! Panics of detours unwind into the hooked code, unless the shims are told to abort through
+//! [`set_abort_on_panic`].
+
+#[cfg(feature = "timing")]
+use std::time::Instant;
+use std::{
+    any::Any,
+    fmt::Debug,
+    mem::{size_of, transmute_copy},
+    os::raw::c_void,
+    ptr::null_mut,
+    sync::{
+        Arc,
+        atomic::{AtomicPtr, Ordering},
+    },
+};
+
+#[cfg(feature = "stats")]
+use crate::guard::HookStats;
+use crate::{
+    error::{Error, Result},
+    guard::{DetourGuard, HookHandle},
+    logging,
+};
+
+mod action;
+mod args;
+mod context;
+mod on_drop;
+mod panic;
+mod pipeline;
+mod reentrancy;
+mod snapshot;
+mod thread_bypass;
+
+pub use action::{HookAction, filter};
+pub use args::{Arg, Args, intercept};
+pub use context::current_hook_context;
+use context::ContextScope;
+pub(crate) use context::ContextSlot;
+pub use on_drop::CallOriginalOnDrop;
+pub use panic::{DetourPanic, clear_abort_on_panic, dispatch, set_abort_on_panic};
+pub use pipeline::{PipelineDetour, Subscription};
+pub use reentrancy::{NonReentrantDetour, Outermost};
+pub(crate) use thread_bypass::bypass_detour_for_current_thread;
+pub use thread_bypass::{bypass_hooks_for_current_thread, is_detour_bypassed, is_thread_bypassed};
+
+/// A hook whose dispatch shim was generated by [`crate::static_detour`], where `T` is the function pointer type
+/// of the hooked function.
+#[derive(Debug)]
+pub struct StaticDetour<T> {
+    shim: T,
+    original: AtomicPtr<c_void>,
+    context: ContextSlot,
+    #[cfg(feature = "stats")]
+    stats: HookStats,
+}
+
+impl<T: Copy> StaticDetour<T> {
+    #[doc(hidden)]
+    pub const fn new(shim: T) -> Self {
+        Self {
+            shim,
+            original: AtomicPtr::new(null_mut()),
+            context: ContextSlot::new(),
+            #[cfg(feature = "stats")]
+            stats: HookStats::new(),
+        }
+    }
+
+    /// Registers a hook for `target` in `guard`, detouring it to the shim.
+    ///
+    /// Refer to [`DetourGuard::create_hook`] for further explaination.
+    ///
+    /// # Arguments
+    ///
+    /// * `guard` - The guard the hook is registered in.
+    /// * `target` - The function to be hooked.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(HookHandle)` if the hook was succesfully registered.
+    /// - `Err(minhook_detours_rs::error::Error)` if the operation failed.
+    pub fn create(&'static self, guard: &mut DetourGuard<'_>, target: *mut c_void) -> Result<HookHandle> {
+        let original = *guard.create_hook::<*mut c_void>(target, self.detour())?;
+        self.original.store(original, Ordering::Release);
+        guard.set_thread_bypassable(target);
+        guard.attach_context(target, &self.context);
+
+        #[cfg(feature = "stats")]
+        guard.attach_stats(target, &self.stats);
+
+        // We succesfully registered the hook!
+        guard.handle(target).ok_or(Error::NotCreated)
+    }
+
+    /// Registers a hook for `symbol` of `library` in `guard`, detouring it to the shim.
+    ///
+    /// Refer to [`DetourGuard::create_hook_in_library`] for further explaination.
+    #[cfg(feature = "libloading")]
+    pub fn create_in_library(
+        &'static self,
+        guard: &mut DetourGuard<'_>,
+        library: &libloading::Library,
+        symbol: &str,
+    ) -> Result<HookHandle> {
+        let (target, original) =
+            guard.create_hook_in_library::<*mut c_void>(library, symbol, self.detour())?;
+        self.original.store(*original, Ordering::Release);
+        guard.set_thread_bypassable(target);
+        guard.attach_context(target, &self.context);
+
+        #[cfg(feature = "stats")]
+        guard.attach_stats(target, &self.stats);
+
+        // We succesfully registered the hook!
+        guard.handle(target).ok_or(Error::NotCreated)
+    }
+
+    /// Registers a hook for `target` in `guard`, like [`StaticDetour::create`], attaching `context` to it.
+    ///
+    /// The detour retrieves `context` through [`current_hook_context`]. Raw pointers, e.g. a `*mut c_void`,
+    /// must be wrapped in a type implementing [`Send`], and [`Sync`] first.
+    ///
+    /// # Arguments
+    ///
+    /// * `guard` - The guard the hook is registered in.
+    /// * `target` - The function to be hooked.
+    /// * `context` - The user data, replacing any attached before.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(HookHandle)` if the hook was succesfully registered.
+    /// - `Err(minhook_detours_rs::error::Error)` if the operation failed, in which case `context` isn't
+    ///   attached.
+    pub fn create_with_context<C: Any + Send + Sync>(
+        &'static self,
+        guard: &mut DetourGuard<'_>,
+        target: *mut c_void,
+        context: Arc<C>,
+    ) -> Result<HookHandle> {
+        let handle = self.create(guard, target)?;
+        self.set_context(context);
+
+        // We succesfully registered the hook!
+        Ok(handle)
+    }
+
+    /// Attach `context` to the hook, replacing any attached before, see [`StaticDetour::create_with_context`].
+    ///
+    /// The user data belongs to the hook currently created through the [`StaticDetour`], and is detached once
+    /// the hook is removed, so that a hook created later on doesn't inherit it.
+    pub fn set_context<C: Any + Send + Sync>(&self, context: Arc<C>) {
+        self.context.store(Some(context));
+    }
+
+    /// Detach the user data from the hook.
+    pub fn clear_context(&self) {
+        self.context.store(None);
+    }
+
+    /// The user data attached to the hook, if any, and of type `C`.
+    pub fn context<C: Any + Send + Sync>(&self) -> Option<Arc<C>> {
+        (*self.context.load()?).clone().downcast().ok()
+    }
+
+    /// The shim the target jumps to, while hooked.
+    pub fn detour(&self) -> *mut c_void {
+        Self::to_ptr(self.shim)
+    }
+
+    /// The original function, as filled in by the hooking engine.
+    ///
+    /// # Panics
+    ///
+    /// If the hook wasn't created through [`StaticDetour::create`] yet.
+    pub fn original(&self) -> T {
+        let original = self.original.load(Ordering::Acquire);
+        assert!(!original.is_null(), "StaticDetour used before being created");
+
+        unsafe { transmute_copy(&original) }
+    }
+
+    /// Call the original function through `call`, accounting for the time spent inside of it with the
+    /// `timing` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `call` - Receives the original function, see [`StaticDetour::original`].
+    pub fn call_original<R>(&self, call: impl FnOnce(T) -> R) -> R {
+        #[cfg(feature = "timing")]
+        let start = Instant::now();
+
+        let result = call(self.original());
+
+        #[cfg(feature = "timing")]
+        self.stats.record_original(start.elapsed());
+
+        result
+    }
+
+    /// The live counters of the hook.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> &HookStats {
+        &self.stats
+    }
+
+    /// Called by the shim upon every call of the hooked function, until the returned value is dropped.
+    #[doc(hidden)]
+    pub fn enter(&self) -> Entered<'_, T> {
+        #[cfg(feature = "stats")]
+        self.stats.record_call();
+
+        Entered {
+            detour: self,
+            _context: ContextScope::enter(&self.context),
+            #[cfg(feature = "timing")]
+            start: Instant::now(),
+        }
+    }
+
+    fn to_ptr(value: T) -> *mut c_void {
+        assert_eq!(size_of::<T>(), size_of::<*mut c_void>(), "T must be a function pointer");
+
+        unsafe { transmute_copy(&value) }
+    }
+}
+
+/// Whether calls traced by [`crate::trace_detour`] would be logged at all.
+#[doc(hidden)]
+pub fn is_tracing() -> bool {
+    logging::debug_enabled()
+}
+
+/// Logs a call of `name` with its `arguments`, on behalf of [`crate::trace_detour`].
+#[doc(hidden)]
+pub fn trace_call(name: &str, arguments: &[(&str, &dyn Debug)]) {
+    let arguments = arguments
+        .iter()
+        .map(|(name, value)| format!("{name} = {value:?}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    logging::debug!("{name}({arguments})");
+}
+
+/// Logs the value `name` returned, on behalf of [`crate::trace_detour`].
+#[doc(hidden)]
+pub fn trace_return(name: &str, value: &dyn Debug) {
+    logging::debug!("{name} returned {value:?}");
+}
+
+/// A call of the detour, in progress.
+#[doc(hidden)]
+pub struct Entered<'d, T> {
+    #[cfg_attr(not(feature = "timing"), allow(dead_code))]
+    detour: &'d StaticDetour<T>,
+    _context: Option<ContextScope<'d>>,
+    #[cfg(feature = "timing")]
+    start: Instant,
+}
+
+#[cfg(feature = "timing")]
+impl<'d, T> Drop for Entered<'d, T> {
+    fn drop(&mut self) {
+        self.detour.stats.record_detour(self.start.elapsed());
+    }
+}
+
+/// Declare a [`StaticDetour`], generating the dispatch shim that forwards the arguments to `detour`.
+///
+/// Arguments are named, so that the shim can forward them. The detour may call the original function through
+/// the declared static, preferably with [`StaticDetour::call_original`] so that its duration is accounted for.
+///
+/// ```ignore
+/// static_detour! {
+///     static ADD_TWO: fn(x: i32, y: i32) -> i64 = |x, y| ADD_TWO.original()(x, y) * 2;
+/// }
+///
+/// let handle = ADD_TWO.create(&mut guard, add_two as _)?;
+/// ```
+#[macro_export]
+macro_rules! static_detour {
+    ($(#[$attr:meta])* $vis:vis static $($item:tt)*) => {
+        $crate::static_detour!(@abi static_detour $(#[$attr])* $vis static $($item)*);
+    };
+    // Shared by every detour macro: splits the qualifiers of the declared signature off, and hands the rest to
+    // the `@emit` arm of `$macro`.
+    (@abi $macro:ident $(#[$attr:meta])* $vis:vis static $name:ident: unsafe extern $abi:literal fn($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)? $(= $detour:expr)?;) => {
+        $crate::$macro!(@emit [unsafe extern $abi] $(#[$attr])* $vis static $name($($arg: $ty),*) $(-> $ret)? $(= $detour)?);
+    };
+    (@abi $macro:ident $(#[$attr:meta])* $vis:vis static $name:ident: extern $abi:literal fn($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)? $(= $detour:expr)?;) => {
+        $crate::$macro!(@emit [extern $abi] $(#[$attr])* $vis static $name($($arg: $ty),*) $(-> $ret)? $(= $detour)?);
+    };
+    (@abi $macro:ident $(#[$attr:meta])* $vis:vis static $name:ident: unsafe fn($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)? $(= $detour:expr)?;) => {
+        $crate::$macro!(@emit [unsafe] $(#[$attr])* $vis static $name($($arg: $ty),*) $(-> $ret)? $(= $detour)?);
+    };
+    (@abi $macro:ident $(#[$attr:meta])* $vis:vis static $name:ident: fn($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)? $(= $detour:expr)?;) => {
+        $crate::$macro!(@emit [] $(#[$attr])* $vis static $name($($arg: $ty),*) $(-> $ret)? $(= $detour)?);
+    };
+    (@emit [$($qual:tt)*] $(#[$attr:meta])* $vis:vis static $name:ident($($arg:ident: $ty:ty),*) $(-> $ret:ty)? = $detour:expr) => {
+        $(#[$attr])*
+        $vis static $name: $crate::detour::StaticDetour<$($qual)* fn($($ty),*) $(-> $ret)?> = {
+            $($qual)* fn shim($($arg: $ty),*) $(-> $ret)? {
+                #[allow(unused_macros)]
+                macro_rules! forward_to_original {
+                    () => {
+                        $name.call_original(|original| {
+                            #[allow(unused_unsafe)]
+                            unsafe { original($($arg),*) }
+                        })
+                    };
+                }
+
+                if $crate::detour::is_detour_bypassed($name.detour()) {
+                    #[allow(unused_unsafe)]
+                    return unsafe { $name.original()($($arg),*) };
+                }
+
+                let _entered = $name.enter();
+
+                $crate::detour::dispatch(stringify!($name), move || {
+                    #[allow(unused_unsafe)]
+                    unsafe { ($detour)($($arg),*) }
+                })
+            }
+
+            $crate::detour::StaticDetour::new(shim)
+        };
+    };
+}
+
+/// Declare a [`StaticDetour`] that only traces calls, logging the arguments, and the returned value through
+/// the crate's logging sink, and forwarding to the original function.
+///
+/// Every argument, and the returned value must implement [`Debug`].
+///
+/// ```ignore
+/// trace_detour! {
+///     static GET_TICK_COUNT: unsafe extern "system" fn() -> u32;
+/// }
+///
+/// GET_TICK_COUNT.create(&mut guard, Target::export("kernel32.dll", "GetTickCount").resolve()?)?;
+/// ```
+#[macro_export]
+macro_rules! trace_detour {
+    ($(#[$attr:meta])* $vis:vis static $($item:tt)*) => {
+        $crate::static_detour!(@abi trace_detour $(#[$attr])* $vis static $($item)*);
+    };
+    (@emit [$($qual:tt)*] $(#[$attr:meta])* $vis:vis static $name:ident($($arg:ident: $ty:ty),*) $(-> $ret:ty)?) => {
+        $crate::static_detour!(@emit [$($qual)*] $(#[$attr])* $vis static $name($($arg: $ty),*) $(-> $ret)? = $crate::trace_detour!(@detour $name($($arg: $ty),*)));
+    };
+    (@detour $name:ident($($arg:ident: $ty:ty),*)) => {
+        |$($arg: $ty),*| {
+            // Nothing is formatted unless it is going to be logged.
+            let tracing = $crate::detour::is_tracing();
+
+            if tracing {
+                $crate::detour::trace_call(
+                    stringify!($name),
+                    &[$((stringify!($arg), &$arg as &dyn ::core::fmt::Debug)),*],
+                );
+            }
+
+            #[allow(unused_unsafe)]
+            let value = $name.call_original(|original| unsafe { original($($arg),*) });
+
+            if tracing {
+                $crate::detour::trace_return(stringify!($name), &value);
+            }
+            value
+        }
+    };
+}
+
+/// Declare a [`StaticDetour`] that hands the arguments to an interceptor as [`Args`], then forwards them,
+/// as modified, to the original function, so that changing one argument doesn't take spelling out a whole
+/// detour.
+///
+/// ```ignore
+/// intercept_detour! {
+///     static WRITE_FILE: unsafe extern "system" fn(
+///         file: HANDLE, buffer: *const c_void, length: u32, written: *mut u32, overlapped: *mut OVERLAPPED,
+///     ) -> BOOL = |args| args.set::<2>(args.get::<2>().min(4096));
+/// }
+/// ```
+#[macro_export]
+macro_rules! intercept_detour {
+    ($(#[$attr:meta])* $vis:vis static $($item:tt)*) => {
+        $crate::static_detour!(@abi intercept_detour $(#[$attr])* $vis static $($item)*);
+    };
+    (@emit [$($qual:tt)*] $(#[$attr:meta])* $vis:vis static $name:ident($($arg:ident: $ty:ty),*) $(-> $ret:ty)? = $interceptor:expr) => {
+        $crate::static_detour!(@emit [$($qual)*] $(#[$attr])* $vis static $name($($arg: $ty),*) $(-> $ret)? = $crate::intercept_detour!(@detour $name($($arg: $ty),*) = $interceptor));
+    };
+    (@detour $name:ident($($arg:ident: $ty:ty),*) = $interceptor:expr) => {
+        |$($arg: $ty),*| {
+            let mut args = $crate::detour::Args::new(($($arg,)*));
+            $crate::detour::intercept(&mut args, $interceptor);
+            let ($($arg,)*) = args.into_inner();
+
+            #[allow(unused_unsafe)]
+            $name.call_original(|original| unsafe { original($($arg),*) })
+        }
+    };
+}
+
+/// Declare a [`StaticDetour`] whose filter decides what happens to every call through a [`HookAction`],
+/// either forwarding the arguments, as possibly modified through [`Args`], to the original function, or
+/// returning a value of its own.
+///
+/// ```ignore
+/// filter_detour! {
+///     static DELETE_FILE: unsafe extern "system" fn(name: LPCWSTR) -> BOOL = |args| {
+///         if is_protected(args.get::<0>()) {
+///             return HookAction::Return(FALSE);
+///         }
+///
+///         HookAction::CallOriginal
+///     };
+/// }
+/// ```
+#[macro_export]
+macro_rules! filter_detour {
+    ($(#[$attr:meta])* $vis:vis static $($item:tt)*) => {
+        $crate::static_detour!(@abi filter_detour $(#[$attr])* $vis static $($item)*);
+    };
+    (@emit [$($qual:tt)*] $(#[$attr:meta])* $vis:vis static $name:ident($($arg:ident: $ty:ty),*) $(-> $ret:ty)? = $filter:expr) => {
+        $crate::static_detour!(@emit [$($qual)*] $(#[$attr])* $vis static $name($($arg: $ty),*) $(-> $ret)? = $crate::filter_detour!(@detour $name($($arg: $ty),*) = $filter));
+    };
+    (@detour $name:ident($($arg:ident: $ty:ty),*) = $filter:expr) => {
+        |$($arg: $ty),*| {
+            let mut args = $crate::detour::Args::new(($($arg,)*));
+
+            match $crate::detour::filter(&mut args, $filter) {
+                $crate::detour::HookAction::Return(value) => value,
+                $crate::detour::HookAction::CallOriginal => {
+                    let ($($arg,)*) = args.into_inner();
+
+                    #[allow(unused_unsafe)]
+                    $name.call_original(|original| unsafe { original($($arg),*) })
+                }
+            }
+        }
+    };
+}
+
+/// Call the original function with the arguments named in the declaration of the enclosing
+/// [`crate::static_detour`], or [`crate::non_reentrant_detour`], through [`StaticDetour::call_original`].
+///
+/// Only usable inside of the detour expression of either, where it's defined for the signature at hand. A
+/// detour closure naming its parameters like the declaration forwards its own bindings, e.g. after
+/// reassigning them.
+///
+/// ```ignore
+/// static_detour! {
+///     static CREATE_FILE: unsafe extern "system" fn(
+///         name: LPCWSTR, access: u32, share: u32, security: *mut c_void,
+///         disposition: u32, flags: u32, template: HANDLE,
+///     ) -> HANDLE = |_, _, _, _, _, _, _| {
+///         log::trace!("CreateFileW");
+///         forward_to_original!()
+///     };
+/// }
+/// ```
+#[macro_export]
+macro_rules! forward_to_original {
+    () => {
+        ::std::compile_error!(
+            "forward_to_original! is only usable inside of static_detour!, or non_reentrant_detour!"
+        )
+    };
+}
+
+/// Declare a [`NonReentrantDetour`], like [`crate::static_detour`], except that calls made while the current
+/// thread is already inside of the detour go straight to the original function.
+///
+/// Required when the detour may end up calling the hooked function, e.g. when hooking allocation, logging,
+/// or synchronization primitives.
+///
+/// ```ignore
+/// non_reentrant_detour! {
+///     static HEAP_ALLOC: unsafe extern "system" fn(heap: HANDLE, flags: u32, size: usize) -> *mut c_void =
+///         |heap, flags, size| {
+///             // Logging allocates, which goes to the original function.
+///             log::trace!("HeapAlloc({size})");
+///             HEAP_ALLOC.call_original(|original| unsafe { original(heap, flags, size) })
+///         };
+/// }
+/// ```
+#[macro_export]
+macro_rules! non_reentrant_detour {
+    ($(#[$attr:meta])* $vis:vis static $($item:tt)*) => {
+        $crate::static_detour!(@abi non_reentrant_detour $(#[$attr])* $vis static $($item)*);
+    };
+    (@emit [$($qual:tt)*] $(#[$attr:meta])* $vis:vis static $name:ident($($arg:ident: $ty:ty),*) $(-> $ret:ty)? = $detour:expr) => {
+        $(#[$attr])*
+        $vis static $name: $crate::detour::NonReentrantDetour<$($qual)* fn($($ty),*) $(-> $ret)?> = {
+            ::std::thread_local! {
+                static ENTERED: ::std::cell::Cell<bool> = const { ::std::cell::Cell::new(false) };
+            }
+
+            $($qual)* fn shim($($arg: $ty),*) $(-> $ret)? {
+                #[allow(unused_macros)]
+                macro_rules! forward_to_original {
+                    () => {
+                        $name.call_original(|original| {
+                            #[allow(unused_unsafe)]
+                            unsafe { original($($arg),*) }
+                        })
+                    };
+                }
+
+                if $crate::detour::is_detour_bypassed($name.detour()) {
+                    #[allow(unused_unsafe)]
+                    return unsafe { $name.original()($($arg),*) };
+                }
+
+                let Some(_outermost) = $name.enter_once() else {
+                    #[allow(unused_unsafe)]
+                    return unsafe { $name.original()($($arg),*) };
+                };
+                let _entered = $name.enter();
+
+                $crate::detour::dispatch(stringify!($name), move || {
+                    #[allow(unused_unsafe)]
+                    unsafe { ($detour)($($arg),*) }
+                })
+            }
+
+            $crate::detour::NonReentrantDetour::new(shim, &ENTERED)
+        };
+    };
+}
+
+/// Declare a [`PipelineDetour`], generating the dispatch shim that hands the arguments to every subscriber in
+/// priority order, then forwards them, as modified, to the original function exactly once.
+///
+/// ```ignore
+/// pipeline_detour! {
+///     pub static SEND: unsafe extern "system" fn(s: SOCKET, buf: *const c_char, len: c_int, flags: c_int) -> c_int;
+/// }
+///
+/// SEND.create(&mut guard, Target::export("ws2_32.dll", "send").resolve()?)?;
+/// SEND.inspect(0, |args| log_packet(args.get::<1>(), args.get::<2>()));
+/// ```
+#[macro_export]
+macro_rules! pipeline_detour {
+    ($(#[$attr:meta])* $vis:vis static $($item:tt)*) => {
+        $crate::static_detour!(@abi pipeline_detour $(#[$attr])* $vis static $($item)*);
+    };
+    (@emit [$($qual:tt)*] $(#[$attr:meta])* $vis:vis static $name:ident($($arg:ident: $ty:ty),*) $(-> $ret:ty)?) => {
+        $(#[$attr])*
+        $vis static $name: $crate::detour::PipelineDetour<$($qual)* fn($($ty),*) $(-> $ret)?, ($($ty,)*)> = {
+            $($qual)* fn shim($($arg: $ty),*) $(-> $ret)? {
+                if $crate::detour::is_detour_bypassed($name.detour()) {
+                    #[allow(unused_unsafe)]
+                    return unsafe { $name.original()($($arg),*) };
+                }
+
+                let _entered = $name.enter();
+
+                $crate::detour::dispatch(stringify!($name), move || {
+                    let mut args = $crate::detour::Args::new(($($arg,)*));
+                    $name.run(&mut args);
+                    let ($($arg,)*) = args.into_inner();
+
+                    #[allow(unused_unsafe)]
+                    $name.call_original(|original| unsafe { original($($arg),*) })
+                })
+            }
+
+            $crate::detour::PipelineDetour::new(shim)
+        };
+    };
+}
+
+/// Declare a struct of function pointers, e.g. mirroring a vtable, along with methods hooking every field at
+/// once, instead of one [`crate::guard::DetourGuard::create_hook`] per method.
+///
+/// Instances hold either targets, detours, or originals: `create_hooks` hooks every field of the targets to
+/// the matching field of the detours, and returns the originals. If creating any of them fails, the ones
+/// already created are removed.
+///
+/// ```ignore
+/// hook_struct! {
+///     #[repr(C)]
+///     pub struct DeviceVtable {
+///         pub open: unsafe extern "system" fn(u32) -> u32,
+///         pub close: unsafe extern "system" fn(u32),
+///     }
+/// }
+///
+/// let originals = targets.create_hooks(&mut guard, &DeviceVtable { open: open_hook, close: close_hook })?;
+/// targets.enable_hooks(&mut guard)?;
+/// ```
+#[macro_export]
+macro_rules! hook_struct {
+    ($(#[$attr:meta])* $vis:vis struct $name:ident { $($(#[$field_attr:meta])* $field_vis:vis $field:ident: $ty:ty),* $(,)? }) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy)]
+        $vis struct $name {
+            $($(#[$field_attr])* $field_vis $field: $ty),*
+        }
+
+        #[allow(dead_code)]
+        impl $name {
+            /// Collects the hooked functions, in declaration order.
+            $vis fn targets(&self) -> ::std::vec::Vec<*mut ::std::os::raw::c_void> {
+                ::std::vec![$(self.$field as *mut ::std::os::raw::c_void),*]
+            }
+
+            /// Hooks every field of `self` to the matching field of `detours`, returning the originals.
+            $vis fn create_hooks(
+                &self,
+                guard: &mut $crate::guard::DetourGuard<'_>,
+                detours: &Self,
+            ) -> $crate::error::Result<Self> {
+                let mut created = ::std::vec::Vec::new();
+
+                let originals = (|| -> $crate::error::Result<Self> {
+                    Ok(Self {
+                        $($field: {
+                            let original = *guard.create_hook::<$ty>(
+                                self.$field as *mut ::std::os::raw::c_void,
+                                detours.$field as *mut ::std::os::raw::c_void,
+                            )?;
+                            created.push(self.$field as *mut ::std::os::raw::c_void);
+                            original
+                        }),*
+                    })
+                })();
+
+                if originals.is_err() {
+                    for target in created {
+                        let _ = guard.remove_hook(target);
+                    }
+                }
+
+                originals
+            }
+
+            /// Enables the hook of every field in a single transaction.
+            $vis fn enable_hooks(
+                &self,
+                guard: &mut $crate::guard::DetourGuard<'_>,
+            ) -> $crate::error::Result<()> {
+                guard.enable_hooks(&self.targets())
+            }
+
+            /// Disables the hook of every field in a single transaction.
+            $vis fn disable_hooks(
+                &self,
+                guard: &mut $crate::guard::DetourGuard<'_>,
+            ) -> $crate::error::Result<()> {
+                guard.disable_hooks(&self.targets())
+            }
+
+            /// Removes the hook of every field.
+            $vis fn remove_hooks(
+                &self,
+                guard: &mut $crate::guard::DetourGuard<'_>,
+            ) -> $crate::error::Result<()> {
+                for target in self.targets() {
+                    guard.remove_hook(target)?;
+                }
+
+                Ok(())
+            }
+        }
+    };
+}
+
+/// Declare a unit of hooks, each a [`StaticDetour`] along with its target, and a struct installing, enabling,
+/// disabling, and uninstalling them together, so that large projects can organize hooks by concern.
+///
+/// Installing creates the hooks in declaration order, removing the ones already created if any fails.
+/// Enabling, and disabling happen in a single transaction, and uninstalling removes the hooks latest first.
+///
+/// ```ignore
+/// hook_module! {
+///     pub name: Network,
+///     hooks: [
+///         SEND => send as _,
+///         RECV => Target::export("ws2_32.dll", "recv").resolve()?,
+///     ],
+/// }
+///
+/// let network = Network::install(&mut guard)?;
+/// network.enable(&mut guard)?;
+/// ```
+#[macro_export]
+macro_rules! hook_module {
+    ($(#[$attr:meta])* $vis:vis name: $name:ident, hooks: [$($detour:ident => $target:expr),* $(,)?] $(,)?) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone)]
+        $vis struct $name {
+            handles: ::std::vec::Vec<$crate::guard::HookHandle>,
+        }
+
+        #[allow(dead_code)]
+        impl $name {
+            /// Creates every hook of the unit in `guard`, disabled.
+            $vis fn install(
+                guard: &mut $crate::guard::DetourGuard<'_>,
+            ) -> $crate::error::Result<Self> {
+                let mut handles = ::std::vec::Vec::new();
+
+                let installed = (|| -> $crate::error::Result<()> {
+                    $(handles.push($detour.create(guard, $target)?);)*
+                    Ok(())
+                })();
+
+                if let Err(error) = installed {
+                    for handle in handles.iter().rev() {
+                        let _ = guard.remove_hook(handle.target());
+                    }
+
+                    return Err(error);
+                }
+
+                Ok(Self { handles })
+            }
+
+            /// The handles of the hooks, in declaration order.
+            $vis fn handles(&self) -> &[$crate::guard::HookHandle] {
+                &self.handles
+            }
+
+            /// Collects the hooked functions, in declaration order.
+            $vis fn targets(&self) -> ::std::vec::Vec<*mut ::std::os::raw::c_void> {
+                self.handles.iter().map(|handle| handle.target()).collect()
+            }
+
+            /// Enables every hook of the unit in a single transaction.
+            $vis fn enable(
+                &self,
+                guard: &mut $crate::guard::DetourGuard<'_>,
+            ) -> $crate::error::Result<()> {
+                guard.enable_hooks(&self.targets())
+            }
+
+            /// Disables every hook of the unit in a single transaction.
+            $vis fn disable(
+                &self,
+                guard: &mut $crate::guard::DetourGuard<'_>,
+            ) -> $crate::error::Result<()> {
+                guard.disable_hooks(&self.targets())
+            }
+
+            /// Removes every hook of the unit, latest first.
+            $vis fn uninstall(
+                &self,
+                guard: &mut $crate::guard::DetourGuard<'_>,
+            ) -> $crate::error::Result<()> {
+                for target in self.targets().into_iter().rev() {
+                    guard.remove_hook(target)?;
+                }
+
+                Ok(())
+            }
+        }
+    };
+}
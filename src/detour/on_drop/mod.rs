@@ -0,0 +1,70 @@
+use std::marker::PhantomData;
+
+use crate::detour::StaticDetour;
+
+/// Calls the original function exactly once: either explicitly through [`CallOriginalOnDrop::call`], or once
+/// dropped, e.g. when the detour returns early, or panics, so that the call is never forgotten.
+///
+/// ```ignore
+/// let original = CLOSE_HANDLE.call_original_on_drop(move |close_handle| unsafe { close_handle(handle) });
+/// if !is_tracked(handle) {
+///     // Closed anyway, once `original` is dropped.
+///     return TRUE;
+/// }
+///
+/// untrack(handle);
+/// original.call()
+/// ```
+#[must_use = "dropping it right away calls the original function right away"]
+pub struct CallOriginalOnDrop<F: FnOnce() -> R, R> {
+    call: Option<F>,
+    _phantom_data: PhantomData<fn() -> R>,
+}
+
+impl<F: FnOnce() -> R, R> CallOriginalOnDrop<F, R> {
+    /// # Arguments
+    ///
+    /// * `call` - Calls the original function.
+    pub fn new(call: F) -> Self {
+        Self {
+            call: Some(call),
+            _phantom_data: PhantomData,
+        }
+    }
+
+    /// Calls the original function now, returning what it returned.
+    pub fn call(mut self) -> R {
+        // Only `call`, and `cancel` take it, and both consume the guard.
+        let call = self.call.take().unwrap();
+
+        call()
+    }
+
+    /// Never call the original function, e.g. as the detour replaces it on purpose.
+    pub fn cancel(mut self) {
+        self.call = None;
+    }
+}
+
+impl<F: FnOnce() -> R, R> Drop for CallOriginalOnDrop<F, R> {
+    fn drop(&mut self) {
+        if let Some(call) = self.call.take() {
+            let _ = call();
+        }
+    }
+}
+
+impl<T: Copy> StaticDetour<T> {
+    /// Calls the original function through `call` exactly once, see [`CallOriginalOnDrop`], accounting for
+    /// the time spent inside of it like [`StaticDetour::call_original`].
+    ///
+    /// # Arguments
+    ///
+    /// * `call` - Receives the original function, see [`StaticDetour::original`].
+    pub fn call_original_on_drop<R>(
+        &self,
+        call: impl FnOnce(T) -> R,
+    ) -> CallOriginalOnDrop<impl FnOnce() -> R, R> {
+        CallOriginalOnDrop::new(move || self.call_original(call))
+    }
+}
@@ -0,0 +1,67 @@
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+    process,
+    sync::RwLock,
+};
+
+/// Called before aborting the process, see [`set_abort_on_panic`].
+type PanicCallback = Box<dyn Fn(&DetourPanic<'_>) + Send + Sync>;
+
+static ABORT_ON_PANIC: RwLock<Option<PanicCallback>> = RwLock::new(None);
+
+/// A panic that escaped a detour, handed to the callback of [`set_abort_on_panic`].
+#[derive(Debug, Clone, Copy)]
+pub struct DetourPanic<'p> {
+    /// The name of the static declared through [`crate::static_detour`].
+    pub detour: &'static str,
+    /// The message the detour panicked with, if it was a string.
+    pub message: Option<&'p str>,
+}
+
+/// Make the dispatch shims generated by [`crate::static_detour`] abort the process when their detour panics,
+/// after calling `callback`, e.g. to write a crash report, instead of unwinding into the hooked code.
+///
+/// By default, panics unwind through the shim into the caller of the hooked function, or abort without any
+/// callback upon reaching an `extern` function, as no unwinding may cross it.
+///
+/// # Arguments
+///
+/// * `callback` - Called with the panic right before aborting.
+pub fn set_abort_on_panic(callback: impl Fn(&DetourPanic<'_>) + Send + Sync + 'static) {
+    *ABORT_ON_PANIC.write().unwrap_or_else(|error| error.into_inner()) = Some(Box::new(callback));
+}
+
+/// Let panics of detours unwind again, see [`set_abort_on_panic`].
+pub fn clear_abort_on_panic() {
+    *ABORT_ON_PANIC.write().unwrap_or_else(|error| error.into_inner()) = None;
+}
+
+/// Runs the detour `name` through `call` on behalf of its shim, applying [`set_abort_on_panic`].
+#[doc(hidden)]
+pub fn dispatch<R>(name: &'static str, call: impl FnOnce() -> R) -> R {
+    match panic::catch_unwind(AssertUnwindSafe(call)) {
+        Ok(value) => value,
+        Err(payload) => {
+            let callback = ABORT_ON_PANIC.read().unwrap_or_else(|error| error.into_inner());
+            let Some(callback) = callback.as_ref() else {
+                drop(callback);
+                panic::resume_unwind(payload);
+            };
+
+            callback(&DetourPanic {
+                detour: name,
+                message: message(payload.as_ref()),
+            });
+            process::abort();
+        }
+    }
+}
+
+/// The message of a panic raised through `panic!`, or `expect`.
+fn message(payload: &(dyn Any + Send)) -> Option<&str> {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+}
@@ -0,0 +1,163 @@
+use std::{
+    fmt::{self, Debug, Formatter},
+    ops::Deref,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use crate::{
+    detour::{Args, StaticDetour, snapshot::Snapshot},
+    logging,
+};
+
+type Callback<A> = Arc<dyn Fn(&mut Args<A>) + Send + Sync>;
+
+/// A [`StaticDetour`] whose shim, generated by [`crate::pipeline_detour`], hands the arguments to every
+/// subscriber in priority order, then forwards them, as modified, to the original function exactly once.
+///
+/// Subscribers are kept in a copy-on-write list, so callbacks may subscribe, or unsubscribe without
+/// deadlocking, and the change applies from the next call on. Calls read the current list without locking.
+pub struct PipelineDetour<T, A> {
+    detour: StaticDetour<T>,
+    subscribers: Snapshot<Vec<Subscriber<A>>>,
+    next_id: AtomicU64,
+}
+
+/// A callback subscribed to a [`PipelineDetour`], removed through [`PipelineDetour::unsubscribe`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Subscription(u64);
+
+struct Subscriber<A> {
+    id: u64,
+    priority: i32,
+    callback: Callback<A>,
+}
+
+impl<A> Clone for Subscriber<A> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            priority: self.priority,
+            callback: self.callback.clone(),
+        }
+    }
+}
+
+impl<T: Copy, A> PipelineDetour<T, A> {
+    #[doc(hidden)]
+    pub const fn new(shim: T) -> Self {
+        Self {
+            detour: StaticDetour::new(shim),
+            subscribers: Snapshot::new(),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Subscribes `callback` to every call of the hooked function, which may modify the arguments before
+    /// they're handed to the next subscriber, and eventually to the original function.
+    ///
+    /// # Arguments
+    ///
+    /// * `priority` - Subscribers with a lower priority run first, and ones of equal priority run in the
+    ///   order they subscribed in.
+    /// * `callback` - Receives the arguments, see [`Args`].
+    pub fn subscribe(
+        &self,
+        priority: i32,
+        callback: impl Fn(&mut Args<A>) + Send + Sync + 'static,
+    ) -> Subscription {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+
+        self.update(|subscribers| {
+            let index = subscribers.partition_point(|subscriber| subscriber.priority <= priority);
+            subscribers.insert(
+                index,
+                Subscriber {
+                    id,
+                    priority,
+                    callback: Arc::new(callback),
+                },
+            );
+        });
+
+        // We succesfully subscribed!
+        logging::debug!("Subscribed #{id} with priority {priority}");
+        Subscription(id)
+    }
+
+    /// Subscribes `callback` to every call of the hooked function, only observing the arguments.
+    ///
+    /// Refer to [`PipelineDetour::subscribe`] for further explaination.
+    pub fn inspect(
+        &self,
+        priority: i32,
+        callback: impl Fn(&Args<A>) + Send + Sync + 'static,
+    ) -> Subscription {
+        self.subscribe(priority, move |args| callback(args))
+    }
+
+    /// Removes the callback of `subscription`.
+    ///
+    /// # Returns
+    ///
+    /// - `true` if the callback was succesfully removed.
+    /// - `false` if it was already removed.
+    pub fn unsubscribe(&self, subscription: Subscription) -> bool {
+        let mut removed = false;
+
+        self.update(|subscribers| {
+            let count = subscribers.len();
+            subscribers.retain(|subscriber| subscriber.id != subscription.0);
+            removed = subscribers.len() != count;
+        });
+
+        removed
+    }
+
+    /// The number of callbacks subscribed.
+    pub fn subscribers(&self) -> usize {
+        self.subscribers
+            .load()
+            .map_or(0, |subscribers| subscribers.len())
+    }
+
+    /// Called by the shim upon every call of the hooked function, before forwarding `args`.
+    #[doc(hidden)]
+    pub fn run(&self, args: &mut Args<A>) {
+        let Some(subscribers) = self.subscribers.load() else {
+            return;
+        };
+
+        for subscriber in subscribers.iter() {
+            (subscriber.callback)(args);
+        }
+    }
+
+    fn update(&self, change: impl FnOnce(&mut Vec<Subscriber<A>>)) {
+        self.subscribers.update(|subscribers| {
+            let mut list = subscribers.cloned().unwrap_or_default();
+            change(&mut list);
+
+            (!list.is_empty()).then_some(list)
+        });
+    }
+}
+
+impl<T: Debug, A> Debug for PipelineDetour<T, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PipelineDetour")
+            .field("detour", &self.detour)
+            .field("next_id", &self.next_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, A> Deref for PipelineDetour<T, A> {
+    type Target = StaticDetour<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.detour
+    }
+}
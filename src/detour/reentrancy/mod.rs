@@ -0,0 +1,63 @@
+use std::{cell::Cell, ops::Deref, thread::LocalKey};
+
+use crate::detour::StaticDetour;
+
+/// A [`StaticDetour`] whose shim, generated by [`crate::non_reentrant_detour`], calls the original function
+/// straight away when entered again on the same thread, e.g. when a detour of an allocator ends up allocating.
+///
+/// The flag is a `const`-initialized thread-local without destructor, so checking it neither allocates, nor
+/// takes locks. Calls made while the thread is being torn down go to the original function.
+#[derive(Debug)]
+pub struct NonReentrantDetour<T> {
+    detour: StaticDetour<T>,
+    entered: &'static LocalKey<Cell<bool>>,
+}
+
+impl<T: Copy> NonReentrantDetour<T> {
+    #[doc(hidden)]
+    pub const fn new(shim: T, entered: &'static LocalKey<Cell<bool>>) -> Self {
+        Self {
+            detour: StaticDetour::new(shim),
+            entered,
+        }
+    }
+
+    /// Whether the current thread is inside of the detour.
+    pub fn is_entered(&self) -> bool {
+        self.entered.try_with(Cell::get).unwrap_or(true)
+    }
+
+    /// Called by the shim upon every call of the hooked function, returning `None` if the current thread is
+    /// already inside of the detour.
+    #[doc(hidden)]
+    pub fn enter_once(&self) -> Option<Outermost> {
+        let outermost = self
+            .entered
+            .try_with(|entered| !entered.replace(true))
+            .unwrap_or(false);
+
+        outermost.then_some(Outermost {
+            entered: self.entered,
+        })
+    }
+}
+
+impl<T> Deref for NonReentrantDetour<T> {
+    type Target = StaticDetour<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.detour
+    }
+}
+
+/// The outermost call of a [`NonReentrantDetour`] on the current thread, in progress.
+#[doc(hidden)]
+pub struct Outermost {
+    entered: &'static LocalKey<Cell<bool>>,
+}
+
+impl Drop for Outermost {
+    fn drop(&mut self) {
+        let _ = self.entered.try_with(|entered| entered.set(false));
+    }
+}
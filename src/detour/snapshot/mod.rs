@@ -0,0 +1,121 @@
+use std::{
+    fmt::{self, Debug, Formatter},
+    ops::Deref,
+    ptr::null_mut,
+    sync::{
+        Mutex,
+        atomic::{AtomicPtr, AtomicUsize, Ordering},
+    },
+};
+
+/// A value read by shims upon every call, without locking, and replaced as a whole.
+///
+/// Replaced values are kept around until a later change finds no reader left, as a reader may still be
+/// holding one, and are dropped along with the [`Snapshot`] otherwise.
+pub(crate) struct Snapshot<T> {
+    current: AtomicPtr<T>,
+    readers: AtomicUsize,
+    retired: Mutex<Vec<Box<T>>>,
+}
+
+impl<T> Snapshot<T> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            current: AtomicPtr::new(null_mut()),
+            readers: AtomicUsize::new(0),
+            retired: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The current value, which stays alive until the returned one is dropped.
+    ///
+    /// Reading nothing costs a single atomic load.
+    pub(crate) fn load(&self) -> Option<Loaded<'_, T>> {
+        if self.current.load(Ordering::Relaxed).is_null() {
+            return None;
+        }
+
+        let reader = Reader::enter(&self.readers);
+        let value = unsafe { self.current.load(Ordering::SeqCst).as_ref()? };
+
+        Some(Loaded {
+            value,
+            _reader: reader,
+        })
+    }
+
+    /// Replaces the current value with the one `change` makes out of it, changes being serialized.
+    pub(crate) fn update(&self, change: impl FnOnce(Option<&T>) -> Option<T>) {
+        let mut retired = self
+            .retired
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let value = change(self.load().as_deref());
+        let value = value.map_or(null_mut(), |value| Box::into_raw(Box::new(value)));
+
+        let previous = self.current.swap(value, Ordering::SeqCst);
+        if !previous.is_null() {
+            retired.push(unsafe { Box::from_raw(previous) });
+        }
+
+        // Readers arriving from now on only see the new value, so once none is left, none of the retired ones
+        // is in use anymore.
+        if self.readers.load(Ordering::SeqCst) == 0 {
+            retired.clear();
+        }
+    }
+
+    /// Replaces the current value with `value`.
+    pub(crate) fn store(&self, value: Option<T>) {
+        self.update(|_| value);
+    }
+}
+
+impl<T> Drop for Snapshot<T> {
+    fn drop(&mut self) {
+        let current = *self.current.get_mut();
+        if !current.is_null() {
+            drop(unsafe { Box::from_raw(current) });
+        }
+    }
+}
+
+impl<T> Debug for Snapshot<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Snapshot")
+            .field("current", &self.current)
+            .field("readers", &self.readers)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The value of a [`Snapshot`], kept alive until dropped.
+pub(crate) struct Loaded<'s, T> {
+    value: &'s T,
+    _reader: Reader<'s>,
+}
+
+impl<T> Deref for Loaded<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+/// Counts a reader of a [`Snapshot`], until dropped, even if the reader panics.
+struct Reader<'r>(&'r AtomicUsize);
+
+impl<'r> Reader<'r> {
+    fn enter(readers: &'r AtomicUsize) -> Self {
+        readers.fetch_add(1, Ordering::SeqCst);
+        Self(readers)
+    }
+}
+
+impl Drop for Reader<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Release);
+    }
+}
@@ -0,0 +1,67 @@
+use std::{
+    cell::{Cell, RefCell},
+    os::raw::c_void,
+};
+
+thread_local! {
+    /// The depth of [`bypass_hooks_for_current_thread`] calls on the current thread.
+    static BYPASSED: Cell<usize> = const { Cell::new(0) };
+    /// The shims bypassed through [`bypass_detour_for_current_thread`] on the current thread, once per call.
+    static BYPASSED_DETOURS: RefCell<Vec<*mut c_void>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Runs `f` with every hook of the current thread bypassed, so that its calls reach the original functions,
+/// e.g. for a detour to log, or write files through hooked APIs, without toggling anything for other threads.
+///
+/// Only the dispatch shims generated by [`crate::static_detour`], [`crate::trace_detour`], and
+/// [`crate::non_reentrant_detour`] check for it, hooks with detours of their own are unaffected. Calls may be
+/// nested.
+///
+/// # Arguments
+///
+/// * `f` - Runs with the hooks bypassed.
+pub fn bypass_hooks_for_current_thread<R>(f: impl FnOnce() -> R) -> R {
+    struct Restore;
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let _ = BYPASSED.try_with(|depth| depth.set(depth.get() - 1));
+        }
+    }
+
+    BYPASSED.with(|depth| depth.set(depth.get() + 1));
+    let _restore = Restore;
+
+    f()
+}
+
+/// Whether the current thread is inside of [`bypass_hooks_for_current_thread`].
+pub fn is_thread_bypassed() -> bool {
+    BYPASSED.try_with(|depth| depth.get() != 0).unwrap_or(false)
+}
+
+/// Runs `f` with the hooks detouring to the shim `detour` bypassed on the current thread only, on behalf of
+/// [`crate::guard::DetourGuard::with_hook_disabled`].
+pub(crate) fn bypass_detour_for_current_thread<R>(detour: *mut c_void, f: impl FnOnce() -> R) -> R {
+    struct Restore;
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let _ = BYPASSED_DETOURS.try_with(|detours| detours.borrow_mut().pop());
+        }
+    }
+
+    BYPASSED_DETOURS.with(|detours| detours.borrow_mut().push(detour));
+    let _restore = Restore;
+
+    f()
+}
+
+/// Whether calls of the shim `detour` should go straight to the original function on the current thread.
+#[doc(hidden)]
+pub fn is_detour_bypassed(detour: *mut c_void) -> bool {
+    is_thread_bypassed()
+        || BYPASSED_DETOURS
+            .try_with(|detours| detours.borrow().contains(&detour))
+            .unwrap_or(false)
+}
@@ -0,0 +1,148 @@
+//! Engine.
+//!
+//! Responsible for dispatching the operations of a [`crate::guard::DetourGuard`] to the backend it was
+//! constructed with: MinHook itself, or a mock with the `testing` feature.
+//!
+//! MinHook is the SlimDetours-based fork bundled by `minhook-detours-sys`. Classic MinHook, or Microsoft
+//! Detours would become variants of [`Engine`] behind the same [`crate::guard::DetourGuard`] surface once
+//! bindings exist.
+
+use minhook_detours_sys::{
+    MH_ApplyQueued, MH_CreateHook, MH_DisableHook, MH_EnableHook, MH_Initialize, MH_OK,
+    MH_QueueDisableHook, MH_QueueEnableHook, MH_RemoveHook, MH_STATUS, MH_SetThreadFreezeMethod,
+    MH_Uninitialize,
+};
+use std::{cell::Cell, os::raw::c_void};
+use winapi::um::errhandlingapi::GetLastError;
+
+use crate::guard::ThreadFreezeMethod;
+#[cfg(feature = "testing")]
+use crate::testing::MockEngine;
+
+/// The backend of a [`crate::guard::DetourGuard`], mirroring the MinHook API.
+///
+/// A null `target` stands for every hook, as with `MH_ALL_HOOKS`.
+#[derive(Debug, Clone, Default)]
+pub(crate) enum Engine {
+    #[default]
+    MinHook,
+    #[cfg(feature = "testing")]
+    Mock(MockEngine),
+}
+
+thread_local! {
+    /// The value of `GetLastError` right after the latest failed operation of the current thread, before
+    /// anything else, e.g. resuming frozen threads, or logging, gets the chance to overwrite it.
+    static LAST_ERROR: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Take the value of `GetLastError` captured when the latest operation of the current thread failed, if any.
+pub(crate) fn take_last_error() -> u32 {
+    LAST_ERROR.take()
+}
+
+impl Engine {
+    /// Whether operations patch the code of the current process.
+    pub(crate) fn patches_code(&self) -> bool {
+        matches!(self, Self::MinHook)
+    }
+
+    /// Capture `GetLastError` right away if `status` is a failure, see [`take_last_error`].
+    fn capture(&self, status: MH_STATUS) -> MH_STATUS {
+        if status != MH_OK {
+            // A mock engine never calls into the system, so whatever is there belongs to someone else.
+            let last_error = if self.patches_code() { unsafe { GetLastError() } } else { 0 };
+            LAST_ERROR.set(last_error);
+        }
+
+        status
+    }
+
+    pub(crate) fn initialize(&self) -> MH_STATUS {
+        self.capture(match self {
+            Self::MinHook => unsafe { MH_Initialize() },
+            #[cfg(feature = "testing")]
+            Self::Mock(mock) => mock.initialize(),
+        })
+    }
+
+    pub(crate) fn uninitialize(&self) -> MH_STATUS {
+        self.capture(match self {
+            Self::MinHook => unsafe { MH_Uninitialize() },
+            #[cfg(feature = "testing")]
+            Self::Mock(mock) => mock.uninitialize(),
+        })
+    }
+
+    pub(crate) fn set_thread_freeze_method(&self, method: ThreadFreezeMethod) -> MH_STATUS {
+        self.capture(match self {
+            Self::MinHook => unsafe { MH_SetThreadFreezeMethod(method.into()) },
+            #[cfg(feature = "testing")]
+            Self::Mock(mock) => mock.set_thread_freeze_method(method),
+        })
+    }
+
+    /// # Safety
+    ///
+    /// `original` must be valid for writes, and outlive the hook.
+    pub(crate) unsafe fn create_hook(
+        &self,
+        target: *mut c_void,
+        detour: *mut c_void,
+        original: *mut *mut c_void,
+    ) -> MH_STATUS {
+        self.capture(match self {
+            Self::MinHook => unsafe { MH_CreateHook(target as _, detour as _, original as _) },
+            #[cfg(feature = "testing")]
+            Self::Mock(mock) => unsafe { mock.create_hook(target, detour, original) },
+        })
+    }
+
+    pub(crate) fn remove_hook(&self, target: *mut c_void) -> MH_STATUS {
+        self.capture(match self {
+            Self::MinHook => unsafe { MH_RemoveHook(target) },
+            #[cfg(feature = "testing")]
+            Self::Mock(mock) => mock.remove_hook(target),
+        })
+    }
+
+    pub(crate) fn enable_hook(&self, target: *mut c_void) -> MH_STATUS {
+        self.capture(match self {
+            Self::MinHook => unsafe { MH_EnableHook(target) },
+            #[cfg(feature = "testing")]
+            Self::Mock(mock) => mock.enable_hook(target),
+        })
+    }
+
+    pub(crate) fn disable_hook(&self, target: *mut c_void) -> MH_STATUS {
+        self.capture(match self {
+            Self::MinHook => unsafe { MH_DisableHook(target) },
+            #[cfg(feature = "testing")]
+            Self::Mock(mock) => mock.disable_hook(target),
+        })
+    }
+
+    pub(crate) fn queue_enable_hook(&self, target: *mut c_void) -> MH_STATUS {
+        self.capture(match self {
+            Self::MinHook => unsafe { MH_QueueEnableHook(target) },
+            #[cfg(feature = "testing")]
+            Self::Mock(mock) => mock.queue_enable_hook(target),
+        })
+    }
+
+    pub(crate) fn queue_disable_hook(&self, target: *mut c_void) -> MH_STATUS {
+        self.capture(match self {
+            Self::MinHook => unsafe { MH_QueueDisableHook(target) },
+            #[cfg(feature = "testing")]
+            Self::Mock(mock) => mock.queue_disable_hook(target),
+        })
+    }
+
+    pub(crate) fn apply_queued(&self) -> MH_STATUS {
+        self.capture(match self {
+            Self::MinHook => unsafe { MH_ApplyQueued() },
+            #[cfg(feature = "testing")]
+            Self::Mock(mock) => mock.apply_queued(),
+        })
+    }
+}
@@ -0,0 +1,129 @@
+use std::fmt::{self, Display, Formatter};
+
+#[cfg(not(feature = "minimal"))]
+use crate::logging::Address;
+
+/// The operation of the hooking engine an [`crate::error::Error`] originates from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Initialize,
+    Uninitialize,
+    SetThreadFreezeMethod,
+    CreateHook,
+    RemoveHook,
+    EnableHook,
+    DisableHook,
+    QueueEnableHook,
+    QueueDisableHook,
+    ApplyQueued,
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // Named after the C API, so that logs can be matched against MinHook's documentation.
+        let name = match self {
+            Self::Initialize => "MH_Initialize",
+            Self::Uninitialize => "MH_Uninitialize",
+            Self::SetThreadFreezeMethod => "MH_SetThreadFreezeMethod",
+            Self::CreateHook => "MH_CreateHook",
+            Self::RemoveHook => "MH_RemoveHook",
+            Self::EnableHook => "MH_EnableHook",
+            Self::DisableHook => "MH_DisableHook",
+            Self::QueueEnableHook => "MH_QueueEnableHook",
+            Self::QueueDisableHook => "MH_QueueDisableHook",
+            Self::ApplyQueued => "MH_ApplyQueued",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// Where, and while doing what an [`crate::error::Error`] happened.
+///
+/// Addresses are stored as integers, so that errors stay [`Send`], and [`Sync`]. Fields may be added, so
+/// contexts are built from [`ErrorContext::default`] outside of the crate.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ErrorContext {
+    /// The operation that failed.
+    pub operation: Option<Operation>,
+    /// The address of the target the operation was performed on.
+    pub target: Option<usize>,
+    /// Human-readable description of the target, e.g. `user32.dll!MessageBoxW`.
+    pub symbol: Option<String>,
+    /// The value of `GetLastError` right after the operation failed, unless it was `ERROR_SUCCESS`.
+    ///
+    /// Failures such as [`crate::error::Error::FailedAllocatingMemory`] are often caused by an underlying
+    /// Win32 error, e.g. `ERROR_ACCESS_DENIED`.
+    pub last_error: Option<u32>,
+    /// The hook already registered for the target, when the operation failed because of it, e.g. with
+    /// [`crate::error::Error::AlreadyCreated`].
+    pub existing: Option<ExistingHook>,
+}
+
+/// Bookkeeping of the hook a [`crate::guard::DetourGuard`] already has registered for a target.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExistingHook {
+    /// The address of the detour the target jumps to, while hooked.
+    pub detour: usize,
+    /// Whether the hook is enabled.
+    pub enabled: bool,
+    /// The name of the hook, see [`crate::guard::DetourGuard::set_hook_name`].
+    pub name: Option<String>,
+    /// The group of the hook, see [`crate::guard::DetourGuard::set_hook_group`].
+    pub group: Option<String>,
+}
+
+#[cfg(not(feature = "minimal"))]
+impl Display for ExistingHook {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("already hooked")?;
+
+        match (&self.name, &self.group) {
+            (Some(name), Some(group)) => write!(f, " as `{name}` of `{group}`")?,
+            (Some(name), None) => write!(f, " as `{name}`")?,
+            (None, Some(group)) => write!(f, " in `{group}`")?,
+            (None, None) => {}
+        }
+
+        let state = if self.enabled { "enabled" } else { "disabled" };
+        write!(f, ", detouring to {}, {state}", Address(self.detour as _))
+    }
+}
+
+impl ErrorContext {
+    /// Fill the fields of `self` that are missing, from `other`.
+    pub(crate) fn merge(&mut self, other: ErrorContext) {
+        self.operation = self.operation.or(other.operation);
+        self.target = self.target.or(other.target);
+        self.symbol = self.symbol.take().or(other.symbol);
+        self.last_error = self.last_error.or(other.last_error);
+        self.existing = self.existing.take().or(other.existing);
+    }
+}
+
+#[cfg(not(feature = "minimal"))]
+impl Display for ErrorContext {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.operation {
+            Some(operation) => write!(f, "{operation} failed")?,
+            None => f.write_str("Operation failed")?,
+        }
+
+        match (self.target, &self.symbol) {
+            (Some(target), Some(symbol)) => write!(f, " for {symbol} ({target:#x})")?,
+            (Some(target), None) => write!(f, " for {}", Address(target as _))?,
+            (None, Some(symbol)) => write!(f, " for {symbol}")?,
+            (None, None) => {}
+        }
+
+        if let Some(last_error) = self.last_error {
+            write!(f, " [last error: {last_error}]")?;
+        }
+
+        match &self.existing {
+            Some(existing) => write!(f, " ({existing})"),
+            None => Ok(()),
+        }
+    }
+}
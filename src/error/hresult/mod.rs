@@ -0,0 +1,63 @@
+use winapi::shared::{
+    ntdef::HRESULT,
+    winerror::{
+        E_FAIL, E_INVALIDARG, E_OUTOFMEMORY, E_POINTER, ERROR_ALREADY_EXISTS,
+        ERROR_ALREADY_INITIALIZED, ERROR_BAD_EXE_FORMAT, ERROR_BUSY, ERROR_INVALID_ADDRESS,
+        ERROR_INVALID_STATE, ERROR_MOD_NOT_FOUND, ERROR_NOT_FOUND, ERROR_NOT_SUPPORTED,
+        ERROR_POSSIBLE_DEADLOCK, ERROR_PROC_NOT_FOUND, HRESULT_FROM_WIN32,
+    },
+};
+
+use crate::error::Error;
+
+impl Error {
+    /// Map the error to the closest matching `HRESULT`, e.g. for propagating it out of a COM server.
+    pub fn to_hresult(&self) -> HRESULT {
+        let win32 = match self.root() {
+            Self::AlreadyInitialized => ERROR_ALREADY_INITIALIZED,
+            Self::NotInitialized | Self::Enabled | Self::Disabled | Self::Poisoned => {
+                ERROR_INVALID_STATE
+            }
+            Self::UnableToInitialize => ERROR_BUSY,
+            Self::LoaderLock => ERROR_POSSIBLE_DEADLOCK,
+            Self::AlreadyCreated => ERROR_ALREADY_EXISTS,
+            Self::NotCreated
+            | Self::PatternMismatch
+            | Self::UnknownDetour(_)
+            | Self::UnknownHook(_)
+            | Self::UnknownPatch(_) => ERROR_NOT_FOUND,
+            Self::NotExecutable | Self::TargetOutOfBounds => ERROR_INVALID_ADDRESS,
+            Self::UnsupportedFunction
+            | Self::Arm64EcCode
+            | Self::NotRebindable
+            | Self::CriticalFunction(_) => ERROR_NOT_SUPPORTED,
+            Self::ModuleNotFound | Self::InvalidModule => ERROR_MOD_NOT_FOUND,
+            Self::BitnessMismatch { .. } => ERROR_BAD_EXE_FORMAT,
+            Self::FunctionNotFound | Self::InvalidExport | Self::InvalidImport => {
+                ERROR_PROC_NOT_FOUND
+            }
+            Self::WindowProcedure(error) | Self::MemoryProtection(error) => *error,
+            Self::FailedAllocatingMemory => return E_OUTOFMEMORY,
+            Self::GraphicsDevice(result) => return *result,
+            Self::InvalidTarget => return E_POINTER,
+            Self::InvalidPattern
+            | Self::InvalidTargetSpec
+            | Self::InvalidManifest(_)
+            | Self::DependencyCycle => return E_INVALIDARG,
+            Self::FailedTransactionBegin
+            | Self::FailedTransactionCommit
+            | Self::Unknown(_)
+            | Self::BatchFailed { .. }
+            | Self::WithContext { .. } => return E_FAIL,
+        };
+
+        HRESULT_FROM_WIN32(win32)
+    }
+}
+
+#[cfg(feature = "windows")]
+impl From<Error> for windows::core::Error {
+    fn from(error: Error) -> Self {
+        Self::new(windows::core::HRESULT(error.to_hresult()), error.to_string())
+    }
+}
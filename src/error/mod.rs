@@ -7,47 +7,189 @@ use minhook_detours_sys::{
     MH_ERROR_NOT_CREATED, MH_ERROR_NOT_EXECUTABLE, MH_ERROR_NOT_INITIALIZED,
     MH_ERROR_UNABLE_TO_UNINITIALIZE, MH_ERROR_UNSUPPORTED_FUNCTION, MH_STATUS,
 };
+use std::os::raw::c_void;
+#[cfg(not(feature = "minimal"))]
 use thiserror::Error;
 
-#[derive(Debug, Error)]
+// The messages are derived through `thiserror`, which `minimal` builds go without.
+#[cfg(not(any(feature = "std-errors", feature = "minimal")))]
+compile_error!(
+    "either the `std-errors` feature, which is enabled by default, or the `minimal` one is required"
+);
+
+use crate::wow64::Bitness;
+
+mod context;
+mod hresult;
+#[cfg(feature = "minimal")]
+mod minimal;
+
+pub use context::{ErrorContext, ExistingHook, Operation};
+
+/// With the `minimal` feature, both `Display` and `Debug` only write [`Error::code`], and the `Display` of an
+/// [`ErrorContext`] only its target, to keep messages out of size-constrained payloads.
+#[cfg_attr(not(feature = "minimal"), derive(Debug, Error))]
 pub enum Error {
-    #[error("MinHook is already initialized")]
+    #[cfg_attr(not(feature = "minimal"), error("MinHook is already initialized"))]
     AlreadyInitialized,
-    #[error("MinHook is not initialized yet, or already uninitialized")]
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error("MinHook is not initialized yet, or already uninitialized")
+    )]
     NotInitialized,
-    #[error("MinHook can't be uninitialized due to hooks that failed to be removed")]
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error("MinHook can't be uninitialized due to hooks that failed to be removed")
+    )]
     UnableToInitialize,
-    #[error("The hook for the specified target function is already created")]
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error("The hook for the specified target function is already created")
+    )]
     AlreadyCreated,
-    #[error("The hook for the specified target function is not created yet")]
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error("The hook for the specified target function is not created yet")
+    )]
     NotCreated,
-    #[error("The hook for the specified target function is already enabled")]
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error("The hook for the specified target function is already enabled")
+    )]
     Enabled,
-    #[error("The hook for the specified target function is not enabled yet, or already disabled")]
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error("The hook for the specified target function is not enabled yet, or already disabled")
+    )]
     Disabled,
-    #[error(
-        "The specified pointer is invalid. It points the address of non-allocated and/or non-executable region"
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error(
+            "The specified pointer is invalid. It points the address of non-allocated and/or non-executable region"
+        )
     )]
     NotExecutable,
-    #[error("Detours failed to begin the hooking transaction")]
+    #[cfg_attr(not(feature = "minimal"), error("Detours failed to begin the hooking transaction"))]
     FailedTransactionBegin,
-    #[error("Detours failed to commit the hooking transaction")]
+    #[cfg_attr(not(feature = "minimal"), error("Detours failed to commit the hooking transaction"))]
     FailedTransactionCommit,
-    #[error("The specified target function cannot be hooked")]
+    #[cfg_attr(not(feature = "minimal"), error("The specified target function cannot be hooked"))]
     UnsupportedFunction,
-    #[error("Failed to allocate memory")]
+    #[cfg_attr(not(feature = "minimal"), error("Failed to allocate memory"))]
     FailedAllocatingMemory,
-    #[error("The specified module is not loaded")]
+    #[cfg_attr(not(feature = "minimal"), error("The specified module is not loaded"))]
     ModuleNotFound,
-    #[error("The specified function is not found")]
+    #[cfg_attr(not(feature = "minimal"), error("The specified function is not found"))]
     FunctionNotFound,
+    #[cfg_attr(not(feature = "minimal"), error("MinHook returned an unknown status: {0}"))]
+    Unknown(MH_STATUS),
 
     // -------------------------------------------------------------------------------------------------------
     // Above are the MinHook-native possible errors, following are Rust-level ones. For consistency, even if
     // it may fit, the previous results will not be used.
     // -------------------------------------------------------------------------------------------------------
-    #[error("The specified pointer is known to be invalid")]
+    #[cfg_attr(not(feature = "minimal"), error("The specified pointer is known to be invalid"))]
     InvalidTarget,
+    #[cfg_attr(not(feature = "minimal"), error("The specified byte pattern is malformed"))]
+    InvalidPattern,
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error("The specified module is not loaded, or its headers are malformed")
+    )]
+    InvalidModule,
+    #[cfg_attr(not(feature = "minimal"), error("The specified export could not be resolved"))]
+    InvalidExport,
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error("The specified module doesn't delay-load the specified import")
+    )]
+    InvalidImport,
+    #[cfg_attr(not(feature = "minimal"), error("The specified target specification is malformed"))]
+    InvalidTargetSpec,
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error("The specified target lands outside of the code of its owning module")
+    )]
+    TargetOutOfBounds,
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error("The specified byte pattern doesn't match exactly one location")
+    )]
+    PatternMismatch,
+    #[cfg_attr(not(feature = "minimal"), error("The specified manifest is malformed: {0}"))]
+    InvalidManifest(String),
+    #[cfg_attr(not(feature = "minimal"), error("The detour `{0}` is not registered"))]
+    UnknownDetour(String),
+    #[cfg_attr(not(feature = "minimal"), error("The hook `{0}` is not registered"))]
+    UnknownHook(String),
+    #[cfg_attr(not(feature = "minimal"), error("No patch is applied at {0:#x}"))]
+    UnknownPatch(usize),
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error(
+            "The specified target is native ARM64 code of an Arm64EC process, which can't be patched as x64 code"
+        )
+    )]
+    Arm64EcCode,
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error("The hook for the specified target function wasn't created as rebindable")
+    )]
+    NotRebindable,
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error("The hook for the specified target function would depend on itself")
+    )]
+    DependencyCycle,
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error("The specified module is {found}, while the current process is {expected}")
+    )]
+    BitnessMismatch { expected: Bitness, found: Bitness },
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error("Creating a dummy graphics device failed with {0:#x}")
+    )]
+    GraphicsDevice(i32),
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error("Replacing the window procedure failed with error {0}")
+    )]
+    WindowProcedure(u32),
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error("Changing the protection of the specified memory failed with error {0}")
+    )]
+    MemoryProtection(u32),
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error("The guard is poisoned, as uninitializing the engine failed")
+    )]
+    Poisoned,
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error("The specified target, {0}, is relied upon by the crate, or the engine, and can't be hooked")
+    )]
+    CriticalFunction(String),
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error("The operation would load a module while the loader lock is held, e.g. from DllMain")
+    )]
+    LoaderLock,
+    #[cfg_attr(
+        not(feature = "minimal"),
+        error("The batch of hooks failed, and was rolled back: {source}")
+    )]
+    BatchFailed {
+        /// The entry of the batch that failed, unless the batch failed as a whole.
+        index: Option<usize>,
+        source: Box<Error>,
+    },
+    #[cfg_attr(not(feature = "minimal"), error("{context}: {source}"))]
+    WithContext {
+        context: ErrorContext,
+        source: Box<Error>,
+    },
 }
 
 impl From<MH_STATUS> for Error {
@@ -72,9 +214,186 @@ impl From<MH_STATUS> for Error {
             MH_ERROR_MEMORY_ALLOC => Self::FailedAllocatingMemory,
             MH_ERROR_MODULE_NOT_FOUND => Self::ModuleNotFound,
             MH_ERROR_FUNCTION_NOT_FOUND => Self::FunctionNotFound,
-            _ => unreachable!(),
+            _ => Self::Unknown(value),
+        }
+    }
+}
+
+impl Error {
+    /// The innermost error, stripped of any [`ErrorContext`], and batch.
+    ///
+    /// Use this to match against the kind of error, e.g. `matches!(error.root(), Error::Enabled)`.
+    pub fn root(&self) -> &Error {
+        match self {
+            Self::WithContext { source, .. } | Self::BatchFailed { source, .. } => source.root(),
+            _ => self,
+        }
+    }
+
+    /// The context attached to the error, if any.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Self::WithContext { context, .. } => Some(context),
+            Self::BatchFailed { source, .. } => source.context(),
+            _ => None,
+        }
+    }
+
+    /// Attach `context` to the error, filling in the fields that weren't known yet.
+    pub(crate) fn with_context(self, context: ErrorContext) -> Self {
+        match self {
+            Self::WithContext {
+                context: mut existing,
+                source,
+            } => {
+                existing.merge(context);
+                Self::WithContext {
+                    context: existing,
+                    source,
+                }
+            }
+            _ => Self::WithContext {
+                context,
+                source: Box::new(self),
+            },
+        }
+    }
+
+    /// Build the error of a failed engine `operation` on `target`, out of the returned [`MH_STATUS`].
+    ///
+    /// The value of `GetLastError` is the one the engine captured right as the operation failed, on the current
+    /// thread, see [`crate::engine::take_last_error`].
+    pub(crate) fn from_operation(
+        status: MH_STATUS,
+        operation: Operation,
+        target: Option<*mut c_void>,
+    ) -> Self {
+        let last_error = crate::engine::take_last_error();
+
+        Self::from(status).with_context(ErrorContext {
+            operation: Some(operation),
+            target: target.map(|target| target as usize),
+            symbol: None,
+            last_error: (last_error != 0).then_some(last_error),
+            existing: None,
+        })
+    }
+
+    /// The value of `GetLastError` captured when the underlying engine operation failed, if any.
+    pub fn last_error(&self) -> Option<u32> {
+        self.context().and_then(|context| context.last_error)
+    }
+
+    /// The [`MH_STATUS`] returned by the C API this error originates from.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(MH_STATUS)` for MinHook-native errors.
+    /// - `None` for Rust-level errors.
+    pub fn raw_status(&self) -> Option<MH_STATUS> {
+        let status = match self.root() {
+            Self::AlreadyInitialized => MH_ERROR_ALREADY_INITIALIZED,
+            Self::NotInitialized => MH_ERROR_NOT_INITIALIZED,
+            Self::UnableToInitialize => MH_ERROR_UNABLE_TO_UNINITIALIZE,
+            Self::AlreadyCreated => MH_ERROR_ALREADY_CREATED,
+            Self::NotCreated => MH_ERROR_NOT_CREATED,
+            Self::Enabled => MH_ERROR_ENABLED,
+            Self::Disabled => MH_ERROR_DISABLED,
+            Self::NotExecutable => MH_ERROR_NOT_EXECUTABLE,
+            Self::FailedTransactionBegin => MH_ERROR_DETOURS_TRANSACTION_BEGIN,
+            Self::FailedTransactionCommit => MH_ERROR_DETOURS_TRANSACTION_COMMIT,
+            Self::UnsupportedFunction => MH_ERROR_UNSUPPORTED_FUNCTION,
+            Self::FailedAllocatingMemory => MH_ERROR_MEMORY_ALLOC,
+            Self::ModuleNotFound => MH_ERROR_MODULE_NOT_FOUND,
+            Self::FunctionNotFound => MH_ERROR_FUNCTION_NOT_FOUND,
+            Self::Unknown(status) => *status,
+            _ => return None,
+        };
+
+        Some(status)
+    }
+
+    /// A stable numeric code of the kind of the error, e.g. for reporting it out of a payload built with the
+    /// `minimal` feature.
+    ///
+    /// # Returns
+    ///
+    /// - The [`MH_STATUS`] for MinHook-native errors, see [`Error::raw_status`].
+    /// - `0x100` onwards for Rust-level errors, in declaration order.
+    pub fn code(&self) -> MH_STATUS {
+        match self {
+            Self::AlreadyInitialized => MH_ERROR_ALREADY_INITIALIZED,
+            Self::NotInitialized => MH_ERROR_NOT_INITIALIZED,
+            Self::UnableToInitialize => MH_ERROR_UNABLE_TO_UNINITIALIZE,
+            Self::AlreadyCreated => MH_ERROR_ALREADY_CREATED,
+            Self::NotCreated => MH_ERROR_NOT_CREATED,
+            Self::Enabled => MH_ERROR_ENABLED,
+            Self::Disabled => MH_ERROR_DISABLED,
+            Self::NotExecutable => MH_ERROR_NOT_EXECUTABLE,
+            Self::FailedTransactionBegin => MH_ERROR_DETOURS_TRANSACTION_BEGIN,
+            Self::FailedTransactionCommit => MH_ERROR_DETOURS_TRANSACTION_COMMIT,
+            Self::UnsupportedFunction => MH_ERROR_UNSUPPORTED_FUNCTION,
+            Self::FailedAllocatingMemory => MH_ERROR_MEMORY_ALLOC,
+            Self::ModuleNotFound => MH_ERROR_MODULE_NOT_FOUND,
+            Self::FunctionNotFound => MH_ERROR_FUNCTION_NOT_FOUND,
+            Self::Unknown(status) => *status,
+            Self::InvalidTarget => 0x100,
+            Self::InvalidPattern => 0x101,
+            Self::InvalidModule => 0x102,
+            Self::InvalidExport => 0x103,
+            Self::InvalidImport => 0x104,
+            Self::InvalidTargetSpec => 0x105,
+            Self::TargetOutOfBounds => 0x106,
+            Self::PatternMismatch => 0x107,
+            Self::InvalidManifest(_) => 0x108,
+            Self::UnknownDetour(_) => 0x109,
+            Self::UnknownHook(_) => 0x10A,
+            Self::UnknownPatch(_) => 0x10B,
+            Self::Arm64EcCode => 0x10C,
+            Self::NotRebindable => 0x10D,
+            Self::DependencyCycle => 0x10E,
+            Self::BitnessMismatch { .. } => 0x10F,
+            Self::GraphicsDevice(_) => 0x110,
+            Self::WindowProcedure(_) => 0x111,
+            Self::MemoryProtection(_) => 0x112,
+            Self::Poisoned => 0x113,
+            Self::CriticalFunction(_) => 0x114,
+            Self::LoaderLock => 0x115,
+            // Batches, and contexts report the error they wrap.
+            Self::BatchFailed { source, .. } | Self::WithContext { source, .. } => source.code(),
         }
     }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
+
+/// Extension of [`Result`] for attaching human-readable context to errors at the call site.
+///
+/// Context that is already known, e.g. the target address of a failed engine operation, is kept.
+pub trait ResultExt<T> {
+    /// Attach the address of the target the failed operation was about.
+    fn for_target(self, target: *const c_void) -> Result<T>;
+
+    /// Attach a human-readable description of the target, e.g. `user32.dll!MessageBoxW`.
+    fn for_symbol(self, symbol: impl Into<String>) -> Result<T>;
+}
+
+impl<T> ResultExt<T> for Result<T> {
+    fn for_target(self, target: *const c_void) -> Result<T> {
+        self.map_err(|error| {
+            error.with_context(ErrorContext {
+                target: Some(target as usize),
+                ..Default::default()
+            })
+        })
+    }
+
+    fn for_symbol(self, symbol: impl Into<String>) -> Result<T> {
+        self.map_err(|error| {
+            error.with_context(ErrorContext {
+                symbol: Some(symbol.into()),
+                ..Default::default()
+            })
+        })
+    }
+}
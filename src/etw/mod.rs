@@ -0,0 +1,111 @@
+//! ETW provider.
+//!
+//! Responsible for reporting the lifecycle of the engine, and of every hook as TraceLogging events, so that
+//! they can be collected on machines where attaching a debugger isn't an option, e.g. with
+//! `wpr -start minhook_detours.wprp` or `tracelog -start hooks -guid *MinHookDetours`.
+//!
+//! Events are emitted through [`EtwObserver`], set with [`crate::guard::DetourGuard::set_observer`].
+
+use std::sync::OnceLock;
+
+use tracelogging as tlg;
+
+use crate::guard::{HookEvent, HookObserver};
+
+tlg::define_provider!(PROVIDER, "MinHookDetours");
+
+/// Name of the provider, whose GUID is derived from it by ETW's name hashing.
+pub const PROVIDER_NAME: &str = "MinHookDetours";
+
+/// Status of the registration of the provider, with `0` on success.
+static REGISTRATION: OnceLock<u32> = OnceLock::new();
+
+/// Reports every [`HookEvent`] it receives through the `MinHookDetours` ETW provider.
+#[derive(Debug)]
+pub struct EtwObserver {
+    _private: (),
+}
+
+impl EtwObserver {
+    /// Register the provider with ETW, if it wasn't already registered.
+    pub fn new() -> Self {
+        // The provider lives for the rest of the process, so it is never unregistered.
+        REGISTRATION.get_or_init(|| unsafe { PROVIDER.register() });
+
+        Self { _private: () }
+    }
+
+    /// Whether ETW accepted the registration of the provider.
+    pub fn is_registered(&self) -> bool {
+        REGISTRATION.get() == Some(&0)
+    }
+}
+
+impl Default for EtwObserver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HookObserver for EtwObserver {
+    fn on_event(&mut self, event: &HookEvent<'_>) {
+        match event {
+            HookEvent::EngineInitialized => {
+                tlg::write_event!(PROVIDER, "EngineInitialized", level(Informational));
+            }
+            HookEvent::EngineUninitialized => {
+                tlg::write_event!(PROVIDER, "EngineUninitialized", level(Informational));
+            }
+            HookEvent::Created { target, detour } => {
+                tlg::write_event!(
+                    PROVIDER,
+                    "HookCreated",
+                    level(Informational),
+                    pointer("Target", &(*target as usize)),
+                    pointer("Detour", &(*detour as usize)),
+                );
+            }
+            HookEvent::Enabled { target } => {
+                tlg::write_event!(
+                    PROVIDER,
+                    "HookEnabled",
+                    level(Informational),
+                    pointer("Target", &(*target as usize)),
+                );
+            }
+            HookEvent::Disabled { target } => {
+                tlg::write_event!(
+                    PROVIDER,
+                    "HookDisabled",
+                    level(Informational),
+                    pointer("Target", &(*target as usize)),
+                );
+            }
+            HookEvent::Removed { target } => {
+                tlg::write_event!(
+                    PROVIDER,
+                    "HookRemoved",
+                    level(Informational),
+                    pointer("Target", &(*target as usize)),
+                );
+            }
+            HookEvent::Failed { error } => {
+                tlg::write_event!(
+                    PROVIDER,
+                    "OperationFailed",
+                    level(Error),
+                    str8("Error", error.to_string()),
+                    i32("HResult", &error.to_hresult()),
+                );
+            }
+            HookEvent::DroppedWithLiveHooks { count } => {
+                tlg::write_event!(
+                    PROVIDER,
+                    "DroppedWithLiveHooks",
+                    level(Warning),
+                    u64("Count", &(*count as u64)),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Graphics API targets.
+//!
+//! Responsible for resolving the methods overlays usually hook, e.g. `IDXGISwapChain::Present`. Those are
+//! only reachable through the vtables of live objects, so a dummy device is created on a hidden window, its
+//! vtable read, and everything released again. The returned addresses can be fed into
+//! [`crate::guard::DetourGuard::create_hook`].
+
+use std::{
+    mem::zeroed,
+    os::raw::c_void,
+    ptr::{null, null_mut},
+};
+use winapi::{
+    shared::{
+        d3d9::{
+            D3D_SDK_VERSION, D3DADAPTER_DEFAULT, D3DCREATE_DISABLE_DRIVER_MANAGEMENT,
+            D3DCREATE_SOFTWARE_VERTEXPROCESSING, Direct3DCreate9, IDirect3DDevice9,
+        },
+        d3d9types::{D3DDEVTYPE_HAL, D3DFMT_UNKNOWN, D3DPRESENT_PARAMETERS, D3DSWAPEFFECT_DISCARD},
+        dxgi::{DXGI_SWAP_CHAIN_DESC, DXGI_SWAP_EFFECT_DISCARD, IDXGISwapChain},
+        dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM,
+        dxgitype::DXGI_USAGE_RENDER_TARGET_OUTPUT,
+        minwindef::TRUE,
+        windef::HWND,
+        winerror::{E_FAIL, HRESULT_FROM_WIN32, SUCCEEDED},
+    },
+    um::{
+        d3d11::{
+            D3D11_SDK_VERSION, D3D11CreateDeviceAndSwapChain, ID3D11Device, ID3D11DeviceContext,
+        },
+        d3dcommon::{D3D_DRIVER_TYPE_HARDWARE, D3D_DRIVER_TYPE_WARP},
+        errhandlingapi::GetLastError,
+        libloaderapi::GetModuleHandleW,
+        winuser::{CreateWindowExW, DestroyWindow, WS_OVERLAPPEDWINDOW},
+    },
+};
+
+use crate::{
+    error::{Error, Result},
+    pe::to_wide,
+};
+
+/// The methods of `IDXGISwapChain` that overlays hook, shared by Direct3D 10, 11, and 12 swap chains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DxgiTargets {
+    pub present: *mut c_void,
+    pub resize_buffers: *mut c_void,
+}
+
+/// The methods of `IDirect3DDevice9` that overlays hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct D3d9Targets {
+    pub end_scene: *mut c_void,
+    pub reset: *mut c_void,
+    pub present: *mut c_void,
+}
+
+/// Hidden window, destroyed once dropped, that dummy devices render to.
+struct DummyWindow(HWND);
+
+impl DummyWindow {
+    fn new() -> Result<Self> {
+        // The predefined `STATIC` class avoids registering a class of our own.
+        let class = to_wide("STATIC");
+
+        let window = unsafe {
+            CreateWindowExW(
+                0,
+                class.as_ptr(),
+                null(),
+                WS_OVERLAPPEDWINDOW,
+                0,
+                0,
+                1,
+                1,
+                null_mut(),
+                null_mut(),
+                GetModuleHandleW(null()),
+                null_mut(),
+            )
+        };
+
+        if window.is_null() {
+            // `HRESULT_FROM_WIN32(0)` would be `S_OK`, so a failure without an error code is reported as such.
+            let hresult = match unsafe { GetLastError() } {
+                0 => E_FAIL,
+                error => HRESULT_FROM_WIN32(error),
+            };
+            return Err(Error::GraphicsDevice(hresult));
+        }
+
+        Ok(Self(window))
+    }
+}
+
+impl Drop for DummyWindow {
+    fn drop(&mut self) {
+        unsafe { DestroyWindow(self.0) };
+    }
+}
+
+/// Resolve the methods of `IDXGISwapChain` out of a dummy Direct3D 11 swap chain, falling back to the WARP
+/// software rasterizer when there's no hardware device.
+///
+/// # Returns
+///
+/// - `Ok(DxgiTargets)` if the dummy swap chain was succesfully created.
+/// - `Err(minhook_detours_rs::error::Error::GraphicsDevice)` otherwise, with the failing `HRESULT`.
+pub fn dxgi_targets() -> Result<DxgiTargets> {
+    let window = DummyWindow::new()?;
+
+    let mut desc: DXGI_SWAP_CHAIN_DESC = unsafe { zeroed() };
+    desc.BufferDesc.Format = DXGI_FORMAT_R8G8B8A8_UNORM;
+    desc.SampleDesc.Count = 1;
+    desc.BufferUsage = DXGI_USAGE_RENDER_TARGET_OUTPUT;
+    desc.BufferCount = 1;
+    desc.OutputWindow = window.0;
+    desc.Windowed = TRUE;
+    desc.SwapEffect = DXGI_SWAP_EFFECT_DISCARD;
+
+    let mut result = E_FAIL;
+    for driver_type in [D3D_DRIVER_TYPE_HARDWARE, D3D_DRIVER_TYPE_WARP] {
+        let mut swap_chain: *mut IDXGISwapChain = null_mut();
+        let mut device: *mut ID3D11Device = null_mut();
+        let mut context: *mut ID3D11DeviceContext = null_mut();
+
+        result = unsafe {
+            D3D11CreateDeviceAndSwapChain(
+                null_mut(),
+                driver_type,
+                null_mut(),
+                0,
+                null(),
+                0,
+                D3D11_SDK_VERSION,
+                &desc,
+                &mut swap_chain,
+                &mut device,
+                null_mut(),
+                &mut context,
+            )
+        };
+
+        if !SUCCEEDED(result) {
+            continue;
+        }
+
+        let targets = unsafe {
+            let vtable = &*(*swap_chain).lpVtbl;
+
+            DxgiTargets {
+                present: vtable.Present as *mut c_void,
+                resize_buffers: vtable.ResizeBuffers as *mut c_void,
+            }
+        };
+
+        unsafe {
+            (*context).Release();
+            (*device).Release();
+            (*swap_chain).Release();
+        }
+
+        // We succesfully read the vtable of the swap chain!
+        return Ok(targets);
+    }
+
+    Err(Error::GraphicsDevice(result))
+}
+
+/// Resolve the methods of `IDirect3DDevice9` out of a dummy device.
+///
+/// # Returns
+///
+/// - `Ok(D3d9Targets)` if the dummy device was succesfully created.
+/// - `Err(minhook_detours_rs::error::Error::GraphicsDevice)` otherwise, with the failing `HRESULT`.
+pub fn d3d9_targets() -> Result<D3d9Targets> {
+    let window = DummyWindow::new()?;
+
+    let direct3d = unsafe { Direct3DCreate9(D3D_SDK_VERSION) };
+    if direct3d.is_null() {
+        return Err(Error::GraphicsDevice(E_FAIL));
+    }
+
+    let mut parameters: D3DPRESENT_PARAMETERS = unsafe { zeroed() };
+    parameters.Windowed = TRUE;
+    parameters.SwapEffect = D3DSWAPEFFECT_DISCARD;
+    parameters.BackBufferFormat = D3DFMT_UNKNOWN;
+    parameters.hDeviceWindow = window.0;
+
+    let mut device: *mut IDirect3DDevice9 = null_mut();
+    let result = unsafe {
+        (*direct3d).CreateDevice(
+            D3DADAPTER_DEFAULT,
+            D3DDEVTYPE_HAL,
+            window.0,
+            D3DCREATE_SOFTWARE_VERTEXPROCESSING | D3DCREATE_DISABLE_DRIVER_MANAGEMENT,
+            &mut parameters,
+            &mut device,
+        )
+    };
+
+    if !SUCCEEDED(result) {
+        unsafe { (*direct3d).Release() };
+        return Err(Error::GraphicsDevice(result));
+    }
+
+    let targets = unsafe {
+        let vtable = &*(*device).lpVtbl;
+
+        D3d9Targets {
+            end_scene: vtable.EndScene as *mut c_void,
+            reset: vtable.Reset as *mut c_void,
+            present: vtable.Present as *mut c_void,
+        }
+    };
+
+    unsafe {
+        (*device).Release();
+        (*direct3d).Release();
+    }
+
+    // We succesfully read the vtable of the device!
+    Ok(targets)
+}
@@ -0,0 +1,168 @@
+use minhook_detours_sys::{MH_OK, MH_STATUS};
+use std::{
+    mem::take,
+    os::raw::c_void,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    error::{Error, Operation, Result},
+    guard::DetourGuard,
+    logging,
+};
+
+/// How long the background toggles are waited for while the loader lock is held, as the threads that didn't
+/// start running yet can't before it's released.
+const LOADER_LOCK_WAIT: Duration = Duration::from_millis(100);
+
+/// Toggles running on background threads, see [`DetourGuard::enable_hook_async`].
+#[derive(Debug, Default)]
+pub(crate) struct Background {
+    threads: Vec<JoinHandle<()>>,
+    /// The number of toggles that didn't run yet, as their threads may outlive them.
+    pending: Arc<AtomicUsize>,
+    /// The targets, and statuses of the toggles that ran, yet to be reflected in the registry.
+    completed: Arc<Mutex<Vec<(usize, MH_STATUS)>>>,
+}
+
+impl<'a> DetourGuard<'a> {
+    /// Enables the hook attached to `target` on a background thread, so that the calling thread never blocks
+    /// on freezing the other threads, then calls `callback` with the result, on that background thread.
+    ///
+    /// Threads are frozen by the engine, with the current [`ThreadFreezeMethod`], as the [`ThreadFreezer`],
+    /// and the excluded threads can't be used from another thread. Every later operation that patches code,
+    /// e.g. disabling the very same hook, waits for the pending toggles first, so that the engine applies
+    /// them in the order they were issued, and the registry reflects the hook as enabled from then on.
+    ///
+    /// While the current thread holds the loader lock, no new thread could run, so the hook is enabled on the
+    /// current thread instead, before returning, see [`DetourGuard::is_loader_lock_held`]. Operations only
+    /// wait for the toggles handed to the background beforehand for a moment then, so they may apply out of
+    /// order.
+    ///
+    /// [`ThreadFreezeMethod`]: crate::guard::ThreadFreezeMethod
+    /// [`ThreadFreezer`]: crate::guard::ThreadFreezer
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The hooked function.
+    /// * `callback` - Called with the result of enabling the hook.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if the hook was succesfully handed to a background thread.
+    /// - `Err(minhook_detours_rs::error::Error::NotCreated)` if no hook is registered for `target`.
+    /// - `Err(minhook_detours_rs::error::Error)` if the [`DetourGuard`] isn't usable.
+    pub fn enable_hook_async(
+        &mut self,
+        target: *mut c_void,
+        callback: impl FnOnce(Result<()>) + Send + 'static,
+    ) -> Result<()> {
+        let _span = logging::span!("enable_hook_async", target = ?target);
+        self.ensure_usable()?;
+
+        if target.is_null() {
+            return Err(Error::InvalidTarget);
+        }
+
+        if self.entry_mut(target).is_none() {
+            return Err(Error::NotCreated);
+        }
+
+        if Self::is_loader_lock_held() {
+            logging::debug!(
+                "Enabling hook for {} in place, as the loader lock is held",
+                logging::Address(target)
+            );
+            callback(self.enable_hook(target));
+            return Ok(());
+        }
+
+        self.background.threads.retain(|thread| !thread.is_finished());
+
+        let engine = self.engine.clone();
+        let completed = self.background.completed.clone();
+        let pending = self.background.pending.clone();
+        let target = target as usize;
+
+        pending.fetch_add(1, Ordering::AcqRel);
+        let thread = thread::spawn(move || {
+            let status = engine.enable_hook(target as _);
+
+            // Recorded before calling back, so that the next operation after the callback sees it.
+            if let Ok(mut completed) = completed.lock() {
+                completed.push((target, status));
+            }
+            pending.fetch_sub(1, Ordering::AcqRel);
+
+            callback(match status {
+                MH_OK => Ok(()),
+                _ => Err(Error::from_operation(status, Operation::EnableHook, Some(target as _))),
+            });
+        });
+        self.background.threads.push(thread);
+
+        // We succesfully handed the hook to a background thread!
+        logging::debug!("Enabling hook for {target:#x} in the background");
+        Ok(())
+    }
+
+    /// Waits for every toggle running in the background, then reflects them in the registry.
+    ///
+    /// Called before anything changes the thread freezing method of the engine, as the background toggles
+    /// would pick it up. While the current thread holds the loader lock, the threads can't exit, nor start
+    /// running, so the toggles are only waited for up to [`LOADER_LOCK_WAIT`]. The ones still pending are
+    /// left running, and land in the registry on a later operation, while the threads are joined later on.
+    pub(crate) fn join_background(&mut self) {
+        if Self::is_loader_lock_held() {
+            let deadline = Instant::now() + LOADER_LOCK_WAIT;
+            let pending = &self.background.pending;
+            while pending.load(Ordering::Acquire) != 0 && Instant::now() < deadline {
+                thread::yield_now();
+            }
+
+            let pending = pending.load(Ordering::Acquire);
+            if pending != 0 {
+                logging::info!("Leaving {pending} toggles running, as the loader lock is held");
+            }
+
+            self.apply_background();
+            return;
+        }
+
+        for thread in take(&mut self.background.threads) {
+            let _ = thread.join();
+        }
+
+        self.apply_background();
+    }
+
+    /// Reflects the toggles that ran in the background in the registry.
+    pub(crate) fn apply_background(&mut self) {
+        let completed = match self.background.completed.lock() {
+            Ok(mut completed) if !completed.is_empty() => take(&mut *completed),
+            _ => return,
+        };
+
+        for (target, status) in completed {
+            // Only the engine's own result tells whether the hook is enabled now.
+            if status != MH_OK {
+                logging::debug!(
+                    "Failed enabling hook for {} in the background with {status}",
+                    logging::Address(target as _)
+                );
+                continue;
+            }
+
+            logging::debug!(
+                "Enabled hook for {} in the background",
+                logging::Address(target as _)
+            );
+            self.set_enabled(target as _, true);
+        }
+    }
+}
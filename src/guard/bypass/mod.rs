@@ -0,0 +1,92 @@
+use std::os::raw::c_void;
+use winapi::um::debugapi::IsDebuggerPresent;
+
+use crate::{guard::DetourGuard, logging};
+
+impl<'a> DetourGuard<'a> {
+    /// Routes the calls of every rebindable hook straight to the original function, without unpatching
+    /// anything, e.g. so that stepping through the host in a debugger doesn't go through the detours.
+    ///
+    /// Only hooks created through [`DetourGuard::create_rebindable_hook`] can be bypassed, as they are switched
+    /// off through their [`crate::guard::HookSwitch`], including the ones created while bypassing. Stopping
+    /// switches back on only the hooks the bypass switched off, so hooks switched off beforehand stay off.
+    ///
+    /// Plain hooks, e.g. the ones of [`DetourGuard::create_hook`], have no stub to switch, so they keep
+    /// running their detours while bypassing. Disable them through [`DetourGuard::disable_hook`] instead,
+    /// which patches code.
+    ///
+    /// # Arguments
+    ///
+    /// * `bypass` - Whether calls should go straight to the original functions.
+    pub fn set_bypass_all(&mut self, bypass: bool) {
+        if bypass == self.bypassed.is_some() {
+            return;
+        }
+
+        if !bypass {
+            for target in self.bypassed.take().unwrap_or_default() {
+                if let Ok(switch) = self.switch(target) {
+                    switch.on();
+                }
+            }
+
+            // We succesfully stopped bypassing!
+            logging::debug!("Stopped bypassing hooks");
+            return;
+        }
+
+        let targets = self.targets();
+        self.bypassed = Some(Vec::new());
+        for target in targets {
+            self.bypass(target);
+        }
+
+        // We succesfully started bypassing!
+        logging::debug!("Bypassing hooks");
+    }
+
+    /// Whether calls of rebindable hooks are routed straight to the original functions, see
+    /// [`DetourGuard::set_bypass_all`].
+    pub fn is_bypassing(&self) -> bool {
+        self.bypassed.is_some()
+    }
+
+    /// Bypass every rebindable hook whenever a debugger is attached, see [`DetourGuard::set_bypass_all`].
+    ///
+    /// Plain hooks aren't bypassed, see [`DetourGuard::set_bypass_all`]. Whether a debugger is attached isn't
+    /// watched either: it's only checked right away, and whenever the [`DetourGuard`] patches code, so a
+    /// debugger attaching in between goes unnoticed until then. Call [`DetourGuard::sync_debugger_bypass`]
+    /// periodically, e.g. from a timer, to catch it sooner.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to bypass while a debugger is attached.
+    pub fn set_bypass_when_debugged(&mut self, enabled: bool) {
+        self.bypass_when_debugged = enabled;
+        self.sync_debugger_bypass();
+    }
+
+    /// Starts, or stops bypassing depending on whether a debugger is attached, if enabled through
+    /// [`DetourGuard::set_bypass_when_debugged`].
+    pub fn sync_debugger_bypass(&mut self) {
+        if self.bypass_when_debugged {
+            self.set_bypass_all(unsafe { IsDebuggerPresent() } != 0);
+        }
+    }
+
+    /// Switches off the hook attached to `target` while bypassing, if it's rebindable, and on.
+    pub(crate) fn bypass(&mut self, target: *mut c_void) {
+        if self.bypassed.is_none() {
+            return;
+        }
+
+        let Ok(switch) = self.switch(target) else {
+            return;
+        };
+
+        if switch.is_on() {
+            switch.off();
+            self.bypassed.get_or_insert_default().push(target);
+        }
+    }
+}
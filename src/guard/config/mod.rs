@@ -0,0 +1,111 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{
+    error::{Error, Result},
+    guard::DetourGuard,
+};
+
+/// Desired state of the named hooks of a [`DetourGuard`], see [`DetourGuard::apply_config`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HookConfig {
+    hooks: BTreeMap<String, bool>,
+}
+
+impl HookConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Desire the hook named `name` to be enabled.
+    pub fn enable(&mut self, name: impl Into<String>) -> &mut Self {
+        self.hooks.insert(name.into(), true);
+        self
+    }
+
+    /// Desire the hook named `name` to be kept, but disabled.
+    pub fn disable(&mut self, name: impl Into<String>) -> &mut Self {
+        self.hooks.insert(name.into(), false);
+        self
+    }
+
+    /// The desired state of the hook named `name`, or `None` if it should be removed.
+    pub fn is_enabled(&self, name: &str) -> Option<bool> {
+        self.hooks.get(name).copied()
+    }
+}
+
+impl<S: Into<String>> FromIterator<(S, bool)> for HookConfig {
+    fn from_iter<I: IntoIterator<Item = (S, bool)>>(iter: I) -> Self {
+        Self {
+            hooks: iter
+                .into_iter()
+                .map(|(name, enabled)| (name.into(), enabled))
+                .collect(),
+        }
+    }
+}
+
+impl<'a> DetourGuard<'a> {
+    /// Reconciles the named hooks of the [`DetourGuard`] with `config`.
+    ///
+    /// Hooks named in `config` are enabled, or disabled accordingly, while named hooks missing from `config`
+    /// are removed, along with their entry in the registry. Unnamed hooks are left untouched. Every enable, and
+    /// disable is applied in a single transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The desired state, keyed by the names assigned through [`DetourGuard::set_hook_name`].
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if the guard now matches `config`.
+    /// - `Err(minhook_detours_rs::error::Error::UnknownHook)` if `config` names a hook that isn't registered. Nothing is applied.
+    /// - `Err(minhook_detours_rs::error::Error)` if the transaction, or a removal failed.
+    pub fn apply_config(&mut self, config: &HookConfig) -> Result<()> {
+        // Validate up front, so that an invalid configuration isn't partially applied.
+        if let Some(name) = config
+            .hooks
+            .keys()
+            .find(|name| self.hooks().all(|hook| hook.name() != Some(name.as_str())))
+        {
+            return Err(Error::UnknownHook(name.clone()));
+        }
+
+        let mut enable = Vec::new();
+        let mut disable = Vec::new();
+        let mut remove = Vec::new();
+
+        for hook in self.hooks() {
+            let Some(name) = hook.name() else {
+                continue;
+            };
+
+            match config.is_enabled(name) {
+                Some(true) if !hook.enabled => enable.push(hook.target),
+                Some(false) if hook.enabled => disable.push(hook.target),
+                Some(_) => {}
+                None => {
+                    // Disabling as part of the transaction, makes the removal itself freeze-free.
+                    if hook.enabled {
+                        disable.push(hook.target);
+                    }
+                    remove.push(hook.target);
+                }
+            }
+        }
+
+        self.apply_queued(&enable, &disable)?;
+
+        for target in remove {
+            self.remove_hook(target)?;
+
+            // The hook is gone for good, so its entry shouldn't linger in the registry either.
+            self.hooks.forget(target);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+use std::{os::raw::c_void, sync::OnceLock};
+
+use crate::{
+    error::{Error, Result},
+    guard::DetourGuard,
+    logging,
+    pe::Module,
+};
+
+/// Exports of `ntdll.dll` the crate, or the engine call while creating, toggling, or removing hooks.
+const NTDLL: &[&str] = &[
+    "RtlAllocateHeap",
+    "RtlFreeHeap",
+    "RtlReAllocateHeap",
+    "RtlSizeHeap",
+    "RtlEnterCriticalSection",
+    "RtlLeaveCriticalSection",
+    "RtlAcquireSRWLockExclusive",
+    "RtlReleaseSRWLockExclusive",
+    "LdrLoadDll",
+    "LdrGetDllHandle",
+    "LdrGetProcedureAddress",
+    "NtAllocateVirtualMemory",
+    "NtFreeVirtualMemory",
+    "NtProtectVirtualMemory",
+    "NtQueryVirtualMemory",
+    "NtFlushInstructionCache",
+    "NtOpenThread",
+    "NtSuspendThread",
+    "NtResumeThread",
+    "NtGetContextThread",
+    "NtSetContextThread",
+    "NtQuerySystemInformation",
+    "NtClose",
+];
+
+/// Exports of `kernel32.dll`, and `kernelbase.dll` the crate, or the engine call while creating, toggling,
+/// or removing hooks.
+const KERNEL32: &[&str] = &[
+    "HeapAlloc",
+    "HeapFree",
+    "HeapReAlloc",
+    "VirtualAlloc",
+    "VirtualFree",
+    "VirtualProtect",
+    "VirtualQuery",
+    "FlushInstructionCache",
+    "CreateToolhelp32Snapshot",
+    "Thread32First",
+    "Thread32Next",
+    "OpenThread",
+    "SuspendThread",
+    "ResumeThread",
+    "GetThreadContext",
+    "SetThreadContext",
+    "CloseHandle",
+    "EnterCriticalSection",
+    "LeaveCriticalSection",
+    "TlsGetValue",
+    "TlsSetValue",
+    "FlsGetValue",
+    "FlsSetValue",
+    "GetModuleHandleW",
+    "GetModuleHandleExW",
+    "GetProcAddress",
+    "LoadLibraryW",
+    "LoadLibraryExW",
+];
+
+/// Hooking a critical function from inside of a detour that doesn't guard against it recursing, or while a
+/// suspended thread holds its lock, deadlocks, or overflows the stack.
+const GUIDANCE: &str = "declare its detour through `non_reentrant_detour!`, keep it free of allocations, \
+    and create it before the hooks that depend on it";
+
+/// What [`DetourGuard::create_hook`] does about targets the crate, or the engine rely on, see
+/// [`DetourGuard::critical_function`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CriticalPolicy {
+    /// Log a warning, with guidance on hooking the function safely.
+    #[default]
+    Warn,
+    /// Fail with [`Error::CriticalFunction`].
+    Deny,
+    /// Hook the function silently.
+    Allow,
+}
+
+impl<'a> DetourGuard<'a> {
+    /// The name of the function at `target`, if the crate, or the engine call it themselves while creating,
+    /// toggling, or removing hooks, e.g. `"kernel32.dll!VirtualProtect"`.
+    ///
+    /// Hooks of these functions run while the crate is in the middle of patching code, and possibly while
+    /// other threads are suspended, so detours of them must neither recurse into themselves, nor wait for
+    /// locks, e.g. of the heap, that a suspended thread may hold. Exports forwarded to `ntdll.dll` are
+    /// resolved to their implementation.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The function to be hooked.
+    pub fn critical_function(target: *const c_void) -> Option<String> {
+        static CRITICAL: OnceLock<Vec<(usize, &'static str, &'static str)>> = OnceLock::new();
+
+        let critical = CRITICAL.get_or_init(|| {
+            let modules = [
+                ("ntdll.dll", NTDLL),
+                ("kernel32.dll", KERNEL32),
+                ("kernelbase.dll", KERNEL32),
+            ];
+
+            modules
+                .into_iter()
+                .filter_map(|(name, exports)| Some((name, Module::from_name(name).ok()?, exports)))
+                .flat_map(|(name, module, exports)| {
+                    exports.iter().filter_map(move |export| {
+                        Some((module.export(export).ok()? as usize, name, *export))
+                    })
+                })
+                .collect()
+        });
+
+        critical
+            .iter()
+            .find(|(address, ..)| *address == target as usize)
+            .map(|(_, module, export)| format!("{module}!{export}"))
+    }
+
+    /// What the [`DetourGuard`] does about hooks of critical functions, see
+    /// [`DetourGuard::critical_function`].
+    pub fn critical_policy(&self) -> CriticalPolicy {
+        self.critical_policy
+    }
+
+    /// Choose what the [`DetourGuard`] does about hooks of critical functions created from now on.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The policy, see [`CriticalPolicy`].
+    pub fn set_critical_policy(&mut self, policy: CriticalPolicy) {
+        self.critical_policy = policy;
+    }
+
+    /// Applies the [`CriticalPolicy`] to a hook about to be created for `target`.
+    pub(crate) fn check_critical(&self, target: *mut c_void) -> Result<()> {
+        if self.critical_policy == CriticalPolicy::Allow {
+            return Ok(());
+        }
+
+        let Some(name) = Self::critical_function(target) else {
+            return Ok(());
+        };
+
+        if self.critical_policy == CriticalPolicy::Deny {
+            return Err(Error::CriticalFunction(name));
+        }
+
+        logging::info!("Hooking {name}, which the crate, or the engine rely on: {GUIDANCE}");
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+use std::{collections::BTreeSet, os::raw::c_void};
+
+use crate::{
+    error::{Error, Result},
+    guard::DetourGuard,
+    logging,
+};
+
+impl<'a> DetourGuard<'a> {
+    /// Declare that the hook attached to `dependent` only works on top of the hook attached to `dependency`,
+    /// e.g. as it hooks the detour, or the original of the other.
+    ///
+    /// From then on, [`DetourGuard::enable_hook`] enables `dependency` before `dependent`, and doesn't enable
+    /// `dependent` if that fails, [`DetourGuard::disable_hook`] disables `dependent` before `dependency`, and
+    /// tearing down removes `dependent` first. Transactions, and operations on every hook patch everything
+    /// at once, so they have no order to respect.
+    ///
+    /// Removing either hook forgets about the dependency.
+    ///
+    /// # Arguments
+    ///
+    /// * `dependent` - The hooked function whose hook depends on the other.
+    /// * `dependency` - The hooked function whose hook is depended on.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if the dependency was succesfully declared.
+    /// - `Err(minhook_detours_rs::error::Error::NotCreated)` if no hook is registered for either target.
+    /// - `Err(minhook_detours_rs::error::Error::DependencyCycle)` if `dependency` already depends on
+    ///   `dependent`, directly or not.
+    pub fn add_dependency(
+        &mut self,
+        dependent: *mut c_void,
+        dependency: *mut c_void,
+    ) -> Result<()> {
+        if self.hook_info(dependent).is_none() || self.hook_info(dependency).is_none() {
+            return Err(Error::NotCreated);
+        }
+
+        if dependent == dependency || self.depends_on(dependency, dependent) {
+            return Err(Error::DependencyCycle);
+        }
+
+        if !self.dependencies.contains(&(dependent, dependency)) {
+            self.dependencies.push((dependent, dependency));
+        }
+
+        // We succesfully declared the dependency!
+        logging::debug!("Hook for {dependent:p} depends on hook for {dependency:p}");
+        Ok(())
+    }
+
+    /// Forget that the hook attached to `dependent` depends on the hook attached to `dependency`.
+    pub fn remove_dependency(&mut self, dependent: *mut c_void, dependency: *mut c_void) {
+        self.dependencies.retain(|edge| *edge != (dependent, dependency));
+    }
+
+    /// Iterates over the hooked functions whose hooks the hook attached to `target` directly depends on, in
+    /// declaration order.
+    pub fn dependencies(&self, target: *mut c_void) -> impl Iterator<Item = *mut c_void> + '_ {
+        self.dependencies
+            .iter()
+            .filter(move |(dependent, _)| *dependent == target)
+            .map(|(_, dependency)| *dependency)
+    }
+
+    /// Iterates over the hooked functions whose hooks directly depend on the hook attached to `target`, in
+    /// declaration order.
+    pub fn dependents(&self, target: *mut c_void) -> impl Iterator<Item = *mut c_void> + '_ {
+        self.dependencies
+            .iter()
+            .filter(move |(_, dependency)| *dependency == target)
+            .map(|(dependent, _)| *dependent)
+    }
+
+    /// Whether the hook attached to `dependent` depends on the hook attached to `dependency`, directly or not.
+    fn depends_on(&self, dependent: *mut c_void, dependency: *mut c_void) -> bool {
+        let mut pending = vec![dependent];
+        let mut visited = Vec::new();
+
+        while let Some(target) = pending.pop() {
+            if target == dependency {
+                return true;
+            }
+
+            if !visited.contains(&target) {
+                visited.push(target);
+                pending.extend(self.dependencies(target));
+            }
+        }
+
+        false
+    }
+
+    /// Enables the disabled hooks the hook attached to `target` depends on, dependencies first.
+    pub(crate) fn enable_dependencies(&mut self, target: *mut c_void) -> Result<()> {
+        for dependency in self.dependencies(target).collect::<Vec<_>>() {
+            if self.hook_info(dependency).is_some_and(|hook| !hook.is_enabled()) {
+                self.enable_hook(dependency)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Disables the enabled hooks depending on the hook attached to `target`, dependents first.
+    pub(crate) fn disable_dependents(&mut self, target: *mut c_void) -> Result<()> {
+        for dependent in self.dependents(target).collect::<Vec<_>>() {
+            if self.hook_info(dependent).is_some_and(|hook| hook.is_enabled()) {
+                self.disable_hook(dependent)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Every registered target, in creation order, moving each after the ones its hook depends on.
+    pub(crate) fn dependency_order(&self) -> Vec<*mut c_void> {
+        let mut ordered = Vec::new();
+        let mut visited = BTreeSet::new();
+
+        for target in self.targets() {
+            self.visit_dependencies(target, &mut ordered, &mut visited);
+        }
+
+        ordered
+    }
+
+    fn visit_dependencies(
+        &self,
+        target: *mut c_void,
+        ordered: &mut Vec<*mut c_void>,
+        visited: &mut BTreeSet<usize>,
+    ) {
+        if !visited.insert(target as usize) || self.hook_info(target).is_none() {
+            return;
+        }
+
+        // Cycles are refused when declaring dependencies, so this ends.
+        for dependency in self.dependencies(target) {
+            self.visit_dependencies(dependency, ordered, visited);
+        }
+
+        ordered.push(target);
+    }
+
+    /// Forgets about the dependencies of, and on the hook attached to `target`.
+    pub(crate) fn forget_dependencies(&mut self, target: *mut c_void) {
+        self.dependencies
+            .retain(|(dependent, dependency)| *dependent != target && *dependency != target);
+    }
+}
@@ -0,0 +1,99 @@
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+#[cfg(feature = "timing")]
+use crate::guard::Timing;
+use crate::guard::{DetourGuard, GuardState, HookInfo, ThreadFreezeMethod};
+
+/// Point-in-time state of a [`DetourGuard`], and of the hooks it registered, see
+/// [`DetourGuard::snapshot`].
+///
+/// Addresses are plain integers, so that the snapshot can be sent around, and serialized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct GuardSnapshot {
+    pub state: GuardState,
+    /// Whether the [`DetourGuard`] initialized the engine, rather than attaching to it.
+    pub owns_engine: bool,
+    pub thread_freeze_method: ThreadFreezeMethod,
+    pub excluded_threads: Vec<u32>,
+    /// Whether rebindable hooks are bypassed, see [`DetourGuard::set_bypass_all`].
+    pub bypassing: bool,
+    /// The hooks, in creation order.
+    pub hooks: Vec<HookSnapshot>,
+    /// The addresses of the applied patches, in application order.
+    pub patches: Vec<usize>,
+}
+
+/// Point-in-time state of a hook, see [`GuardSnapshot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct HookSnapshot {
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub info: HookInfo,
+    /// Whether the hook was created through [`DetourGuard::create_rebindable_hook`].
+    pub rebindable: bool,
+    /// Whether calls go to the detour, rather than to the original function, if the hook is rebindable,
+    /// see [`crate::guard::HookSwitch`].
+    pub switched_on: Option<bool>,
+    /// Whether toggling the hook skips thread freezing, see [`DetourGuard::set_freeze_free`].
+    pub freeze_free: bool,
+    /// The targets of the hooks it depends on, see [`DetourGuard::add_dependency`].
+    pub dependencies: Vec<usize>,
+    /// The number of times the detour was entered, if the hook has [`crate::guard::HookStats`].
+    #[cfg(feature = "stats")]
+    pub calls: Option<u64>,
+    #[cfg(feature = "timing")]
+    pub detour_timing: Option<Timing>,
+    #[cfg(feature = "timing")]
+    pub original_timing: Option<Timing>,
+}
+
+impl<'a> DetourGuard<'a> {
+    /// Captures the state of the [`DetourGuard`], and of every hook it registered, e.g. for a control UI to
+    /// poll.
+    pub fn snapshot(&self) -> GuardSnapshot {
+        let hooks = self
+            .hooks
+            .iter()
+            .filter(|hook| !hook.removed)
+            .map(|hook| {
+                let target = hook.info.target;
+
+                HookSnapshot {
+                    info: hook.info.clone(),
+                    rebindable: hook.indirection.is_some(),
+                    switched_on: self.switch(target).ok().map(|switch| switch.is_on()),
+                    freeze_free: hook.freeze_free,
+                    dependencies: self
+                        .dependencies(target)
+                        .map(|dependency| dependency as usize)
+                        .collect(),
+                    #[cfg(feature = "stats")]
+                    calls: hook.stats.map(|stats| stats.call_count()),
+                    #[cfg(feature = "timing")]
+                    detour_timing: hook.stats.map(|stats| stats.detour_timing()),
+                    #[cfg(feature = "timing")]
+                    original_timing: hook.stats.map(|stats| stats.original_timing()),
+                }
+            })
+            .collect();
+
+        GuardSnapshot {
+            state: self.state,
+            owns_engine: self.owns_engine,
+            thread_freeze_method: self.thread_freeze_method,
+            excluded_threads: self.excluded_threads.iter().copied().collect(),
+            bypassing: self.is_bypassing(),
+            hooks,
+            patches: self.patches.iter().map(|patch| patch.address() as usize).collect(),
+        }
+    }
+
+    /// Serializes [`DetourGuard::snapshot`] as pretty-printed JSON, e.g. for attaching to bug reports.
+    #[cfg(feature = "json")]
+    pub fn dump_state(&self) -> String {
+        // Every field is a plain value, or a sequence of them, so serializing can't fail.
+        serde_json::to_string_pretty(&self.snapshot()).unwrap_or_default()
+    }
+}
@@ -0,0 +1,76 @@
+use std::os::raw::c_void;
+
+#[cfg(feature = "stats")]
+use crate::guard::HookStats;
+
+/// Lightweight, copyable reference to a hook registered through a [`crate::guard::DetourGuard`].
+///
+/// Obtained through [`crate::guard::DetourGuard::handle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HookHandle {
+    target: *mut c_void,
+    original: *const *mut c_void,
+    #[cfg(feature = "stats")]
+    stats: *const HookStats,
+}
+
+impl HookHandle {
+    pub(crate) fn new(target: *mut c_void, original: *const *mut c_void) -> Self {
+        Self {
+            target,
+            original,
+            #[cfg(feature = "stats")]
+            stats: std::ptr::null(),
+        }
+    }
+
+    #[cfg(feature = "stats")]
+    pub(crate) fn with_stats(self, stats: Option<&'static HookStats>) -> Self {
+        Self {
+            stats: stats.map_or(std::ptr::null(), |stats| stats as *const HookStats),
+            ..self
+        }
+    }
+
+    /// The hooked function.
+    pub fn target(&self) -> *mut c_void {
+        self.target
+    }
+
+    /// The pointer to the original function, as filled in by the hooking engine.
+    ///
+    /// # Safety
+    ///
+    /// The handle doesn't borrow the [`crate::guard::DetourGuard`] it was obtained from, whose registry owns
+    /// the slot being read, so that guard must not have been dropped yet. Leaking it through
+    /// [`crate::guard::DetourGuard::into_raw`] keeps the slot alive. For handles built through
+    /// [`HookHandle::from_raw`], the slot must still be alive.
+    pub unsafe fn original(&self) -> *mut c_void {
+        unsafe { *self.original }
+    }
+
+    /// Decompose the handle into the raw engine state: the target, and the slot holding the pointer to the
+    /// original function.
+    pub fn as_raw(&self) -> (*mut c_void, *const *mut c_void) {
+        (self.target, self.original)
+    }
+
+    /// Build a handle out of raw engine state, e.g. for hooks created by code talking to MinHook directly.
+    ///
+    /// Building the handle never reads `original`, see [`HookHandle::original`] for when it may be read.
+    ///
+    /// # Safety
+    ///
+    /// `original` must be the slot that was passed to `MH_CreateHook` for `target`.
+    pub unsafe fn from_raw(target: *mut c_void, original: *const *mut c_void) -> Self {
+        Self::new(target, original)
+    }
+
+    /// The number of times the detour was entered, if the hook was created through a
+    /// [`crate::detour::StaticDetour`].
+    #[cfg(feature = "stats")]
+    pub fn call_count(&self) -> Option<u64> {
+        // Statistics live in `static`-s, so they outlive any handle.
+        unsafe { self.stats.as_ref() }.map(HookStats::call_count)
+    }
+}
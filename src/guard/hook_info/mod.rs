@@ -0,0 +1,100 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{self, Display, Formatter},
+    os::raw::c_void,
+};
+
+use crate::logging::Address;
+
+/// Bookkeeping of a hook registered through a [`crate::guard::DetourGuard`].
+///
+/// With the `serde` feature, addresses are (de)serialized as plain integers. Displayed as e.g.
+/// `` `present` in `overlay`: 0x7ffd1234abcd -> 0x7ffd4321dcba, enabled ``, with the addresses as
+/// `d3d11.dll!D3D11CreateDevice+0x40 (0x7ffd1234abcd)` with the `symbol` feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HookInfo {
+    #[cfg_attr(feature = "serde", serde(with = "address"))]
+    pub(crate) target: *mut c_void,
+    #[cfg_attr(feature = "serde", serde(with = "address"))]
+    pub(crate) detour: *mut c_void,
+    pub(crate) enabled: bool,
+    pub(crate) name: Option<String>,
+    pub(crate) group: Option<String>,
+}
+
+impl HookInfo {
+    pub(crate) fn new(target: *mut c_void, detour: *mut c_void) -> Self {
+        Self {
+            target,
+            detour,
+            enabled: false,
+            name: None,
+            group: None,
+        }
+    }
+
+    /// The hooked function.
+    pub fn target(&self) -> *mut c_void {
+        self.target
+    }
+
+    /// The place where the function jumps to, while hooked.
+    pub fn detour(&self) -> *mut c_void {
+        self.detour
+    }
+
+    /// Whether the hook is currently enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// The human-readable name of the hook, if any was assigned.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The group the hook belongs to, if any was assigned.
+    pub fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+}
+
+impl Display for HookInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match (&self.name, &self.group) {
+            (Some(name), Some(group)) => write!(f, "`{name}` in `{group}`: ")?,
+            (Some(name), None) => write!(f, "`{name}`: ")?,
+            (None, Some(group)) => write!(f, "in `{group}`: ")?,
+            (None, None) => {}
+        }
+
+        let state = if self.enabled { "enabled" } else { "disabled" };
+        write!(
+            f,
+            "{} -> {}, {state}",
+            Address(self.target),
+            Address(self.detour)
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+mod address {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::os::raw::c_void;
+
+    pub(super) fn serialize<S: Serializer>(
+        address: &*mut c_void,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(*address as usize as u64)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<*mut c_void, D::Error> {
+        Ok(u64::deserialize(deserializer)? as usize as *mut c_void)
+    }
+}
@@ -0,0 +1,65 @@
+use libloading::{Library, Symbol};
+use std::os::raw::c_void;
+
+use crate::{
+    error::{Error, ErrorContext, Result},
+    guard::DetourGuard,
+    logging,
+};
+
+impl<'a> DetourGuard<'a> {
+    /// Resolves `symbol` out of `library`, and registers entry for it in the hooking engine's internal
+    /// registry, for libraries managed through [`libloading`].
+    ///
+    /// The hook is removed on its own once `library` is unloaded, e.g. when it's dropped.
+    ///
+    /// Refer to [`DetourGuard::create_hook`] for further explaination.
+    ///
+    /// # Arguments
+    ///
+    /// * `library` - The library exporting `symbol`.
+    /// * `symbol` - The name of the export, e.g. `"plugin_update"`.
+    /// * `detour` - The place where the function will jump to, while hooked.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok((*mut c_void, &T))` with the resolved target, if the hook was succesfully registered. The
+    ///   lifetime of the reference is the lifetime of the [`DetourGuard`].
+    /// - `Err(minhook_detours_rs::error::Error::InvalidExport)` if `library` doesn't export `symbol`.
+    /// - `Err(minhook_detours_rs::error::Error)` if the operation failed.
+    pub fn create_hook_in_library<T>(
+        &mut self,
+        library: &Library,
+        symbol: &str,
+        detour: *mut c_void,
+    ) -> Result<(*mut c_void, &'a T)> {
+        let _span = logging::span!("create_hook_in_library", symbol = symbol);
+
+        let context = || ErrorContext {
+            symbol: Some(symbol.to_string()),
+            ..Default::default()
+        };
+
+        let target = resolve(library, symbol).map_err(|error| error.with_context(context()))?;
+        let original = self
+            .create_hook(target, detour)
+            .map_err(|error| error.with_context(context()))?;
+
+        // We succesfully registered the hook!
+        logging::debug!("Resolved {symbol} through libloading to {target:p}");
+        Ok((target, original))
+    }
+}
+
+/// Resolves the address of `symbol` out of `library`.
+pub(crate) fn resolve(library: &Library, symbol: &str) -> Result<*mut c_void> {
+    // Only the address is read, so the type of the symbol doesn't matter.
+    let address: Symbol<*mut c_void> =
+        unsafe { library.get(symbol.as_bytes()) }.map_err(|_| Error::InvalidExport)?;
+
+    if address.is_null() {
+        return Err(Error::InvalidExport);
+    }
+
+    Ok(*address)
+}
@@ -0,0 +1,34 @@
+use winapi::um::{processthreadsapi::GetCurrentThreadId, winnt::RTL_CRITICAL_SECTION};
+
+use crate::{arch, guard::DetourGuard};
+
+/// The offset of `LoaderLock` in the Process Environment Block.
+#[cfg(target_pointer_width = "64")]
+const LOADER_LOCK_OFFSET: usize = 0x110;
+#[cfg(target_pointer_width = "32")]
+const LOADER_LOCK_OFFSET: usize = 0xA0;
+
+impl<'a> DetourGuard<'a> {
+    /// Whether the current thread holds the loader lock, e.g. from `DllMain`, or a TLS callback.
+    ///
+    /// Meanwhile, threads can't start, nor exit, and modules can't be loaded, so the [`DetourGuard`] keeps
+    /// toggles on the current thread, see [`DetourGuard::enable_hook_async`], and operations that would load
+    /// a module fail with [`crate::error::Error::LoaderLock`] instead of deadlocking. Freezing threads doesn't
+    /// involve the loader, so the [`crate::guard::ThreadFreezeMethod`] is kept.
+    pub fn is_loader_lock_held() -> bool {
+        let peb = arch::peb();
+        if peb.is_null() {
+            return false;
+        }
+
+        unsafe {
+            let lock = peb
+                .add(LOADER_LOCK_OFFSET)
+                .cast::<*const RTL_CRITICAL_SECTION>()
+                .read();
+
+            // `OwningThread` holds the identifier of the owner, rather than a handle to it.
+            !lock.is_null() && (*lock).OwningThread as usize == GetCurrentThreadId() as usize
+        }
+    }
+}
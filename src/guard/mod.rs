@@ -2,20 +2,83 @@
 //!
 //! Responsible for instanciating MinHook engine, initializing it, and de-initializing it upon end.
 
-use minhook_detours_sys::{
-    MH_CreateHook, MH_DisableHook, MH_EnableHook, MH_Initialize, MH_OK, MH_SetThreadFreezeMethod,
-    MH_Uninitialize,
+use minhook_detours_sys::{MH_ERROR_ALREADY_CREATED, MH_ERROR_ALREADY_INITIALIZED, MH_OK};
+use std::{
+    collections::BTreeSet,
+    marker::PhantomData,
+    ops::Drop,
+    os::raw::c_void,
+    sync::{Arc, Mutex},
 };
-use std::{collections::LinkedList, marker::PhantomData, ops::Drop, os::raw::c_void};
 
 use crate::{
-    error::{Error, Result},
-    guard::thread_freeze::ThreadFreezeMethod,
+    arch,
+    detour::ContextSlot,
+    engine::Engine,
+    error::{Error, ErrorContext, ExistingHook, Operation, Result},
+    guard::{
+        background::Background,
+        observer::Observer,
+        rebind::{Indirection, StubPool},
+        store::HookStore,
+        thread_freeze::Freezer,
+        unload::Tracked,
+        worker::Worker,
+    },
+    logging,
+    mem::Patch,
+    target::Target,
 };
 
+mod background;
+mod bypass;
+mod config;
+mod critical;
+mod dependency;
+mod dump;
+mod handle;
+mod hook_info;
+#[cfg(feature = "libloading")]
+mod library;
+mod loader_lock;
+mod observer;
+mod patches;
+mod provider;
+mod rebind;
+mod reload;
+mod scoped;
+mod state;
+#[cfg(feature = "stats")]
+mod stats;
+mod store;
+mod switch;
 mod thread_freeze;
+mod unload;
+#[cfg(feature = "window")]
+mod window;
+mod worker;
 
-/// Can be used with [`MH_EnableHook`], ...
+pub use config::HookConfig;
+pub use critical::CriticalPolicy;
+pub use dump::{GuardSnapshot, HookSnapshot};
+pub use handle::HookHandle;
+pub use hook_info::HookInfo;
+pub use observer::{HookEvent, HookObserver};
+pub use provider::HookProvider;
+pub use rebind::StubPlacement;
+pub use scoped::ScopedDisable;
+pub use state::{GuardState, RetryPolicy};
+#[cfg(feature = "stats")]
+pub use stats::HookStats;
+#[cfg(feature = "timing")]
+pub use stats::Timing;
+pub use switch::HookSwitch;
+pub use thread_freeze::{ThreadFreezeMethod, ThreadFreezer};
+pub use worker::WorkerSink;
+#[cfg(feature = "stats")]
+pub use worker::WorkerStats;
+
+/// Can be used with `MH_EnableHook`, ...
 const MH_ALL_HOOKS: *mut c_void = std::ptr::null_mut();
 
 /// [`DetourGuard`] is the structure responsible for initializing, and deinitializing the
@@ -25,42 +88,240 @@ const MH_ALL_HOOKS: *mut c_void = std::ptr::null_mut();
 /// otherwise it's going to return an error.
 #[derive(Debug)]
 pub struct DetourGuard<'a> {
-    original_pointers: LinkedList<*mut c_void>,
+    hooks: HookStore,
+    stub_pool: StubPool,
+    background: Background,
+    worker: Option<Worker>,
+    observer: Option<Observer>,
+    engine: Engine,
+    thread_freeze_method: ThreadFreezeMethod,
+    excluded_threads: BTreeSet<u32>,
+    freezer: Option<Freezer>,
+    suspended: Vec<*mut c_void>,
+    patches: Vec<Patch>,
+    #[cfg(feature = "window")]
+    window_hooks: Vec<crate::window::WndProcHook>,
+    /// The modules owning the hooked targets, see [`DetourGuard::prune_unloaded`].
+    tracked: Arc<Mutex<Tracked>>,
+    /// `(dependent, dependency)` pairs, see [`DetourGuard::add_dependency`].
+    dependencies: Vec<(*mut c_void, *mut c_void)>,
+    /// The targets switched off by [`DetourGuard::set_bypass_all`], while bypassing.
+    bypassed: Option<Vec<*mut c_void>>,
+    bypass_when_debugged: bool,
+    stub_placement: StubPlacement,
+    critical_policy: CriticalPolicy,
+    state: GuardState,
+    owns_engine: bool,
     _phantom_data: PhantomData<&'a ()>,
 }
 
+/// Entry of the [`DetourGuard`]'s registry.
+///
+/// Entries of removed hooks are kept around, unless dropped through [`HookStore::forget`], whose slot for
+/// `original` outlives them either way, as references to it may still be alive.
+#[derive(Debug)]
+struct HookEntry {
+    info: HookInfo,
+    /// The slot holding the pointer to the original function, owned by the [`HookStore`].
+    original: *mut *mut c_void,
+    removed: bool,
+    indirection: Option<Indirection>,
+    spec: Option<Target>,
+    /// Whether the hook was removed, as its module was unloaded.
+    unloaded: bool,
+    /// Whether toggling the hook skips thread freezing, see [`DetourGuard::set_freeze_free`].
+    freeze_free: bool,
+    /// Whether the detour is a shim generated by [`crate::static_detour`], which can be bypassed per thread.
+    thread_bypassable: bool,
+    /// The user data of the [`crate::detour::StaticDetour`] the hook was created through, detached once it's
+    /// removed.
+    context: Option<&'static ContextSlot>,
+    #[cfg(feature = "stats")]
+    stats: Option<&'static HookStats>,
+}
+
+impl HookEntry {
+    /// The detour the engine jumps to, which is the stub in front of the detour for rebindable hooks.
+    fn engine_detour(&self) -> *mut c_void {
+        self.indirection
+            .as_ref()
+            .map_or(self.info.detour, Indirection::code)
+    }
+
+    /// Marks the hook as removed, detaching its user data.
+    fn remove(&mut self) {
+        self.removed = true;
+
+        if let Some(context) = self.context.take() {
+            context.store(None);
+        }
+    }
+}
+
 impl<'a> DetourGuard<'a> {
     pub fn new() -> Result<Self> {
+        Self::with_engine(Engine::MinHook)
+    }
+
+    /// Construct a [`DetourGuard`] driving `engine`.
+    pub(crate) fn with_engine(engine: Engine) -> Result<Self> {
+        let _span = logging::span!("initialize");
+
         // Attempt to initialize MinHook engine.
-        let status = unsafe { MH_Initialize() };
+        let status = engine.initialize();
 
         // If the status is [`MH_OK`], return an instance of the [`DetourGuard`].
         if status == MH_OK {
-            return Ok(Self::default());
+            logging::info!("MinHook engine initialized");
+            let mut guard = Self::default();
+            guard.engine = engine;
+            return Ok(guard);
         }
 
         // If the `status` is not [`MH_OK`], return an error from it.
-        Err(Error::from(status))
+        Err(Error::from_operation(status, Operation::Initialize, None))
+    }
+
+    /// Construct a [`DetourGuard`], attaching to the engine if another component already initialized it.
+    ///
+    /// An attached [`DetourGuard`] doesn't own the engine: upon end, it only removes the hooks it created,
+    /// and leaves the engine initialized for its owner.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(DetourGuard)` owning the engine if it was initialized by this call, attached to it otherwise.
+    /// - `Err(minhook_detours_rs::error::Error)` if initializing the engine failed.
+    pub fn new_or_attach() -> Result<Self> {
+        let _span = logging::span!("new_or_attach");
+
+        let engine = Engine::MinHook;
+        let status = engine.initialize();
+
+        if status == MH_OK || status == MH_ERROR_ALREADY_INITIALIZED {
+            let mut guard = Self::default();
+            guard.owns_engine = status == MH_OK;
+
+            if guard.owns_engine {
+                logging::info!("MinHook engine initialized");
+            } else {
+                logging::info!("MinHook engine attached");
+            }
+            return Ok(guard);
+        }
+
+        Err(Error::from_operation(status, Operation::Initialize, None))
+    }
+
+    /// Whether the [`DetourGuard`] uninitializes the engine upon end, see [`DetourGuard::new_or_attach`].
+    pub fn owns_engine(&self) -> bool {
+        self.owns_engine
+    }
+
+    /// Construct a [`DetourGuard`] reporting its lifecycle to `observer`, starting with the engine's initialization.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `observer` - The receiver of the [`HookEvent`]-s, see [`DetourGuard::set_observer`].
+    pub fn with_observer(observer: impl HookObserver + 'static) -> Result<Self> {
+        let mut guard = Self::new()?;
+        guard.set_observer(observer);
+        guard.notify(HookEvent::EngineInitialized);
+        Ok(guard)
+    }
+
+    /// Build a [`DetourGuard`] around an engine that was already initialized, e.g. by code talking to MinHook directly.
+    ///
+    /// The [`DetourGuard`] takes ownership of the engine, and deinitializes it upon end. Hooks created before
+    /// are unknown to it, unless registered through [`DetourGuard::adopt_hook`].
+    ///
+    /// # Safety
+    ///
+    /// The engine must be initialized, and no other [`DetourGuard`] may own it.
+    pub unsafe fn from_raw() -> Self {
+        Self::default()
+    }
+
+    /// Consume [`DetourGuard`] without deinitializing the engine, handing its ownership over to the caller.
+    ///
+    /// References to original functions stay valid, as the [`DetourGuard`]'s registry is leaked.
+    pub fn into_raw(self) {
+        // Make sure destructor doesn't run.
+        std::mem::forget(self);
     }
 
     /// Attempt to do a graceful close of the [`DetourGuard`].
     ///
+    /// Upon failure, the [`DetourGuard`] is [`GuardState::Poisoned`], and closing can be retried. Closing an
+    /// already closed [`DetourGuard`] does nothing.
+    ///
     /// # Returns
     ///
     /// - `Ok(())` if the close was succesful.
     /// - `Err(minhook_detours_rs::error::Error)` if the deinitialization didn't succeed.
     pub fn try_close(&mut self) -> Result<()> {
+        let _span = logging::span!("uninitialize");
+
+        if self.state == GuardState::Closed {
+            return Ok(());
+        }
+
+        self.state = GuardState::Closing;
+
+        // Toggles mustn't run in the background past the engine.
+        self.join_background();
+
+        // Flushes whatever was logged so far, as nothing is left to flush it past the guard.
+        self.stop_worker();
+
+        // The engine belongs to someone else, so only clean up after ourselves.
+        if !self.owns_engine {
+            if let Err(error) = self.reset() {
+                self.state = GuardState::Poisoned;
+                return Err(error);
+            }
+
+            logging::info!("Detached from MinHook engine");
+            self.state = GuardState::Closed;
+            return Ok(());
+        }
+
+        // The engine tears hooks down in its own order, so do it latest first beforehand. Whatever fails
+        // is left to the engine.
+        if let Err(error) = self.unwind_hooks() {
+            logging::info!("Leaving hooks to the MinHook engine: {error}");
+        }
+
         // Also responsible for disabling all current hooks, and then removing them.
-        let status = unsafe { MH_Uninitialize() };
+        let status = self.patch(Engine::uninitialize);
 
         // If the status is [`MH_OK`], we succeeded in closing the guard.
         if status == MH_OK {
             // We succesfully disposed of ourselves!
-            return Ok(());
+            logging::info!("MinHook engine uninitialized");
+            self.state = GuardState::Closed;
+
+            // The engine forgot about every hook, and so should the registry.
+            for target in self.targets() {
+                self.untrack_module(target);
+            }
+            for hook in self.entries_mut() {
+                hook.info.enabled = false;
+                hook.remove();
+            }
+
+            self.notify(HookEvent::EngineUninitialized);
+
+            #[cfg(feature = "window")]
+            self.restore_window_hooks();
+
+            // The engine is gone either way, so patches that couldn't be restored are only reported.
+            return self.restore_patches();
         }
 
+        self.state = GuardState::Poisoned;
+
         // If the `status` is not [`MH_OK`], return an error from it.
-        Err(Error::from(status))
+        self.fail(Error::from_operation(status, Operation::Uninitialize, None))
     }
 
     /// Consume [`DetourGuard`] attempting to do a graceful close of the [`DetourGuard`].
@@ -87,14 +348,20 @@ impl<'a> DetourGuard<'a> {
         &mut self,
         thread_freeze_method: ThreadFreezeMethod,
     ) -> Result<()> {
-        let status = unsafe { MH_SetThreadFreezeMethod(thread_freeze_method.into()) };
+        self.ensure_usable()?;
+
+        // The background toggles pick up the method of the engine.
+        self.join_background();
+        let status = self.engine.set_thread_freeze_method(thread_freeze_method);
 
         if status == MH_OK {
             // We succesfully changed the method!
+            logging::debug!("Thread freeze method set to {thread_freeze_method:?}");
+            self.thread_freeze_method = thread_freeze_method;
             return Ok(());
         }
 
-        Err(Error::from(status))
+        self.fail(Error::from_operation(status, Operation::SetThreadFreezeMethod, None))
     }
 
     /// Registers entry for our `target` in the hooking engine's internal registry.
@@ -111,25 +378,103 @@ impl<'a> DetourGuard<'a> {
     /// - `Ok(&T)` if the hook was succesfully registered. The lifetime of the reference is the lifetime of the [`DetourGuard`].
     /// - `Err(minhook_detours_rs::error::Error)` if the operation failed.
     pub fn create_hook<T>(&mut self, target: *mut c_void, detour: *mut c_void) -> Result<&'a T> {
-        // The `original` pointer must live as long as the [`DetourGuard`].
-        self.original_pointers.push_back(std::ptr::null_mut());
+        let _span = logging::span!("create_hook", target = ?target, detour = ?detour);
+        self.ensure_usable()?;
 
-        // Get `original`.
-        let original = self.original_pointers.back_mut().unwrap();
+        // The engine patches x64 code, and would fail obscurely on the ARM64 half of an Arm64EC process.
+        if arch::is_arm64ec_code(target) {
+            return Err(Error::Arm64EcCode);
+        }
+
+        // Hooking what the crate, or the engine rely on is likely to deadlock, or recurse.
+        self.check_critical(target)?;
+
+        // The engine may still hold the hook of a module unloaded from this very address.
+        self.prune_unloaded();
 
-        // Cast to pointer.
-        let original = original as *mut *mut c_void;
+        // The `original` pointer must live as long as the [`DetourGuard`], so the store provides its slot.
+        let entry = self.hooks.push(HookEntry {
+            info: HookInfo::new(target, detour),
+            original: std::ptr::null_mut(),
+            removed: false,
+            indirection: None,
+            spec: None,
+            unloaded: false,
+            freeze_free: false,
+            thread_bypassable: false,
+            context: None,
+            #[cfg(feature = "stats")]
+            stats: None,
+        });
+
+        // Get `original`.
+        let original = entry.original;
 
         // Only responsible for registering a hook in the engine's structure, but does nothing
         // without the hook being enabled. Refer to [`DetourGuard::enable_hook`].
-        let status = unsafe { MH_CreateHook(target as _, detour as _, original as _) };
+        let status = unsafe { self.engine.create_hook(target, detour, original) };
 
         if status == MH_OK {
             // We succesfully registered a hook!
+            logging::debug!(
+                "Created hook for {}, detouring to {}",
+                logging::Address(target),
+                logging::Address(detour)
+            );
+            self.track_module(target);
+            self.notify(HookEvent::Created { target, detour });
             return Ok(unsafe { (original as *mut T).as_ref().unwrap() });
         }
 
-        Err(Error::from(status))
+        // The hook was never registered, so it shouldn't be part of the registry either.
+        self.hooks.pop();
+
+        let mut error = Error::from_operation(status, Operation::CreateHook, Some(target));
+        if status == MH_ERROR_ALREADY_CREATED {
+            error = self.with_existing(error, target);
+        }
+
+        self.fail(error)
+    }
+
+    /// Resolves `target`, and registers entry for it in the hooking engine's internal registry.
+    /// 
+    /// This action is inert without being combined with [`DetourGuard::enable_hook`], or [`DetourGuard::enable_all_hooks`].
+    /// 
+    /// # Arguments
+    /// 
+    /// * `target` - The description of the function to be hooked, e.g. `"kernel32.dll!CreateFileW+0x40".parse()?`.
+    /// * `detour` - The place where the function will jump to, while hooked.
+    /// 
+    /// # Returns
+    /// 
+    /// - `Ok((*mut c_void, &T))` with the resolved target, if the hook was succesfully registered. The lifetime of the reference is the lifetime of the [`DetourGuard`].
+    /// - `Err(minhook_detours_rs::error::Error)` if the resolution, or the operation failed.
+    pub fn create_hook_at<T>(
+        &mut self,
+        target: &Target,
+        detour: *mut c_void,
+    ) -> Result<(*mut c_void, &'a T)> {
+        let _span = logging::span!("create_hook_at", symbol = %target);
+
+        // Keep the human-readable description around, as the address alone says little.
+        let context = || ErrorContext {
+            symbol: Some(target.to_string()),
+            ..Default::default()
+        };
+
+        let spec = target;
+        let target = spec
+            .resolve()
+            .map_err(|error| error.with_context(context()))?;
+        let original = self
+            .create_hook(target, detour)
+            .map_err(|error| error.with_context(context()))?;
+
+        // Keep the description, so that the hook can follow its module across reloads.
+        self.entry_mut(target).unwrap().spec = Some(spec.clone());
+
+        Ok((target, original))
     }
 
     /// Registers entry for our `target` in the hooking engine's internal registry, and immediately enables it.
@@ -153,12 +498,45 @@ impl<'a> DetourGuard<'a> {
         Ok(result)
     }
 
+    /// Registers entry for our `target` in the hooking engine's internal registry, unless the [`DetourGuard`]
+    /// already has one, so that initialization code can safely run again, e.g. after a hot-reload.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `target` - The function to be hooked.
+    /// * `detour` - The place where the function will jump to, while hooked.
+    /// 
+    /// # Returns
+    /// 
+    /// - `Ok(&T)` with the original of the existing hook, if it detours to `detour`, or of the newly registered one otherwise. The lifetime of the reference is the lifetime of the [`DetourGuard`].
+    /// - `Err(minhook_detours_rs::error::Error::AlreadyCreated)` if the existing hook detours elsewhere.
+    /// - `Err(minhook_detours_rs::error::Error)` if the operation failed.
+    pub fn create_or_get_hook<T>(
+        &mut self,
+        target: *mut c_void,
+        detour: *mut c_void,
+    ) -> Result<&'a T> {
+        let Some(hook) = self.entries_mut().find(|hook| hook.info.target == target) else {
+            return self.create_hook(target, detour);
+        };
+
+        if hook.info.detour != detour {
+            return Err(self.with_existing(Error::AlreadyCreated, target));
+        }
+
+        // We succesfully found the hook!
+        Ok(unsafe { (hook.original as *mut T).as_ref().unwrap() })
+    }
+
     /// Looks for `target` in hooking engine internal registry, and enables the hook attached to it.
     /// 
     /// # Arguments
     /// 
     /// * `target` - The function to be hooked.
     pub fn enable_hook(&mut self, target: *mut c_void) -> Result<()> {
+        let _span = logging::span!("enable_hook", target = ?target);
+        self.ensure_usable()?;
+
         // Although it would be a valid API usage, you should instead refer to
         // [`DetourGuard::enable_all_hooks`] to not introduce multiple ways of
         // achieving the same goal.
@@ -166,26 +544,37 @@ impl<'a> DetourGuard<'a> {
             return Err(Error::InvalidTarget);
         }
 
-        let status = unsafe { MH_EnableHook(target) };
+        self.enable_dependencies(target)?;
+
+        let status = self.patch_hook(target, |engine| engine.enable_hook(target));
 
         if status == MH_OK {
             // We succesfully enabled a hook!
+            logging::debug!("Enabled hook for {}", logging::Address(target));
+            self.set_enabled(target, true);
             return Ok(());
         }
 
-        Err(Error::from(status))
+        self.fail(Error::from_operation(status, Operation::EnableHook, Some(target)))
     }
 
     /// Goes through every entry in the hooking engine's internal registry, and enables all of them.
     pub fn enable_all_hooks(&mut self) -> Result<()> {
-        let status = unsafe { MH_EnableHook(MH_ALL_HOOKS) };
+        let _span = logging::span!("enable_all_hooks");
+        self.ensure_usable()?;
+
+        let status = self.patch(|engine| engine.enable_hook(MH_ALL_HOOKS));
 
         if status == MH_OK {
             // We succesfully enabled all hooks!
+            logging::debug!("Enabled all hooks");
+            for target in self.targets() {
+                self.set_enabled(target, true);
+            }
             return Ok(());
         }
 
-        Err(Error::from(status))
+        self.fail(Error::from_operation(status, Operation::EnableHook, None))
     }
 
     /// Looks for `target` in hooking engine internal registry, and disables the hook attached to it.
@@ -194,6 +583,9 @@ impl<'a> DetourGuard<'a> {
     /// 
     /// * `target` - The function to be un-hooked.
     pub fn disable_hook(&mut self, target: *mut c_void) -> Result<()> {
+        let _span = logging::span!("disable_hook", target = ?target);
+        self.ensure_usable()?;
+
         // Although it would be a valid API usage, you should instead refer to
         // [`DetourGuard::disable_all_hooks`] to not introduce multiple ways of
         // achieving the same goal.
@@ -201,33 +593,563 @@ impl<'a> DetourGuard<'a> {
             return Err(Error::InvalidTarget);
         }
 
-        let status = unsafe { MH_DisableHook(target) };
+        self.disable_dependents(target)?;
+
+        let status = self.patch_hook(target, |engine| engine.disable_hook(target));
 
         if status == MH_OK {
             // We succesfully disabled a hook!
+            logging::debug!("Disabled hook for {}", logging::Address(target));
+            self.set_enabled(target, false);
             return Ok(());
         }
 
-        Err(Error::from(status))
+        self.fail(Error::from_operation(status, Operation::DisableHook, Some(target)))
     }
 
     /// Goes through every entry in the hooking engine's internal registry, and disables all of them.
     pub fn disable_all_hooks(&mut self) -> Result<()> {
-        let status = unsafe { MH_DisableHook(MH_ALL_HOOKS) };
+        let _span = logging::span!("disable_all_hooks");
+        self.ensure_usable()?;
+
+        let status = self.patch(|engine| engine.disable_hook(MH_ALL_HOOKS));
 
         if status == MH_OK {
             // We succesfully disabled all hooks!
+            logging::debug!("Disabled all hooks");
+            for target in self.targets() {
+                self.set_enabled(target, false);
+            }
             return Ok(());
         }
 
-        Err(Error::from(status))
+        self.fail(Error::from_operation(status, Operation::DisableHook, None))
+    }
+
+    /// Looks for `target` in hooking engine internal registry, and removes the hook attached to it, disabling it first if needed.
+    /// 
+    /// The reference to the original function that was returned upon creation stays valid, but must no longer be called.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `target` - The function to be un-hooked.
+    pub fn remove_hook(&mut self, target: *mut c_void) -> Result<()> {
+        let _span = logging::span!("remove_hook", target = ?target);
+        self.ensure_usable()?;
+
+        if target.is_null() {
+            return Err(Error::InvalidTarget);
+        }
+
+        let status = self.patch(|engine| engine.remove_hook(target));
+
+        if status == MH_OK {
+            // We succesfully removed a hook!
+            logging::debug!("Removed hook for {}", logging::Address(target));
+            self.set_enabled(target, false);
+            if let Some(hook) = self.entry_mut(target) {
+                hook.remove();
+            }
+            self.untrack_module(target);
+            self.forget_dependencies(target);
+            self.notify(HookEvent::Removed { target });
+            return Ok(());
+        }
+
+        self.fail(Error::from_operation(status, Operation::RemoveHook, Some(target)))
+    }
+
+    /// Registers, and enables a hook for every `(target, detour)` of `hooks`, enabling them in a single
+    /// transaction. Either every hook is applied, or none is: upon failure, the hooks that were already
+    /// registered are removed.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `hooks` - The functions to be hooked, along with the places where they jump to, while hooked.
+    /// 
+    /// # Returns
+    /// 
+    /// - `Ok(Vec<*mut c_void>)` with the original functions, in the order of `hooks`, if every hook was succesfully applied.
+    /// - `Err(minhook_detours_rs::error::Error::BatchFailed)` with the index of the entry that failed otherwise.
+    pub fn create_and_enable_hooks(
+        &mut self,
+        hooks: &[(*mut c_void, *mut c_void)],
+    ) -> Result<Vec<*mut c_void>> {
+        let _span = logging::span!("create_and_enable_hooks", count = hooks.len());
+
+        let mut originals = Vec::with_capacity(hooks.len());
+
+        for (index, (target, detour)) in hooks.iter().enumerate() {
+            match self.create_hook::<*mut c_void>(*target, *detour) {
+                Ok(original) => originals.push(*original),
+                Err(error) => {
+                    self.remove_batch(&hooks[..index]);
+                    return Err(Error::BatchFailed {
+                        index: Some(index),
+                        source: Box::new(error),
+                    });
+                }
+            }
+        }
+
+        let targets = hooks.iter().map(|(target, _)| *target).collect::<Vec<_>>();
+
+        if let Err(error) = self.enable_hooks(&targets) {
+            // The transaction is all or nothing, so every hook is still disabled.
+            self.remove_batch(hooks);
+
+            let index = error.context().and_then(|context| context.target).and_then(|failed| {
+                targets
+                    .iter()
+                    .position(|target| *target as usize == failed)
+            });
+            return Err(Error::BatchFailed {
+                index,
+                source: Box::new(error),
+            });
+        }
+
+        // We succesfully applied every hook!
+        Ok(originals)
+    }
+
+    /// Removes the hooks of a batch that failed, on a best-effort basis.
+    fn remove_batch(&mut self, hooks: &[(*mut c_void, *mut c_void)]) {
+        for (target, _) in hooks {
+            let _ = self.remove_hook(*target);
+        }
+    }
+
+    /// Enables the hooks attached to every target of `targets` in a single transaction, so that threads are
+    /// only frozen once. Either every hook is enabled, or none is.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `targets` - The hooked functions.
+    pub fn enable_hooks(&mut self, targets: &[*mut c_void]) -> Result<()> {
+        if targets.is_empty() {
+            return Ok(());
+        }
+
+        self.apply_queued(targets, &[])
+    }
+
+    /// Disables the hooks attached to every target of `targets` in a single transaction, so that threads are
+    /// only frozen once. Either every hook is disabled, or none is.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `targets` - The hooked functions.
+    pub fn disable_hooks(&mut self, targets: &[*mut c_void]) -> Result<()> {
+        if targets.is_empty() {
+            return Ok(());
+        }
+
+        self.apply_queued(&[], targets)
+    }
+
+    /// Disables, and removes every hook, then restores every subclassed window, and [`Patch`], while leaving
+    /// the engine initialized, so that hooks can be created again from a clean slate.
+    /// 
+    /// Hooks are torn down in reverse creation order, see [`DetourGuard::hooks`].
+    /// 
+    /// References to original functions stay valid, as the [`DetourGuard`]'s registry keeps their storage.
+    pub fn reset(&mut self) -> Result<()> {
+        let _span = logging::span!("reset");
+
+        self.unwind_hooks()?;
+
+        #[cfg(feature = "window")]
+        self.restore_window_hooks();
+
+        // Patches are undone after the hooks, as they may have been applied around them.
+        self.restore_patches()?;
+
+        // We succesfully removed every hook!
+        logging::info!("Removed every hook, and patch");
+        Ok(())
+    }
+
+    /// Disables every enabled hook in a single transaction, remembering which ones were enabled, so that
+    /// [`DetourGuard::resume_all`] restores exactly those, unlike [`DetourGuard::enable_all_hooks`].
+    ///
+    /// Suspending again while suspended adds the hooks enabled meanwhile to the ones to restore.
+    pub fn suspend_all(&mut self) -> Result<()> {
+        let _span = logging::span!("suspend_all");
+
+        let enabled = self
+            .hooks()
+            .filter(|hook| hook.is_enabled())
+            .map(|hook| hook.target)
+            .collect::<Vec<_>>();
+        self.disable_hooks(&enabled)?;
+
+        // We succesfully suspended every hook!
+        logging::debug!("Suspended {} hooks", enabled.len());
+        self.suspended.extend(enabled);
+        Ok(())
+    }
+
+    /// Re-enables, in a single transaction, the hooks disabled by [`DetourGuard::suspend_all`] that are still
+    /// registered, and disabled.
+    pub fn resume_all(&mut self) -> Result<()> {
+        let _span = logging::span!("resume_all");
+
+        let suspended = self
+            .hooks()
+            .filter(|hook| !hook.is_enabled() && self.suspended.contains(&hook.target))
+            .map(|hook| hook.target)
+            .collect::<Vec<_>>();
+        self.enable_hooks(&suspended)?;
+
+        // We succesfully resumed the suspended hooks!
+        logging::debug!("Resumed {} hooks", suspended.len());
+        self.suspended.clear();
+        Ok(())
+    }
+
+    /// Whether hooks disabled by [`DetourGuard::suspend_all`] are waiting for [`DetourGuard::resume_all`].
+    pub fn is_suspended(&self) -> bool {
+        !self.suspended.is_empty()
+    }
+
+    /// Goes through every entry of the given `group`, and enables the ones that aren't enabled yet, in a
+    /// single transaction.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `group` - The group, as assigned by [`DetourGuard::set_hook_group`].
+    pub fn enable_group(&mut self, group: &str) -> Result<()> {
+        let targets = self.group_targets(group, false);
+        self.enable_hooks(&targets)
+    }
+
+    /// Goes through every entry of the given `group`, and disables the ones that are enabled, in a single
+    /// transaction.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `group` - The group, as assigned by [`DetourGuard::set_hook_group`].
+    pub fn disable_group(&mut self, group: &str) -> Result<()> {
+        let targets = self.group_targets(group, true);
+        self.disable_hooks(&targets)
+    }
+
+    /// Set the receiver of the [`HookEvent`]-s of the [`DetourGuard`], replacing the previous one.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `observer` - The receiver, e.g. a closure taking a [`HookEvent`].
+    pub fn set_observer(&mut self, observer: impl HookObserver + 'static) {
+        self.observer = Some(Observer(Box::new(observer)));
+    }
+
+    /// Assigns a human-readable name to the hook attached to `target`.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `target` - The hooked function.
+    /// * `name` - The name of the hook.
+    pub fn set_hook_name(&mut self, target: *mut c_void, name: impl Into<String>) -> Result<()> {
+        let hook = self.entry_mut(target).ok_or(Error::NotCreated)?;
+        hook.info.name = Some(name.into());
+        Ok(())
+    }
+
+    /// Assigns the hook attached to `target` to a group, which can then be toggled as a whole.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `target` - The hooked function.
+    /// * `group` - The name of the group.
+    pub fn set_hook_group(&mut self, target: *mut c_void, group: impl Into<String>) -> Result<()> {
+        let hook = self.entry_mut(target).ok_or(Error::NotCreated)?;
+        hook.info.group = Some(group.into());
+        Ok(())
+    }
+
+    /// Registers a hook that was created without going through the [`DetourGuard`], e.g. by code talking to MinHook directly.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `target` - The hooked function.
+    /// * `detour` - The place where the function jumps to, while hooked.
+    /// * `original` - The pointer to the original function, as filled in by `MH_CreateHook`.
+    /// * `enabled` - Whether the hook is currently enabled.
+    /// 
+    /// # Returns
+    /// 
+    /// - `Ok(&T)` if the hook was succesfully adopted. The lifetime of the reference is the lifetime of the [`DetourGuard`].
+    /// - `Err(minhook_detours_rs::error::Error::AlreadyCreated)` if `target` is already registered in the [`DetourGuard`].
+    /// 
+    /// # Safety
+    /// 
+    /// The hook must exist in the engine owned by the [`DetourGuard`], and match the given arguments.
+    pub unsafe fn adopt_hook<T>(
+        &mut self,
+        target: *mut c_void,
+        detour: *mut c_void,
+        original: *mut c_void,
+        enabled: bool,
+    ) -> Result<&'a T> {
+        self.ensure_usable()?;
+
+        if self.hook_info(target).is_some() {
+            return Err(self.with_existing(Error::AlreadyCreated, target));
+        }
+
+        let mut info = HookInfo::new(target, detour);
+        info.enabled = enabled;
+
+        let entry = self.hooks.push(HookEntry {
+            info,
+            original: std::ptr::null_mut(),
+            removed: false,
+            indirection: None,
+            spec: None,
+            unloaded: false,
+            freeze_free: false,
+            thread_bypassable: false,
+            context: None,
+            #[cfg(feature = "stats")]
+            stats: None,
+        });
+
+        let slot = entry.original;
+        unsafe { *slot = original };
+        self.track_module(target);
+        self.notify(HookEvent::Created { target, detour });
+
+        Ok(unsafe { (slot as *mut T).as_ref().unwrap() })
+    }
+
+    /// Looks for `target` in the [`DetourGuard`]'s registry, returning a copyable handle to it.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `target` - The hooked function.
+    pub fn handle(&self, target: *mut c_void) -> Option<HookHandle> {
+        self.hooks
+            .iter()
+            .find(|hook| !hook.removed && hook.info.target == target)
+            .map(|hook| {
+                let handle = HookHandle::new(target, hook.original);
+
+                #[cfg(feature = "stats")]
+                let handle = handle.with_stats(hook.stats);
+
+                handle
+            })
+    }
+
+    /// Looks for `target` in the [`DetourGuard`]'s registry.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `target` - The hooked function.
+    pub fn hook_info(&self, target: *mut c_void) -> Option<&HookInfo> {
+        self.hooks().find(|hook| hook.target == target)
+    }
+
+    /// Iterates over every hook registered through the [`DetourGuard`], in creation order.
+    ///
+    /// Hooks are torn down in the reverse order, by [`DetourGuard::reset`], and when closing, or dropping the
+    /// [`DetourGuard`], so that a hook layered over another, e.g. one hooking the detour of another, is
+    /// always disabled, and removed first. Dependencies declared through [`DetourGuard::add_dependency`]
+    /// take precedence.
+    pub fn hooks(&self) -> impl Iterator<Item = &HookInfo> {
+        self.hooks
+            .iter()
+            .filter(|hook| !hook.removed)
+            .map(|hook| &hook.info)
+    }
+
+    /// Disables every enabled hook in a single transaction, then removes every hook, latest first, and
+    /// dependents before their dependencies.
+    fn unwind_hooks(&mut self) -> Result<()> {
+        let targets = self.dependency_order();
+
+        // Disable everything at once, so that removing doesn't patch code one hook at a time.
+        let enabled = targets
+            .iter()
+            .rev()
+            .copied()
+            .filter(|target| self.hook_info(*target).is_some_and(HookInfo::is_enabled))
+            .collect::<Vec<_>>();
+        self.disable_hooks(&enabled)?;
+
+        for target in targets.into_iter().rev() {
+            self.remove_hook(target)?;
+        }
+
+        Ok(())
+    }
+
+    fn entries_mut(&mut self) -> impl Iterator<Item = &mut HookEntry> {
+        self.hooks.iter_mut().filter(|hook| !hook.removed)
+    }
+
+    fn entry_mut(&mut self, target: *mut c_void) -> Option<&mut HookEntry> {
+        self.entries_mut().find(|hook| hook.info.target == target)
+    }
+
+    /// Queues `enable`, and `disable` in the hooking engine, then applies them all in a single transaction.
+    pub(crate) fn apply_queued(
+        &mut self,
+        enable: &[*mut c_void],
+        disable: &[*mut c_void],
+    ) -> Result<()> {
+        let _span = logging::span!("transaction", enable = enable.len(), disable = disable.len());
+        self.ensure_usable()?;
+
+        let queued = enable
+            .iter()
+            .map(|target| (*target, true))
+            .chain(disable.iter().map(|target| (*target, false)))
+            .collect::<Vec<_>>();
+
+        for (index, (target, enabled)) in queued.iter().enumerate() {
+            if target.is_null() {
+                self.unqueue(&queued[..index]);
+                return Err(Error::InvalidTarget);
+            }
+
+            let status = if *enabled {
+                self.engine.queue_enable_hook(*target)
+            } else {
+                self.engine.queue_disable_hook(*target)
+            };
+
+            if status != MH_OK {
+                // Don't leave anything behind for the next transaction.
+                self.unqueue(&queued[..index]);
+
+                let operation = if *enabled {
+                    Operation::QueueEnableHook
+                } else {
+                    Operation::QueueDisableHook
+                };
+                return self.fail(Error::from_operation(status, operation, Some(*target)));
+            }
+        }
+
+        let status = self.patch(Engine::apply_queued);
+
+        if status == MH_OK {
+            // We succesfully applied the transaction!
+            logging::debug!(
+                "Applied queued transaction, enabling {} and disabling {} hooks",
+                enable.len(),
+                disable.len()
+            );
+            for (target, enabled) in queued {
+                self.set_enabled(target, enabled);
+            }
+            return Ok(());
+        }
+
+        self.fail(Error::from_operation(status, Operation::ApplyQueued, None))
+    }
+
+    /// Reverts already queued operations, by queueing their opposite.
+    fn unqueue(&self, queued: &[(*mut c_void, bool)]) {
+        for (target, enabled) in queued {
+            let _ = if *enabled {
+                self.engine.queue_disable_hook(*target)
+            } else {
+                self.engine.queue_enable_hook(*target)
+            };
+        }
+    }
+
+    /// Tracks the enabled state of `target`, reporting changes to the observer.
+    fn set_enabled(&mut self, target: *mut c_void, enabled: bool) {
+        let Some(hook) = self.entry_mut(target) else {
+            return;
+        };
+
+        if hook.info.enabled == enabled {
+            return;
+        }
+
+        hook.info.enabled = enabled;
+
+        self.notify(if enabled {
+            HookEvent::Enabled { target }
+        } else {
+            HookEvent::Disabled { target }
+        });
+    }
+
+    /// Collects the targets of every registered hook.
+    fn targets(&self) -> Vec<*mut c_void> {
+        self.hooks().map(|hook| hook.target).collect()
+    }
+
+    /// Reports `event` to the observer, if any.
+    fn notify(&mut self, event: HookEvent<'_>) {
+        #[cfg(feature = "hook-table")]
+        crate::table::record(&event);
+
+        if let Some(Observer(observer)) = &mut self.observer {
+            observer.on_event(&event);
+        }
+
+        #[cfg(feature = "stats")]
+        self.publish_stats();
+    }
+
+    /// Reports `error` to the observer, if any, and returns it.
+    fn fail<T>(&mut self, error: Error) -> Result<T> {
+        self.notify(HookEvent::Failed { error: &error });
+        Err(error)
+    }
+
+    /// Attaches the details of the hook registered for `target` to `error`, if any.
+    fn with_existing(&self, error: Error, target: *mut c_void) -> Error {
+        let Some(hook) = self.hook_info(target) else {
+            return error;
+        };
+
+        error.with_context(ErrorContext {
+            target: Some(target as usize),
+            existing: Some(ExistingHook {
+                detour: hook.detour as usize,
+                enabled: hook.enabled,
+                name: hook.name.clone(),
+                group: hook.group.clone(),
+            }),
+            ..Default::default()
+        })
+    }
+
+    /// Collects the targets of `group` whose enabled state is `enabled`.
+    fn group_targets(&self, group: &str, enabled: bool) -> Vec<*mut c_void> {
+        self.hooks()
+            .filter(|hook| hook.group() == Some(group) && hook.enabled == enabled)
+            .map(|hook| hook.target)
+            .collect()
     }
 }
 
 impl<'a> Drop for DetourGuard<'a> {
     fn drop(&mut self) {
+        if self.state == GuardState::Closed {
+            return;
+        }
+
+        let count = self.hooks().count();
+        if count > 0 {
+            self.notify(HookEvent::DroppedWithLiveHooks { count });
+        }
+
         if let Err(e) = self.try_close() {
-            eprintln!("DetourGuard drop failed: {e:?}");
+            logging::warn!("DetourGuard drop failed: {e}");
+        }
+
+        // Hooks that weren't removed, e.g. as the guard is poisoned, may still jump through their stubs.
+        if self.state != GuardState::Closed {
+            self.leak_live_stubs();
         }
     }
 }
@@ -235,7 +1157,27 @@ impl<'a> Drop for DetourGuard<'a> {
 impl<'a> Default for DetourGuard<'a> {
     fn default() -> Self {
         Self {
-            original_pointers: LinkedList::new(),
+            hooks: HookStore::default(),
+            stub_pool: StubPool::default(),
+            background: Background::default(),
+            worker: None,
+            observer: None,
+            engine: Engine::default(),
+            thread_freeze_method: ThreadFreezeMethod::Original,
+            excluded_threads: BTreeSet::new(),
+            freezer: None,
+            suspended: Vec::new(),
+            patches: Vec::new(),
+            #[cfg(feature = "window")]
+            window_hooks: Vec::new(),
+            tracked: Arc::default(),
+            dependencies: Vec::new(),
+            bypassed: None,
+            bypass_when_debugged: false,
+            stub_placement: StubPlacement::default(),
+            critical_policy: CriticalPolicy::default(),
+            state: GuardState::Initialized,
+            owns_engine: true,
             _phantom_data: Default::default(),
         }
     }
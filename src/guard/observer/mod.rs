@@ -0,0 +1,53 @@
+use std::{
+    fmt::{self, Debug, Formatter},
+    os::raw::c_void,
+};
+
+use crate::error::Error;
+
+/// Lifecycle event of the engine, or of a hook, reported to a [`HookObserver`].
+#[derive(Debug)]
+pub enum HookEvent<'e> {
+    /// The engine was initialized, see [`crate::guard::DetourGuard::with_observer`].
+    EngineInitialized,
+    /// The engine was uninitialized, disabling, and removing every hook.
+    EngineUninitialized,
+    /// A hook was registered.
+    Created {
+        target: *mut c_void,
+        detour: *mut c_void,
+    },
+    /// A hook was enabled, patching its target.
+    Enabled { target: *mut c_void },
+    /// A hook was disabled, restoring its target.
+    Disabled { target: *mut c_void },
+    /// A hook was removed.
+    Removed { target: *mut c_void },
+    /// An operation failed.
+    Failed { error: &'e Error },
+    /// The guard is being dropped while hooks are still registered.
+    DroppedWithLiveHooks { count: usize },
+}
+
+/// Receiver of the [`HookEvent`]-s of a [`crate::guard::DetourGuard`], e.g. to keep an audit trail of every
+/// patch applied to the process.
+///
+/// Implemented for closures taking a [`HookEvent`].
+pub trait HookObserver: Send {
+    fn on_event(&mut self, event: &HookEvent<'_>);
+}
+
+impl<F: FnMut(&HookEvent<'_>) + Send> HookObserver for F {
+    fn on_event(&mut self, event: &HookEvent<'_>) {
+        self(event)
+    }
+}
+
+/// Storage of the [`HookObserver`] of a [`crate::guard::DetourGuard`].
+pub(crate) struct Observer(pub(crate) Box<dyn HookObserver>);
+
+impl Debug for Observer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("Observer")
+    }
+}
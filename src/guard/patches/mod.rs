@@ -0,0 +1,95 @@
+use std::os::raw::c_void;
+
+use crate::{
+    error::{Error, Result},
+    guard::DetourGuard,
+    logging,
+    mem::Patch,
+};
+
+impl<'a> DetourGuard<'a> {
+    /// Writes `bytes` at `address`, keeping the [`Patch`] alongside the hooks, so that
+    /// [`DetourGuard::reset`], and closing the [`DetourGuard`] restore the original bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - Where to write the bytes.
+    /// * `bytes` - The bytes to write.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`Patch::apply`], for as long as the [`DetourGuard`] keeps the patch.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if the bytes were succesfully written.
+    /// - `Err(minhook_detours_rs::error::Error)` if the range isn't accessible, or couldn't be made writable.
+    pub unsafe fn apply_patch(&mut self, address: *mut c_void, bytes: &[u8]) -> Result<()> {
+        let _span = logging::span!("apply_patch", address = ?address, size = bytes.len());
+
+        let patch = unsafe { Patch::apply(address, bytes)? };
+        self.keep_patch(patch);
+
+        Ok(())
+    }
+
+    /// Keeps `patch` alongside the hooks, e.g. one built through [`Patch::nop`], so that
+    /// [`DetourGuard::reset`], and closing the [`DetourGuard`] restore its original bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `patch` - The applied patch.
+    pub fn keep_patch(&mut self, patch: Patch) {
+        self.patches.push(patch);
+    }
+
+    /// Restores the original bytes of the latest [`Patch`] applied at `address`, and forgets about it.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - Where the bytes were written.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if the original bytes were succesfully restored.
+    /// - `Err(minhook_detours_rs::error::Error::UnknownPatch)` if no patch was applied at `address`.
+    /// - `Err(minhook_detours_rs::error::Error)` if the range couldn't be made writable, in which case the
+    ///   patch is kept.
+    pub fn remove_patch(&mut self, address: *mut c_void) -> Result<()> {
+        let _span = logging::span!("remove_patch", address = ?address);
+
+        let Some(index) = self
+            .patches
+            .iter()
+            .rposition(|patch| patch.address() == address)
+        else {
+            return Err(Error::UnknownPatch(address as usize));
+        };
+
+        self.patches[index].restore()?;
+        self.patches.remove(index);
+
+        Ok(())
+    }
+
+    /// Iterates over every [`Patch`] applied through the [`DetourGuard`], in application order.
+    pub fn patches(&self) -> impl Iterator<Item = &Patch> {
+        self.patches.iter()
+    }
+
+    /// Restores every [`Patch`], latest first, so that overlapping ones are undone correctly.
+    ///
+    /// Patches that fail to be restored are kept, and the first failure is returned after trying the rest.
+    pub(crate) fn restore_patches(&mut self) -> Result<()> {
+        let mut result = Ok(());
+
+        for patch in self.patches.iter_mut().rev() {
+            if let Err(error) = patch.restore() {
+                result = result.and(Err(error));
+            }
+        }
+
+        self.patches.retain(Patch::is_applied);
+        result
+    }
+}
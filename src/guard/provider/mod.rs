@@ -0,0 +1,70 @@
+use crate::{
+    error::{Error, Result},
+    guard::DetourGuard,
+    logging,
+};
+
+/// Contributor of hooks to a [`DetourGuard`] it doesn't own, e.g. a crate of a workspace hooking its own
+/// concern, without knowing about the others. Installed through [`DetourGuard::install_providers`].
+///
+/// Implemented for closures taking the [`DetourGuard`].
+pub trait HookProvider {
+    /// Creates, and optionally enables the hooks of the provider in `guard`.
+    fn register(&self, guard: &mut DetourGuard<'_>) -> Result<()>;
+
+    /// The name of the provider, for diagnostics.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+impl<F: Fn(&mut DetourGuard<'_>) -> Result<()>> HookProvider for F {
+    fn register(&self, guard: &mut DetourGuard<'_>) -> Result<()> {
+        self(guard)
+    }
+}
+
+impl<'a> DetourGuard<'a> {
+    /// Registers the hooks of every provider of `providers`, in order. Either every provider is installed, or
+    /// none is: upon failure, the hooks created by the providers already run are removed, latest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `providers` - The providers, see [`HookProvider`].
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if every provider was succesfully installed.
+    /// - `Err(minhook_detours_rs::error::Error::BatchFailed)` with the index of the provider that failed
+    ///   otherwise.
+    pub fn install_providers(&mut self, providers: &[&dyn HookProvider]) -> Result<()> {
+        let _span = logging::span!("install_providers", count = providers.len());
+
+        let existing = self.targets();
+
+        for (index, provider) in providers.iter().enumerate() {
+            if let Err(error) = provider.register(self) {
+                logging::info!("Hook provider {} failed: {error}", provider.name());
+
+                let created = self
+                    .targets()
+                    .into_iter()
+                    .filter(|target| !existing.contains(target))
+                    .collect::<Vec<_>>();
+                for target in created.into_iter().rev() {
+                    let _ = self.remove_hook(target);
+                }
+
+                return Err(Error::BatchFailed {
+                    index: Some(index),
+                    source: Box::new(error),
+                });
+            }
+
+            logging::debug!("Installed hook provider {}", provider.name());
+        }
+
+        // We succesfully installed every provider!
+        Ok(())
+    }
+}
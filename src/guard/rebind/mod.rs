@@ -0,0 +1,292 @@
+use std::{
+    os::raw::c_void,
+    ptr::null_mut,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+use winapi::um::{
+    memoryapi::{VirtualAlloc, VirtualFree, VirtualProtect},
+    processthreadsapi::{FlushInstructionCache, GetCurrentProcess},
+    winnt::{MEM_COMMIT, MEM_RELEASE, MEM_RESERVE, PAGE_EXECUTE_READ, PAGE_READWRITE},
+};
+
+use crate::{
+    error::{Error, Result},
+    guard::DetourGuard,
+    logging,
+    mem,
+};
+
+mod pool;
+
+pub(crate) use pool::StubPool;
+
+/// The space reserved for the code of an [`Indirection`].
+const CODE_SIZE: usize = 32;
+
+/// The detour the engine jumps to for a rebindable hook: a stub jumping through `slot`, which can be swapped
+/// atomically while calls flow through it.
+#[derive(Debug)]
+pub(crate) struct Indirection {
+    code: *mut u8,
+    slot: Box<AtomicPtr<c_void>>,
+    /// The detour, which `slot` points at unless switched off through a [`crate::guard::HookSwitch`].
+    detour: Box<AtomicPtr<c_void>>,
+    /// Whether `code` belongs to the [`StubPool`], rather than to the [`Indirection`].
+    pooled: bool,
+}
+
+/// Where the stubs the crate places in front of detours are allocated, see
+/// [`DetourGuard::set_stub_placement`].
+///
+/// The trampolines of the engine itself are always allocated within +-2GiB of their target by the engine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StubPlacement {
+    /// Anywhere in the address space.
+    #[default]
+    Anywhere,
+    /// Within +-2GiB of the target, or anywhere if no region is free there.
+    PreferNearTarget,
+    /// Within +-2GiB of the target, failing with [`Error::FailedAllocatingMemory`] if no region is free there.
+    NearTarget,
+}
+
+impl Indirection {
+    fn new(
+        detour: *mut c_void,
+        target: *mut c_void,
+        placement: StubPlacement,
+        pool: &mut StubPool,
+    ) -> Result<Self> {
+        let slot = Box::new(AtomicPtr::new(detour));
+        let stub = stub(&*slot);
+        let detour = Box::new(AtomicPtr::new(detour));
+
+        // Reserved stubs are already allocated, and executable, so writing them only makes their page
+        // writable for the duration of the write.
+        let near = (placement != StubPlacement::Anywhere).then_some(target as *const c_void);
+        if let Some(code) = pool.take(near) {
+            unsafe { mem::write_bytes(code as _, &stub)? };
+            return Ok(Self {
+                code,
+                slot,
+                detour,
+                pooled: true,
+            });
+        }
+
+        let near = match placement {
+            StubPlacement::Anywhere => None,
+            _ => mem::alloc_near(target, CODE_SIZE).ok(),
+        };
+
+        let code = match near {
+            Some(code) => code as *mut u8,
+            None if placement == StubPlacement::NearTarget => {
+                return Err(Error::FailedAllocatingMemory);
+            }
+            None => unsafe {
+                VirtualAlloc(
+                    null_mut(),
+                    CODE_SIZE,
+                    MEM_COMMIT | MEM_RESERVE,
+                    PAGE_READWRITE,
+                ) as *mut u8
+            },
+        };
+        if code.is_null() {
+            return Err(Error::FailedAllocatingMemory);
+        }
+
+        unsafe { std::ptr::copy_nonoverlapping(stub.as_ptr(), code, stub.len()) };
+
+        let mut protection = 0;
+        let protected =
+            unsafe { VirtualProtect(code as _, CODE_SIZE, PAGE_EXECUTE_READ, &mut protection) };
+        if protected == 0 {
+            unsafe { VirtualFree(code as _, 0, MEM_RELEASE) };
+            return Err(Error::FailedAllocatingMemory);
+        }
+
+        unsafe { FlushInstructionCache(GetCurrentProcess(), code as _, CODE_SIZE) };
+
+        Ok(Self {
+            code,
+            slot,
+            detour,
+            pooled: false,
+        })
+    }
+
+    /// The address of the stub, used as the detour of the hook.
+    pub(crate) fn code(&self) -> *mut c_void {
+        self.code as _
+    }
+
+    /// The slot the stub jumps through, and the slot of the detour.
+    pub(crate) fn slots(&self) -> (&AtomicPtr<c_void>, &AtomicPtr<c_void>) {
+        (&self.slot, &self.detour)
+    }
+}
+
+impl Drop for Indirection {
+    fn drop(&mut self) {
+        if !self.pooled {
+            unsafe { VirtualFree(self.code as _, 0, MEM_RELEASE) };
+        }
+    }
+}
+
+/// `mov rax, slot; jmp [rax]`, as `rax` holds nothing at function entry.
+#[cfg(target_arch = "x86_64")]
+fn stub(slot: *const AtomicPtr<c_void>) -> Vec<u8> {
+    let mut code = vec![0x48, 0xB8];
+    code.extend_from_slice(&(slot as u64).to_le_bytes());
+    code.extend_from_slice(&[0xFF, 0x20]);
+    code
+}
+
+/// `jmp [slot]`.
+#[cfg(target_arch = "x86")]
+fn stub(slot: *const AtomicPtr<c_void>) -> Vec<u8> {
+    let mut code = vec![0xFF, 0x25];
+    code.extend_from_slice(&(slot as u32).to_le_bytes());
+    code
+}
+
+/// `ldr x16, #16; ldr x16, [x16]; br x16; nop`, followed by the address of `slot`, as `x16` is reserved for
+/// veneers.
+#[cfg(target_arch = "aarch64")]
+fn stub(slot: *const AtomicPtr<c_void>) -> Vec<u8> {
+    let mut code = [0x58000090u32, 0xF9400210, 0xD61F0200, 0xD503201F]
+        .iter()
+        .flat_map(|instruction| instruction.to_le_bytes())
+        .collect::<Vec<_>>();
+    code.extend_from_slice(&(slot as u64).to_le_bytes());
+    code
+}
+
+impl<'a> DetourGuard<'a> {
+    /// Registers entry for our `target` in the hooking engine's internal registry, through a stub whose
+    /// destination can later be swapped with [`DetourGuard::rebind`], without a window where calls flow
+    /// unhooked.
+    /// 
+    /// This action is inert without being combined with [`DetourGuard::enable_hook`], or [`DetourGuard::enable_all_hooks`].
+    /// 
+    /// # Arguments
+    /// 
+    /// * `target` - The function to be hooked.
+    /// * `detour` - The place where the function will jump to, while hooked.
+    /// 
+    /// # Returns
+    /// 
+    /// - `Ok(&T)` if the hook was succesfully registered. The lifetime of the reference is the lifetime of the [`DetourGuard`].
+    /// - `Err(minhook_detours_rs::error::Error)` if the operation failed.
+    pub fn create_rebindable_hook<T>(
+        &mut self,
+        target: *mut c_void,
+        detour: *mut c_void,
+    ) -> Result<&'a T> {
+        if detour.is_null() {
+            return Err(Error::InvalidTarget);
+        }
+
+        let indirection =
+            Indirection::new(detour, target, self.stub_placement, &mut self.stub_pool)?;
+        let original = self.create_hook(target, indirection.code())?;
+
+        // Report the actual detour, rather than the stub in front of it.
+        let hook = self.entry_mut(target).unwrap();
+        hook.info.detour = detour;
+        hook.indirection = Some(indirection);
+        self.bypass(target);
+
+        Ok(original)
+    }
+
+    /// Swaps the detour of a hook created through [`DetourGuard::create_rebindable_hook`], atomically, so that
+    /// every call goes either to the previous detour, or to `detour`, whether the hook is enabled or not.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `target` - The hooked function.
+    /// * `detour` - The place where the function will jump to from now on.
+    /// 
+    /// # Returns
+    /// 
+    /// - `Ok(())` if the detour was succesfully swapped.
+    /// - `Err(minhook_detours_rs::error::Error::NotCreated)` if no hook is registered for `target`.
+    /// - `Err(minhook_detours_rs::error::Error::NotRebindable)` if the hook wasn't created as rebindable.
+    pub fn rebind(&mut self, target: *mut c_void, detour: *mut c_void) -> Result<()> {
+        let _span = logging::span!("rebind", target = ?target, detour = ?detour);
+
+        if detour.is_null() {
+            return Err(Error::InvalidTarget);
+        }
+
+        let hook = self.entry_mut(target).ok_or(Error::NotCreated)?;
+        let indirection = hook.indirection.as_ref().ok_or(Error::NotRebindable)?;
+
+        let previous = indirection.detour.swap(detour, Ordering::AcqRel);
+
+        // A switched off hook keeps flowing to the original function.
+        let _ = indirection.slot.compare_exchange(
+            previous,
+            detour,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        );
+        hook.info.detour = detour;
+
+        // We succesfully rebound the hook!
+        logging::debug!("Rebound hook for {target:p} to {detour:p}");
+        Ok(())
+    }
+
+    /// Allocates executable memory for the stubs of `count` rebindable hooks up front, e.g. right after
+    /// construction, so that creating them later allocates no executable memory, nor searches for a region
+    /// near their target.
+    ///
+    /// Creating a hook still allocates the slots its stub jumps through, and briefly makes the page of its
+    /// stub writable, to write the stub. The trampolines of the engine are still allocated by the engine, in
+    /// blocks shared by the hooks near each other.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - The number of stubs to reserve.
+    /// * `near` - An address the stubs should be allocated near of, e.g. the base of the module the targets
+    ///   live in, so that they suit [`StubPlacement::NearTarget`]. `None` for anywhere.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if the stubs were succesfully reserved.
+    /// - `Err(minhook_detours_rs::error::Error::FailedAllocatingMemory)` otherwise.
+    pub fn reserve_stubs(&mut self, count: usize, near: Option<*const c_void>) -> Result<()> {
+        let _span = logging::span!("reserve_stubs", count = count);
+        self.stub_pool.reserve(count, near)
+    }
+
+    /// Where the stubs of rebindable hooks are allocated.
+    pub fn stub_placement(&self) -> StubPlacement {
+        self.stub_placement
+    }
+
+    /// Choose where the stubs of the rebindable hooks created from now on are allocated.
+    ///
+    /// # Arguments
+    ///
+    /// * `placement` - The placement, see [`StubPlacement`].
+    pub fn set_stub_placement(&mut self, placement: StubPlacement) {
+        self.stub_placement = placement;
+    }
+
+    /// Leaks the stubs of every hook the engine may still jump through, e.g. once closing the [`DetourGuard`]
+    /// failed, rather than freeing code that may still run.
+    pub(super) fn leak_live_stubs(&mut self) {
+        for hook in self.hooks.iter_mut().filter(|hook| !hook.removed) {
+            std::mem::forget(hook.indirection.take());
+        }
+
+        // Pooled stubs share their regions, so the pool goes as a whole.
+        std::mem::forget(std::mem::take(&mut self.stub_pool));
+    }
+}
@@ -0,0 +1,74 @@
+use std::{os::raw::c_void, ptr::null_mut};
+use winapi::um::{
+    memoryapi::{VirtualAlloc, VirtualFree, VirtualProtect},
+    winnt::{MEM_COMMIT, MEM_RELEASE, MEM_RESERVE, PAGE_EXECUTE_READ, PAGE_READWRITE},
+};
+
+use crate::{
+    error::{Error, Result},
+    guard::rebind::CODE_SIZE,
+    logging,
+    mem,
+};
+
+/// Executable memory for the stubs of rebindable hooks, allocated up front, see
+/// [`crate::guard::DetourGuard::reserve_stubs`].
+///
+/// Stubs are never handed back, as the entries of removed hooks, and so their stubs, live as long as the
+/// [`crate::guard::DetourGuard`].
+#[derive(Debug, Default)]
+pub(crate) struct StubPool {
+    regions: Vec<*mut c_void>,
+    free: Vec<*mut u8>,
+}
+
+impl StubPool {
+    /// Allocates `count` stubs, near `near` if given.
+    pub(crate) fn reserve(&mut self, count: usize, near: Option<*const c_void>) -> Result<()> {
+        if count == 0 {
+            return Ok(());
+        }
+
+        let size = count * CODE_SIZE;
+        let region = match near {
+            Some(address) => mem::alloc_near(address, size)?,
+            None => unsafe {
+                VirtualAlloc(null_mut(), size, MEM_COMMIT | MEM_RESERVE, PAGE_READWRITE)
+            },
+        };
+        if region.is_null() {
+            return Err(Error::FailedAllocatingMemory);
+        }
+
+        let mut protection = 0;
+        if unsafe { VirtualProtect(region, size, PAGE_EXECUTE_READ, &mut protection) } == 0 {
+            unsafe { VirtualFree(region, 0, MEM_RELEASE) };
+            return Err(Error::FailedAllocatingMemory);
+        }
+
+        self.regions.push(region);
+        let stubs = (0..count).map(|index| (region as *mut u8).wrapping_add(index * CODE_SIZE));
+        self.free.extend(stubs.rev());
+
+        // We succesfully reserved the stubs!
+        logging::debug!("Reserved {count} stubs at {region:p}");
+        Ok(())
+    }
+
+    /// Takes a reserved stub, near `near` if given.
+    pub(crate) fn take(&mut self, near: Option<*const c_void>) -> Option<*mut u8> {
+        let index = self.free.iter().rposition(|stub| {
+            near.is_none_or(|address| mem::is_near(*stub as *const c_void, address))
+        })?;
+
+        Some(self.free.remove(index))
+    }
+}
+
+impl Drop for StubPool {
+    fn drop(&mut self) {
+        for region in &self.regions {
+            unsafe { VirtualFree(*region, 0, MEM_RELEASE) };
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use minhook_detours_sys::MH_OK;
+use std::os::raw::c_void;
+
+use crate::{
+    error::{Error, ErrorContext, Operation, Result},
+    guard::{DetourGuard, HookEvent},
+    logging,
+};
+
+impl<'a> DetourGuard<'a> {
+    /// Re-installs the hooks created through [`DetourGuard::create_hook_at`] in `module`, after it was
+    /// unloaded, and loaded again, possibly at another base.
+    ///
+    /// Every hook is resolved again from its description, and re-created in place, so that the references to
+    /// the original functions handed out so far stay valid. Hooks that were enabled are enabled again.
+    ///
+    /// # Arguments
+    ///
+    /// * `module` - The name of the reloaded module, compared case-insensitively, e.g. `"plugin.dll"`.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(usize)` with the number of hooks that were succesfully re-installed.
+    /// - `Err(minhook_detours_rs::error::Error)` if a hook couldn't be resolved, or re-created, in which case
+    ///   the hooks before it stay re-installed.
+    pub fn rehook_module(&mut self, module: &str) -> Result<usize> {
+        let _span = logging::span!("rehook_module", module = module);
+        self.ensure_usable()?;
+        self.prune_unloaded();
+
+        // Hooks that were removed as the module was unloaded are brought back as well.
+        let stale = self
+            .hooks
+            .iter()
+            .enumerate()
+            .filter(|(_, hook)| !hook.removed || hook.unloaded)
+            .filter_map(|(index, hook)| Some((index, hook.unloaded, hook.spec.as_ref()?)))
+            .filter(|(_, _, spec)| {
+                spec.module()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(module))
+            })
+            .map(|(index, unloaded, spec)| (index, unloaded, spec.clone()))
+            .collect::<Vec<_>>();
+
+        for (index, unloaded, spec) in &stale {
+            let stale_target = self.hooks.iter().nth(*index).unwrap().info.target;
+
+            // The engine's entry refers to the previous instance, whose code may be gone. Removing it fails
+            // in that case, leaving an inert entry behind.
+            if !*unloaded {
+                let _ = self.patch(|engine| engine.remove_hook(stale_target));
+                self.untrack_module(stale_target);
+            }
+
+            let context = || ErrorContext {
+                symbol: Some(spec.to_string()),
+                ..Default::default()
+            };
+            let target = spec
+                .resolve()
+                .map_err(|error| error.with_context(context()))?;
+
+            let hook = self.hooks.iter_mut().nth(*index).unwrap();
+            let (detour, enabled) = (hook.engine_detour(), hook.info.enabled);
+            let original = hook.original;
+            hook.info.target = target;
+            hook.info.enabled = false;
+            hook.removed = false;
+            hook.unloaded = false;
+
+            let status = unsafe { self.engine.create_hook(target, detour, original) };
+            if status != MH_OK {
+                let error = Error::from_operation(status, Operation::CreateHook, Some(target));
+                return self.fail(error.with_context(context()));
+            }
+
+            self.track_module(target);
+            self.notify(HookEvent::Created { target, detour });
+
+            if enabled {
+                self.enable_hook(target)?;
+            }
+        }
+
+        // We succesfully re-installed the hooks!
+        logging::info!("Re-installed {} hooks in {module}", stale.len());
+        Ok(stale.len())
+    }
+}
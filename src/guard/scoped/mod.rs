@@ -0,0 +1,154 @@
+use std::{
+    ops::{Deref, DerefMut},
+    os::raw::c_void,
+};
+
+use crate::{
+    detour::{ContextSlot, bypass_detour_for_current_thread},
+    error::{Error, Result},
+    guard::DetourGuard,
+    logging,
+};
+
+/// A hook disabled for the lifetime of the value, re-enabled once it's dropped.
+///
+/// Obtained through [`DetourGuard::disable_scoped`]. The [`DetourGuard`] stays usable through it meanwhile.
+#[derive(Debug)]
+pub struct ScopedDisable<'g, 'a> {
+    guard: &'g mut DetourGuard<'a>,
+    target: *mut c_void,
+    restore: bool,
+}
+
+impl<'g, 'a> ScopedDisable<'g, 'a> {
+    /// The hook that is disabled.
+    pub fn target(&self) -> *mut c_void {
+        self.target
+    }
+
+    /// Re-enable the hook now, surfacing the error that dropping the value would only report.
+    pub fn restore(mut self) -> Result<()> {
+        self.restore_hook()
+    }
+
+    fn restore_hook(&mut self) -> Result<()> {
+        if !std::mem::take(&mut self.restore) {
+            return Ok(());
+        }
+
+        // The hook may have been removed in the meantime, through the guard.
+        if self.guard.hook_info(self.target).is_none() {
+            return Ok(());
+        }
+
+        self.guard.enable_hook(self.target)
+    }
+}
+
+impl<'g, 'a> Deref for ScopedDisable<'g, 'a> {
+    type Target = DetourGuard<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        self.guard
+    }
+}
+
+impl<'g, 'a> DerefMut for ScopedDisable<'g, 'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.guard
+    }
+}
+
+impl<'g, 'a> Drop for ScopedDisable<'g, 'a> {
+    fn drop(&mut self) {
+        if let Err(e) = self.restore_hook() {
+            logging::warn!("ScopedDisable drop failed: {e}");
+        }
+    }
+}
+
+impl<'a> DetourGuard<'a> {
+    /// Disables the hook attached to `target` until the returned value is dropped, e.g. for calling the real
+    /// function from maintenance code, whichever way it returns.
+    ///
+    /// A hook that was already disabled is left disabled afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The hooked function.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(ScopedDisable)` if the hook was succesfully disabled, or already was.
+    /// - `Err(minhook_detours_rs::error::Error::NotCreated)` if no hook is registered for `target`.
+    pub fn disable_scoped(&mut self, target: *mut c_void) -> Result<ScopedDisable<'_, 'a>> {
+        let enabled = self
+            .hook_info(target)
+            .map(|hook| hook.enabled)
+            .ok_or(Error::NotCreated)?;
+
+        if enabled {
+            self.disable_hook(target)?;
+        }
+
+        Ok(ScopedDisable {
+            guard: self,
+            target,
+            restore: enabled,
+        })
+    }
+
+    /// Disables the hook attached to `target`, runs `f`, and re-enables the hook, even if `f` panics.
+    ///
+    /// Hooks created through a [`crate::detour::StaticDetour`] are only bypassed on the calling thread, leaving
+    /// the hook untouched for every other one. Any other hook is disabled for every thread while `f` runs.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The hooked function.
+    /// * `f` - The code to run while the hook is disabled.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(R)` with the result of `f`, if the hook was succesfully disabled, and re-enabled.
+    /// - `Err(minhook_detours_rs::error::Error::NotCreated)` if no hook is registered for `target`.
+    pub fn with_hook_disabled<R>(
+        &mut self,
+        target: *mut c_void,
+        f: impl FnOnce() -> R,
+    ) -> Result<R> {
+        let bypassable = self
+            .entry_mut(target)
+            .filter(|hook| hook.thread_bypassable)
+            .map(|hook| hook.info.detour);
+
+        if let Some(detour) = bypassable {
+            // We succesfully ran `f` without the hook, without touching it for the other threads!
+            return Ok(bypass_detour_for_current_thread(detour, f));
+        }
+
+        let scoped = self.disable_scoped(target)?;
+
+        let result = f();
+
+        scoped.restore()?;
+
+        // We succesfully ran `f` without the hook!
+        Ok(result)
+    }
+
+    /// Marks the hook attached to `target` as bypassable per thread, see [`DetourGuard::with_hook_disabled`].
+    pub(crate) fn set_thread_bypassable(&mut self, target: *mut c_void) {
+        if let Some(hook) = self.entry_mut(target) {
+            hook.thread_bypassable = true;
+        }
+    }
+
+    /// Associates the user data in `context` with the hook attached to `target`, detaching it once the hook
+    /// is removed.
+    pub(crate) fn attach_context(&mut self, target: *mut c_void, context: &'static ContextSlot) {
+        if let Some(hook) = self.entry_mut(target) {
+            hook.context = Some(context);
+        }
+    }
+}
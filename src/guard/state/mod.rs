@@ -0,0 +1,86 @@
+use minhook_detours_sys::MH_OK;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Operation, Result},
+    guard::{DetourGuard, HookEvent},
+    logging,
+};
+
+mod retry;
+
+pub use retry::RetryPolicy;
+
+/// Lifecycle of a [`DetourGuard`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum GuardState {
+    /// The engine is initialized, and hooks can be operated on.
+    Initialized,
+    /// The engine is being uninitialized, e.g. as observed from a [`crate::guard::HookObserver`].
+    Closing,
+    /// The engine was uninitialized, and every hook removed. Refer to [`DetourGuard::recover`] to use the
+    /// [`DetourGuard`] again.
+    Closed,
+    /// Uninitializing the engine failed, so some hooks may be left behind. Refer to
+    /// [`DetourGuard::try_close`] to retry, or [`DetourGuard::recover`] to keep using the [`DetourGuard`].
+    ///
+    /// Every operation on the engine, other than closing, returns [`Error::Poisoned`] meanwhile.
+    Poisoned,
+}
+
+impl<'a> DetourGuard<'a> {
+    /// The current state of the [`DetourGuard`]'s lifecycle.
+    pub fn state(&self) -> GuardState {
+        self.state
+    }
+
+    /// Bring the [`DetourGuard`] back to [`GuardState::Initialized`].
+    ///
+    /// A poisoned [`DetourGuard`] keeps operating on whatever hooks are left, as the engine is still
+    /// initialized. A closed [`DetourGuard`] initializes the engine again, from a clean slate.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if the [`DetourGuard`] is succesfully initialized.
+    /// - `Err(minhook_detours_rs::error::Error)` if initializing the engine again failed.
+    pub fn recover(&mut self) -> Result<()> {
+        let _span = logging::span!("recover", state = ?self.state);
+
+        match self.state {
+            GuardState::Initialized | GuardState::Closing => Ok(()),
+            GuardState::Poisoned => {
+                logging::info!("Recovered poisoned guard");
+                self.state = GuardState::Initialized;
+                Ok(())
+            }
+            GuardState::Closed if !self.owns_engine => {
+                // The engine was left initialized for its owner.
+                self.state = GuardState::Initialized;
+                Ok(())
+            }
+            GuardState::Closed => {
+                let status = self.engine.initialize();
+
+                if status == MH_OK {
+                    // We succesfully brought ourselves back!
+                    logging::info!("MinHook engine initialized again");
+                    self.state = GuardState::Initialized;
+                    self.notify(HookEvent::EngineInitialized);
+                    return Ok(());
+                }
+
+                self.fail(Error::from_operation(status, Operation::Initialize, None))
+            }
+        }
+    }
+
+    /// Refuse to operate on a half-torn-down engine.
+    pub(crate) fn ensure_usable(&self) -> Result<()> {
+        match self.state {
+            GuardState::Poisoned => Err(Error::Poisoned),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use std::{thread, time::Duration};
+
+use crate::{
+    error::Result,
+    guard::{DetourGuard, GuardState},
+    logging,
+};
+
+/// How [`DetourGuard::close_with_retry`] retries closing, when uninitializing the engine fails transiently,
+/// e.g. while a thread executes a target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first one.
+    pub attempts: u32,
+    /// The delay before the first retry, capped by `max_delay`.
+    pub delay: Duration,
+    /// The factor the delay grows by after every retry.
+    pub backoff: u32,
+    /// The longest delay between two attempts.
+    pub max_delay: Duration,
+    /// If set, every hook is disabled before the first attempt, then the given duration is waited for, so
+    /// that threads can leave the trampolines.
+    pub disable_first: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 5,
+            delay: Duration::from_millis(10),
+            backoff: 2,
+            max_delay: Duration::from_secs(1),
+            disable_first: None,
+        }
+    }
+}
+
+impl<'a> DetourGuard<'a> {
+    /// Attempt to do a graceful close of the [`DetourGuard`], retrying with backoff as described by `policy`.
+    ///
+    /// Refer to [`DetourGuard::try_close`] for further explaination.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - How to retry.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if the close was succesful.
+    /// - `Err(minhook_detours_rs::error::Error)` of the last attempt, if none succeeded.
+    pub fn close_with_retry(&mut self, policy: &RetryPolicy) -> Result<()> {
+        let _span = logging::span!("close_with_retry", attempts = policy.attempts);
+
+        if let Some(settle) = policy.disable_first {
+            if self.state == GuardState::Initialized {
+                self.disable_all_hooks()?;
+                thread::sleep(settle);
+            }
+        }
+
+        let mut delay = policy.delay.min(policy.max_delay);
+        let mut attempt = 1;
+
+        loop {
+            match self.try_close() {
+                Ok(()) => return Ok(()),
+                Err(error) if attempt >= policy.attempts => return Err(error),
+                Err(error) => {
+                    logging::debug!("Closing failed on attempt {attempt}, retrying in {delay:?}: {error}");
+
+                    thread::sleep(delay);
+                    // Overflowing means the delay is way past the maximum anyway.
+                    delay = delay
+                        .checked_mul(policy.backoff)
+                        .unwrap_or(policy.max_delay)
+                        .min(policy.max_delay);
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
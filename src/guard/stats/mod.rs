@@ -0,0 +1,157 @@
+#[cfg(all(feature = "timing", feature = "serde"))]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "timing")]
+use std::time::Duration;
+use std::{
+    os::raw::c_void,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::guard::{DetourGuard, HookInfo};
+
+/// Live counters of a hook, updated by the shim generated through [`crate::static_detour`].
+#[derive(Debug)]
+pub struct HookStats {
+    calls: AtomicU64,
+    #[cfg(feature = "timing")]
+    detour: AtomicTiming,
+    #[cfg(feature = "timing")]
+    original: AtomicTiming,
+}
+
+impl HookStats {
+    pub const fn new() -> Self {
+        Self {
+            calls: AtomicU64::new(0),
+            #[cfg(feature = "timing")]
+            detour: AtomicTiming::new(),
+            #[cfg(feature = "timing")]
+            original: AtomicTiming::new(),
+        }
+    }
+
+    /// The number of times the detour was entered.
+    pub fn call_count(&self) -> u64 {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn record_call(&self) {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Time spent inside of the detour, including the original function.
+    #[cfg(feature = "timing")]
+    pub fn detour_timing(&self) -> Timing {
+        self.detour.snapshot()
+    }
+
+    /// Time spent inside of the original function, when called through
+    /// [`crate::detour::StaticDetour::call_original`].
+    #[cfg(feature = "timing")]
+    pub fn original_timing(&self) -> Timing {
+        self.original.snapshot()
+    }
+
+    #[cfg(feature = "timing")]
+    pub(crate) fn record_detour(&self, elapsed: Duration) {
+        self.detour.record(elapsed);
+    }
+
+    #[cfg(feature = "timing")]
+    pub(crate) fn record_original(&self, elapsed: Duration) {
+        self.original.record(elapsed);
+    }
+}
+
+impl Default for HookStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Snapshot of the durations of a set of calls.
+#[cfg(feature = "timing")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Timing {
+    pub count: u64,
+    pub total: Duration,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+#[cfg(feature = "timing")]
+impl Timing {
+    /// The average duration of a call, or zero if none was recorded.
+    pub fn mean(&self) -> Duration {
+        match self.count {
+            0 => Duration::ZERO,
+            count => Duration::from_nanos((self.total.as_nanos() / count as u128) as u64),
+        }
+    }
+}
+
+/// Lock-free accumulator behind [`Timing`], in nanoseconds.
+#[cfg(feature = "timing")]
+#[derive(Debug)]
+struct AtomicTiming {
+    count: AtomicU64,
+    total: AtomicU64,
+    min: AtomicU64,
+    max: AtomicU64,
+}
+
+#[cfg(feature = "timing")]
+impl AtomicTiming {
+    const fn new() -> Self {
+        Self {
+            count: AtomicU64::new(0),
+            total: AtomicU64::new(0),
+            min: AtomicU64::new(u64::MAX),
+            max: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, elapsed: Duration) {
+        let nanos = elapsed.as_nanos().min(u64::MAX as u128) as u64;
+
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.total.fetch_add(nanos, Ordering::Relaxed);
+        self.min.fetch_min(nanos, Ordering::Relaxed);
+        self.max.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> Timing {
+        let count = self.count.load(Ordering::Relaxed);
+
+        Timing {
+            count,
+            total: Duration::from_nanos(self.total.load(Ordering::Relaxed)),
+            min: match count {
+                0 => Duration::ZERO,
+                _ => Duration::from_nanos(self.min.load(Ordering::Relaxed)),
+            },
+            max: Duration::from_nanos(self.max.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl<'a> DetourGuard<'a> {
+    /// Iterates over every hook registered through the [`DetourGuard`] that keeps [`HookStats`], in creation
+    /// order.
+    pub fn stats(&self) -> impl Iterator<Item = (&HookInfo, &'static HookStats)> {
+        self.hooks
+            .iter()
+            .filter(|hook| !hook.removed)
+            .filter_map(|hook| Some((&hook.info, hook.stats?)))
+    }
+
+    /// Associates `stats` with the hook attached to `target`.
+    pub(crate) fn attach_stats(&mut self, target: *mut c_void, stats: &'static HookStats) {
+        if let Some(hook) = self.entry_mut(target) {
+            hook.stats = Some(stats);
+        }
+
+        self.publish_stats();
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    os::raw::c_void,
+    ptr::{null, null_mut},
+};
+
+use crate::guard::{DetourGuard, HookEntry};
+
+/// The smallest chunk of slots allocated once the [`HookStore`] runs out of room.
+const MIN_CHUNK: usize = 16;
+
+/// Storage of the entries of a [`DetourGuard`], along with the slots holding their `original` pointer, whose
+/// addresses never change, as references to them are handed out.
+///
+/// Slots live in chunks that never grow past their capacity, so pushing an entry never moves the others'
+/// slots, and only allocates once the last chunk is full. Entries themselves may be dropped once their hook
+/// is removed, while their slot stays around for the references that may still be alive.
+#[derive(Debug, Default)]
+pub(super) struct HookStore {
+    entries: Vec<HookEntry>,
+    slots: Vec<Vec<*mut c_void>>,
+}
+
+impl HookStore {
+    /// Makes sure the next `additional` entries are pushed without allocating.
+    pub(super) fn reserve(&mut self, additional: usize) {
+        self.entries.reserve(additional);
+
+        if self.spare() < additional {
+            self.slots.push(Vec::with_capacity(additional));
+        }
+    }
+
+    /// Pushes `entry`, pointing its `original` at a fresh slot, starting a new chunk if the last one is full.
+    pub(super) fn push(&mut self, mut entry: HookEntry) -> &mut HookEntry {
+        if self.spare() == 0 {
+            // Grow geometrically, so that the number of chunks stays logarithmic.
+            let capacity = self.slot_count().max(MIN_CHUNK);
+            self.slots.push(Vec::with_capacity(capacity));
+        }
+
+        let chunk = self.slots.last_mut().unwrap();
+        chunk.push(null_mut());
+        entry.original = chunk.last_mut().unwrap();
+
+        self.entries.push(entry);
+        self.entries.last_mut().unwrap()
+    }
+
+    /// Takes back the latest entry, along with its slot, e.g. of a hook the engine refused.
+    pub(super) fn pop(&mut self) -> Option<HookEntry> {
+        let entry = self.entries.pop()?;
+        self.slots.iter_mut().rev().find_map(Vec::pop);
+        Some(entry)
+    }
+
+    /// Drops the entry of the removed hook of `target`, keeping its slot.
+    ///
+    /// Entries of hooks removed as their module was unloaded are kept, as they may be re-installed.
+    pub(super) fn forget(&mut self, target: *mut c_void) {
+        self.entries
+            .retain(|hook| !(hook.removed && !hook.unloaded && hook.info.target == target));
+    }
+
+    pub(super) fn iter(&self) -> impl DoubleEndedIterator<Item = &HookEntry> {
+        self.entries.iter()
+    }
+
+    pub(super) fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut HookEntry> {
+        self.entries.iter_mut()
+    }
+
+    fn slot_count(&self) -> usize {
+        self.slots.iter().map(Vec::len).sum()
+    }
+
+    /// The room left in the last chunk of slots.
+    fn spare(&self) -> usize {
+        self.slots
+            .last()
+            .map_or(0, |chunk| chunk.capacity() - chunk.len())
+    }
+}
+
+impl<'a> DetourGuard<'a> {
+    /// Makes room for `additional` hooks up front, e.g. right after construction, so that creating them
+    /// through [`DetourGuard::create_hook`] later on performs no heap allocation, unless features recording
+    /// hooks elsewhere are enabled, e.g. `hook-table`, or `stats` with a worker running.
+    ///
+    /// Room is made in the registry, and in the tracking of the modules owning the targets, and what's built
+    /// upon first use, e.g. the list of critical functions, is built right away. Only the engine allocates,
+    /// from a heap of its own, for its trampolines, and bookkeeping. Naming, or grouping a hook allocates its
+    /// name, and rebindable hooks need their stubs reserved, see [`DetourGuard::reserve_stubs`].
+    ///
+    /// # Arguments
+    ///
+    /// * `additional` - The number of hooks to make room for.
+    pub fn reserve(&mut self, additional: usize) {
+        self.hooks.reserve(additional);
+        self.reserve_tracking(additional);
+
+        // Otherwise built upon creating the first hook.
+        let _ = Self::critical_function(null());
+    }
+}
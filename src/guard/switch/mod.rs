@@ -0,0 +1,114 @@
+use std::{
+    marker::PhantomData,
+    os::raw::c_void,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+use crate::{
+    error::{Error, Result},
+    guard::DetourGuard,
+};
+
+/// Copyable switch turning a rebindable hook on, and off, from any thread, without locking, nor patching code.
+///
+/// Switched off, the stub in front of the detour jumps to the original function instead, so the hook stays
+/// enabled in the engine meanwhile. Obtained through [`DetourGuard::switch`], and borrowing the
+/// [`DetourGuard`], so that the hook can't be removed while the switch is alive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HookSwitch<'g> {
+    target: *mut c_void,
+    slot: *const AtomicPtr<c_void>,
+    detour: *const AtomicPtr<c_void>,
+    original: *const *mut c_void,
+    _phantom_data: PhantomData<&'g ()>,
+}
+
+// Every state the switch touches is atomic, and lives as long as the borrowed [`DetourGuard`].
+unsafe impl<'g> Send for HookSwitch<'g> {}
+unsafe impl<'g> Sync for HookSwitch<'g> {}
+
+impl<'g> HookSwitch<'g> {
+    /// The hooked function.
+    pub fn target(&self) -> *mut c_void {
+        self.target
+    }
+
+    /// Whether calls go to the detour, rather than straight to the original function.
+    pub fn is_on(&self) -> bool {
+        unsafe { (*self.slot).load(Ordering::Acquire) != *self.original }
+    }
+
+    /// Sends calls to the detour, or straight to the original function.
+    ///
+    /// # Arguments
+    ///
+    /// * `on` - Whether calls should go to the detour.
+    pub fn set(&self, on: bool) {
+        let (slot, detour) = unsafe { (&*self.slot, &*self.detour) };
+
+        if !on {
+            slot.store(unsafe { *self.original }, Ordering::Release);
+            return;
+        }
+
+        // The hook may be rebound meanwhile, in which case the latest detour must be the one left in place.
+        loop {
+            let current = detour.load(Ordering::Acquire);
+            slot.store(current, Ordering::Release);
+
+            if detour.load(Ordering::Acquire) == current {
+                break;
+            }
+        }
+    }
+
+    /// Sends calls to the detour.
+    pub fn on(&self) {
+        self.set(true);
+    }
+
+    /// Sends calls straight to the original function.
+    pub fn off(&self) {
+        self.set(false);
+    }
+}
+
+impl<'a> DetourGuard<'a> {
+    /// Looks for `target` in the [`DetourGuard`]'s registry, returning a [`HookSwitch`] for it, which toggles
+    /// the hook far cheaper than [`DetourGuard::enable_hook`], and [`DetourGuard::disable_hook`], and from any
+    /// thread.
+    ///
+    /// Only rebindable hooks have a stub to switch, so plain hooks, e.g. the ones of
+    /// [`DetourGuard::create_hook`], are refused with [`Error::NotRebindable`], and are toggled through
+    /// [`DetourGuard::enable_hook`], and [`DetourGuard::disable_hook`] instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The hooked function.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(HookSwitch)` borrowing the [`DetourGuard`], if the hook was created through [`DetourGuard::create_rebindable_hook`].
+    /// - `Err(minhook_detours_rs::error::Error::NotCreated)` if no hook is registered for `target`.
+    /// - `Err(minhook_detours_rs::error::Error::NotRebindable)` if the hook wasn't created as rebindable.
+    pub fn switch(&self, target: *mut c_void) -> Result<HookSwitch<'_>> {
+        let hook = self
+            .hooks
+            .iter()
+            .find(|hook| !hook.removed && hook.info.target == target)
+            .ok_or(Error::NotCreated)?;
+        let (slot, detour) = hook
+            .indirection
+            .as_ref()
+            .ok_or(Error::NotRebindable)?
+            .slots();
+
+        Ok(HookSwitch {
+            target,
+            slot,
+            detour,
+            original: hook.original,
+            _phantom_data: PhantomData,
+        })
+    }
+}
@@ -0,0 +1,23 @@
+use std::fmt::{self, Debug, Formatter};
+
+/// Strategy for keeping other threads out of the code being patched, invoked around every operation that
+/// enables, or disables hooks, e.g. to reuse a suspension mechanism of the host application.
+///
+/// While set, the engine doesn't freeze threads on its own. Refer to [`crate::guard::DetourGuard::exclude_thread`]
+/// for the caveats of freezing threads outside of the engine.
+pub trait ThreadFreezer: Send {
+    /// Called before code is patched.
+    fn freeze(&mut self);
+
+    /// Called after code was patched, even if patching failed.
+    fn unfreeze(&mut self);
+}
+
+/// Storage of the [`ThreadFreezer`] of a [`crate::guard::DetourGuard`].
+pub(crate) struct Freezer(pub(crate) Box<dyn ThreadFreezer>);
+
+impl Debug for Freezer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("Freezer")
+    }
+}
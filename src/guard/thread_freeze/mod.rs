@@ -1,12 +1,33 @@
 use minhook_detours_sys::{
     MH_FREEZE_METHOD_FAST_UNDOCUMENTED, MH_FREEZE_METHOD_NONE_UNSAFE, MH_FREEZE_METHOD_ORIGINAL,
-    MH_THREAD_FREEZE_METHOD,
+    MH_OK, MH_STATUS, MH_THREAD_FREEZE_METHOD,
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::os::raw::c_void;
 
-#[derive(Debug, PartialEq, Eq)]
+use crate::{
+    engine::Engine,
+    error::{Error, Result},
+    guard::DetourGuard,
+    logging,
+};
+
+mod freezer;
+mod suspend;
+
+pub(crate) use freezer::Freezer;
+pub use freezer::ThreadFreezer;
+use suspend::SuspendedThreads;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ThreadFreezeMethod {
     /// Documentation at [SlimDetours](https://github.com/KNSoft/KNSoft.SlimDetours/blob/d5c4dddd85d67b961ca79bd11cc90f25313bc1b5/Source/SlimDetours/Transaction.c#L43) [[implementation](https://github.com/KNSoft/KNSoft.SlimDetours/blob/d5c4dddd85d67b961ca79bd11cc90f25313bc1b5/Source/SlimDetours/Thread.c#L189)]. Skips current thread.
     Original,
+    /// Freezes threads through undocumented NT APIs, which is faster than [`ThreadFreezeMethod::Original`] with
+    /// many threads, but relies on behavior that Windows may change without notice. Skips current thread.
+    Fast,
     /// When beginning a SlimDetours transaction, threads won't be frozen.
     None,
 }
@@ -15,7 +36,7 @@ impl From<MH_THREAD_FREEZE_METHOD> for ThreadFreezeMethod {
     fn from(value: MH_THREAD_FREEZE_METHOD) -> Self {
         match value {
             MH_FREEZE_METHOD_ORIGINAL => Self::Original,
-            MH_FREEZE_METHOD_FAST_UNDOCUMENTED => Self::Original,
+            MH_FREEZE_METHOD_FAST_UNDOCUMENTED => Self::Fast,
             MH_FREEZE_METHOD_NONE_UNSAFE => Self::None,
             _ => unreachable!(),
         }
@@ -26,7 +47,231 @@ impl Into<MH_THREAD_FREEZE_METHOD> for ThreadFreezeMethod {
     fn into(self) -> MH_THREAD_FREEZE_METHOD {
         match self {
             Self::Original => MH_FREEZE_METHOD_ORIGINAL,
+            Self::Fast => MH_FREEZE_METHOD_FAST_UNDOCUMENTED,
             Self::None => MH_FREEZE_METHOD_NONE_UNSAFE,
         }
     }
 }
+
+impl<'a> DetourGuard<'a> {
+    /// The thread freezing method last set through the [`DetourGuard`], or [`ThreadFreezeMethod::Original`],
+    /// the engine's default.
+    ///
+    /// A [`DetourGuard`] built through [`DetourGuard::from_raw`] assumes the default, as it can't query the
+    /// engine.
+    pub fn thread_freeze_method(&self) -> ThreadFreezeMethod {
+        self.thread_freeze_method
+    }
+
+    /// Run `operation` with `thread_freeze_method`, restoring the previous method afterwards, even if
+    /// `operation` failed.
+    ///
+    /// # Arguments
+    ///
+    /// * `thread_freeze_method` - The method used for thread freezing during `operation`.
+    /// * `operation` - The operation, e.g. a single toggle, or a whole transaction.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(R)` if `operation` succeeded, and the method was succesfully restored.
+    /// - `Err(minhook_detours_rs::error::Error)` of `operation` if it failed, or of restoring the method
+    ///   otherwise.
+    pub fn with_thread_freeze_method<R>(
+        &mut self,
+        thread_freeze_method: ThreadFreezeMethod,
+        operation: impl FnOnce(&mut Self) -> Result<R>,
+    ) -> Result<R> {
+        let previous = self.thread_freeze_method;
+
+        if previous == thread_freeze_method {
+            return operation(self);
+        }
+
+        self.set_thread_freeze_method(thread_freeze_method)?;
+
+        let result = operation(self);
+        let restored = self.set_thread_freeze_method(previous);
+
+        let value = result?;
+        restored?;
+        Ok(value)
+    }
+
+    /// Enables the hook attached to `target`, with `thread_freeze_method` for this call only.
+    ///
+    /// Refer to [`DetourGuard::enable_hook`], and [`DetourGuard::with_thread_freeze_method`] for further
+    /// explaination.
+    pub fn enable_hook_with(
+        &mut self,
+        target: *mut c_void,
+        thread_freeze_method: ThreadFreezeMethod,
+    ) -> Result<()> {
+        self.with_thread_freeze_method(thread_freeze_method, |guard| guard.enable_hook(target))
+    }
+
+    /// Disables the hook attached to `target`, with `thread_freeze_method` for this call only.
+    ///
+    /// Refer to [`DetourGuard::disable_hook`], and [`DetourGuard::with_thread_freeze_method`] for further
+    /// explaination.
+    pub fn disable_hook_with(
+        &mut self,
+        target: *mut c_void,
+        thread_freeze_method: ThreadFreezeMethod,
+    ) -> Result<()> {
+        self.with_thread_freeze_method(thread_freeze_method, |guard| guard.disable_hook(target))
+    }
+
+    /// Keep the thread identified by `thread_id` running while hooks are enabled or disabled, e.g. a watchdog,
+    /// or an audio thread.
+    ///
+    /// With excluded threads, threads are suspended by the crate instead of by the engine, unless the method
+    /// is [`ThreadFreezeMethod::None`]. Unlike the engine, the crate doesn't move instruction pointers out of
+    /// the code being patched, so a suspended thread that is in the middle of a target's first instructions
+    /// may crash once resumed.
+    ///
+    /// # Arguments
+    ///
+    /// * `thread_id` - The identifier of the thread, as returned by `GetCurrentThreadId`.
+    pub fn exclude_thread(&mut self, thread_id: u32) {
+        self.excluded_threads.insert(thread_id);
+    }
+
+    /// Let the thread identified by `thread_id` be suspended again, see [`DetourGuard::exclude_thread`].
+    pub fn include_thread(&mut self, thread_id: u32) {
+        self.excluded_threads.remove(&thread_id);
+    }
+
+    /// Iterates over the identifiers of the threads excluded from freezing.
+    pub fn excluded_threads(&self) -> impl Iterator<Item = u32> + '_ {
+        self.excluded_threads.iter().copied()
+    }
+
+    /// Freeze threads with `freezer` instead of the engine, for every operation that enables, or disables
+    /// hooks, unless the method is [`ThreadFreezeMethod::None`]. Takes precedence over
+    /// [`DetourGuard::exclude_thread`].
+    ///
+    /// # Arguments
+    ///
+    /// * `freezer` - The strategy, see [`ThreadFreezer`].
+    pub fn set_thread_freezer(&mut self, freezer: impl ThreadFreezer + 'static) {
+        self.freezer = Some(Freezer(Box::new(freezer)));
+    }
+
+    /// Go back to freezing threads through the engine, or the crate for excluded threads.
+    pub fn clear_thread_freezer(&mut self) {
+        self.freezer = None;
+    }
+
+    /// Toggle the hook attached to `target` without freezing any thread, no matter the method, the
+    /// [`ThreadFreezer`], or the excluded threads, cutting the latency of [`DetourGuard::enable_hook`], and
+    /// [`DetourGuard::disable_hook`] down to writing the jump.
+    ///
+    /// Transactions, and operations on every hook still freeze threads as usual.
+    ///
+    /// Hooks are never marked automatically, as no target is safe to toggle unfrozen by its shape alone: the
+    /// engine writes its jump with plain stores, rather than a single atomic one, even at an aligned patch
+    /// site, so a thread executing the first instructions of `target` meanwhile may run a torn instruction.
+    /// Only mark targets that can't run while toggled, e.g. ones only called from the toggling thread, and
+    /// toggle hot hooks through a [`crate::guard::HookSwitch`] instead, which never patches code.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The hooked function.
+    /// * `freeze_free` - Whether toggling the hook skips thread freezing.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if the hook was succesfully marked.
+    /// - `Err(minhook_detours_rs::error::Error::NotCreated)` if no hook is registered for `target`.
+    pub fn set_freeze_free(&mut self, target: *mut c_void, freeze_free: bool) -> Result<()> {
+        let hook = self.entry_mut(target).ok_or(Error::NotCreated)?;
+        hook.freeze_free = freeze_free;
+
+        // We succesfully marked the hook!
+        logging::debug!("Hook for {target:p} is freeze-free: {freeze_free}");
+        Ok(())
+    }
+
+    /// Whether toggling the hook attached to `target` skips thread freezing, see
+    /// [`DetourGuard::set_freeze_free`]. `false` if no hook is registered for `target`.
+    pub fn is_freeze_free(&self, target: *mut c_void) -> bool {
+        self.hooks
+            .iter()
+            .any(|hook| !hook.removed && hook.info.target == target && hook.freeze_free)
+    }
+
+    /// Run `operation`, which toggles the hook attached to `target`, through [`DetourGuard::patch`], or
+    /// without freezing any thread if the hook is freeze-free.
+    pub(crate) fn patch_hook(
+        &mut self,
+        target: *mut c_void,
+        operation: impl FnOnce(&Engine) -> MH_STATUS,
+    ) -> MH_STATUS {
+        if !self.is_freeze_free(target) || self.thread_freeze_method == ThreadFreezeMethod::None {
+            return self.patch(operation);
+        }
+
+        self.prune_unloaded();
+
+        // The background toggles mustn't run unfrozen, and a debugger is checked upon every patch, like
+        // through `DetourGuard::patch`.
+        self.join_background();
+        self.sync_debugger_bypass();
+        let status = self.engine.set_thread_freeze_method(ThreadFreezeMethod::None);
+        if status != MH_OK {
+            return status;
+        }
+
+        let status = operation(&self.engine);
+
+        let restored = self.engine.set_thread_freeze_method(self.thread_freeze_method);
+        if status != MH_OK { status } else { restored }
+    }
+
+    /// Run `operation`, which patches code, freezing threads through the crate if a [`ThreadFreezer`] is set,
+    /// or any thread is excluded.
+    ///
+    /// Hooks whose module was unloaded are dropped from the registry first, see [`DetourGuard::prune_unloaded`].
+    pub(crate) fn patch(&mut self, operation: impl FnOnce(&Engine) -> MH_STATUS) -> MH_STATUS {
+        // Hooks of unloaded modules are gone from the engine, and their code with them.
+        self.prune_unloaded();
+        // Toggles handed to the background land first, so that operations reach the engine in order, and
+        // none runs unfrozen.
+        self.join_background();
+        self.sync_debugger_bypass();
+
+        if (self.freezer.is_none() && self.excluded_threads.is_empty())
+            || self.thread_freeze_method == ThreadFreezeMethod::None
+        {
+            return operation(&self.engine);
+        }
+
+        // The engine mustn't suspend the excluded threads on its own.
+        let status = self.engine.set_thread_freeze_method(ThreadFreezeMethod::None);
+        if status != MH_OK {
+            return status;
+        }
+
+        let status = match &mut self.freezer {
+            Some(Freezer(freezer)) => {
+                freezer.freeze();
+                let status = operation(&self.engine);
+                freezer.unfreeze();
+                status
+            }
+            None => {
+                // A mock engine doesn't patch anything, so there's nothing to protect other threads from.
+                let suspended = self
+                    .engine
+                    .patches_code()
+                    .then(|| SuspendedThreads::suspend(&self.excluded_threads));
+                let status = operation(&self.engine);
+                drop(suspended);
+                status
+            }
+        };
+
+        let restored = self.engine.set_thread_freeze_method(self.thread_freeze_method);
+        if status != MH_OK { status } else { restored }
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::BTreeSet;
+use winapi::{
+    shared::minwindef::FALSE,
+    um::{
+        handleapi::{CloseHandle, INVALID_HANDLE_VALUE},
+        processthreadsapi::{
+            GetCurrentProcessId, GetCurrentThreadId, OpenThread, ResumeThread, SuspendThread,
+        },
+        tlhelp32::{CreateToolhelp32Snapshot, TH32CS_SNAPTHREAD, THREADENTRY32, Thread32First, Thread32Next},
+        winnt::{HANDLE, THREAD_SUSPEND_RESUME},
+    },
+};
+
+/// Threads of the current process suspended by the crate, instead of by the engine, resumed upon drop.
+///
+/// Unlike the engine, instruction pointers that are inside of code being patched aren't moved.
+pub(crate) struct SuspendedThreads {
+    handles: Vec<HANDLE>,
+}
+
+impl SuspendedThreads {
+    /// Suspend every thread of the current process, except for the current one, and `excluded`.
+    pub(crate) fn suspend(excluded: &BTreeSet<u32>) -> Self {
+        let threads = Self::threads();
+
+        // Every handle is allocated for upfront, as a suspended thread may hold the heap's lock.
+        let mut handles = Vec::with_capacity(threads.len());
+
+        for thread_id in threads {
+            if excluded.contains(&thread_id) {
+                continue;
+            }
+
+            let handle = unsafe { OpenThread(THREAD_SUSPEND_RESUME, FALSE, thread_id) };
+
+            // The thread may have exited in the meantime.
+            if handle.is_null() {
+                continue;
+            }
+
+            if unsafe { SuspendThread(handle) } == u32::MAX {
+                unsafe { CloseHandle(handle) };
+                continue;
+            }
+
+            handles.push(handle);
+        }
+
+        Self { handles }
+    }
+
+    /// The identifiers of the other threads of the current process.
+    fn threads() -> Vec<u32> {
+        let snapshot = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0) };
+
+        if snapshot == INVALID_HANDLE_VALUE {
+            return Vec::new();
+        }
+
+        let (process_id, thread_id) = unsafe { (GetCurrentProcessId(), GetCurrentThreadId()) };
+
+        let mut threads = Vec::new();
+        let mut entry: THREADENTRY32 = unsafe { std::mem::zeroed() };
+        entry.dwSize = size_of::<THREADENTRY32>() as u32;
+
+        let mut found = unsafe { Thread32First(snapshot, &mut entry) };
+        while found != FALSE {
+            if entry.th32OwnerProcessID == process_id && entry.th32ThreadID != thread_id {
+                threads.push(entry.th32ThreadID);
+            }
+
+            found = unsafe { Thread32Next(snapshot, &mut entry) };
+        }
+
+        unsafe { CloseHandle(snapshot) };
+        threads
+    }
+}
+
+impl Drop for SuspendedThreads {
+    fn drop(&mut self) {
+        for handle in self.handles.drain(..) {
+            unsafe {
+                ResumeThread(handle);
+                CloseHandle(handle);
+            }
+        }
+    }
+}
@@ -0,0 +1,271 @@
+use std::{
+    collections::HashMap,
+    mem::{take, transmute},
+    os::raw::c_void,
+    ptr::null_mut,
+    sync::{Arc, Mutex, OnceLock, Weak},
+};
+use winapi::um::{
+    memoryapi::{VirtualAlloc, VirtualFree},
+    winnt::{MEM_COMMIT, MEM_RELEASE, MEM_RESERVE, PAGE_EXECUTE_READWRITE},
+};
+
+use crate::{
+    guard::{DetourGuard, HookEvent},
+    logging,
+    pe::Module,
+};
+
+/// The bytes in front of a target the engine may restore, as it patches the hot-patch area of functions.
+const SCRATCH_BEFORE: usize = 16;
+
+/// The bytes mapped in place of an unloaded target, covering whatever the engine may restore around it.
+const SCRATCH_SIZE: usize = 64;
+
+/// The notification `LdrRegisterDllNotification` sends for a module that is being unloaded.
+const LDR_DLL_NOTIFICATION_REASON_UNLOADED: u32 = 2;
+
+/// Mirror of `LDR_DLL_NOTIFICATION_DATA`, whose layout is the same for loaded, and unloaded modules.
+#[repr(C)]
+#[allow(dead_code)]
+struct LdrDllNotificationData {
+    flags: u32,
+    full_dll_name: *const c_void,
+    base_dll_name: *const c_void,
+    dll_base: *mut c_void,
+    size_of_image: u32,
+}
+
+type LdrDllNotificationFunction = unsafe extern "system" fn(
+    reason: u32,
+    data: *const LdrDllNotificationData,
+    context: *mut c_void,
+);
+
+type LdrRegisterDllNotification = unsafe extern "system" fn(
+    flags: u32,
+    function: LdrDllNotificationFunction,
+    context: *mut c_void,
+    cookie: *mut *mut c_void,
+) -> i32;
+
+/// The identity of a mapped image, telling apart a module loaded again at the same base from another one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Image {
+    base: usize,
+    size: u32,
+    stamp: u32,
+}
+
+impl Image {
+    fn of(module: &Module) -> Self {
+        let nt_headers = module.nt_headers();
+
+        Self {
+            base: module.base() as usize,
+            size: nt_headers.OptionalHeader.SizeOfImage,
+            stamp: nt_headers.FileHeader.TimeDateStamp,
+        }
+    }
+}
+
+/// The bookkeeping of a [`DetourGuard`], shared with the loader's notifications, which may arrive on any
+/// thread.
+#[derive(Debug, Default)]
+pub(crate) struct Tracked {
+    /// The base of the module owning every hooked target, by target. A hash map, so that room can be
+    /// reserved for it, see [`DetourGuard::reserve`].
+    modules: HashMap<usize, usize>,
+    /// The targets whose module was unloaded, along with its image, whose hooks are yet to be removed from
+    /// the engine.
+    unloaded: Vec<(usize, Image)>,
+}
+
+/// The bookkeeping of every [`DetourGuard`] tracking modules, for the loader's notifications to look up.
+static GUARDS: Mutex<Vec<Weak<Mutex<Tracked>>>> = Mutex::new(Vec::new());
+
+/// Registers for the loader's notifications, once per process.
+///
+/// # Returns
+///
+/// - `true` if the notifications are delivered.
+/// - `false` if registering failed, e.g. as `ntdll.dll` doesn't export `LdrRegisterDllNotification`.
+fn watch() -> bool {
+    static WATCHING: OnceLock<bool> = OnceLock::new();
+
+    *WATCHING.get_or_init(|| {
+        let export = Module::from_name("ntdll.dll")
+            .and_then(|ntdll| ntdll.export("LdrRegisterDllNotification"));
+        let Ok(export) = export else {
+            return false;
+        };
+
+        let register = unsafe { transmute::<*mut c_void, LdrRegisterDllNotification>(export) };
+
+        // The registration lasts for the lifetime of the process, so the cookie is never needed again.
+        let mut cookie = null_mut();
+        let status = unsafe { register(0, on_notification, null_mut(), &mut cookie) };
+
+        if status < 0 {
+            logging::debug!("Failed registering for DLL notifications with {status:#x}");
+            return false;
+        }
+
+        true
+    })
+}
+
+/// Queues the hooks of a module that is being unloaded, to be removed by the next operation of their
+/// [`DetourGuard`].
+///
+/// The engine isn't touched here, as it freezes threads, which mustn't happen under the loader lock.
+unsafe extern "system" fn on_notification(
+    reason: u32,
+    data: *const LdrDllNotificationData,
+    _context: *mut c_void,
+) {
+    if reason != LDR_DLL_NOTIFICATION_REASON_UNLOADED {
+        return;
+    }
+
+    let base = unsafe { (*data).dll_base };
+
+    // The image is still mapped, so its headers can be read.
+    let Ok(module) = (unsafe { Module::from_handle(base as _) }) else {
+        return;
+    };
+    let image = Image::of(&module);
+
+    // Called under the loader lock, so there's no unwinding, or blocking on anything but our own locks.
+    let Ok(guards) = GUARDS.lock() else {
+        return;
+    };
+
+    for tracked in guards.iter().filter_map(Weak::upgrade) {
+        let Ok(mut tracked) = tracked.lock() else {
+            continue;
+        };
+        let tracked = &mut *tracked;
+
+        let targets = tracked
+            .modules
+            .iter()
+            .filter(|(_, module)| **module == image.base)
+            .map(|(target, _)| (*target, image));
+        tracked.unloaded.extend(targets);
+
+        tracked.modules.retain(|_, module| *module != image.base);
+    }
+}
+
+impl<'a> DetourGuard<'a> {
+    /// Remember the module owning `target`, so that its hook is removed once the module is unloaded.
+    ///
+    /// Targets outside of any module, e.g. generated code, are never removed automatically.
+    pub(crate) fn track_module(&mut self, target: *mut c_void) {
+        // A mock engine doesn't patch anything, so there's nothing that could outlive a module.
+        if !self.engine.patches_code() || !watch() {
+            return;
+        }
+
+        let Ok(module) = Module::from_address(target) else {
+            return;
+        };
+
+        self.register_tracked();
+
+        if let Ok(mut tracked) = self.tracked.lock() {
+            tracked.modules.insert(target as usize, module.base() as usize);
+        }
+    }
+
+    /// Makes room for tracking `additional` targets, registering for the notifications up front, see
+    /// [`DetourGuard::reserve`].
+    pub(crate) fn reserve_tracking(&mut self, additional: usize) {
+        if !self.engine.patches_code() || !watch() {
+            return;
+        }
+
+        self.register_tracked();
+
+        if let Ok(mut tracked) = self.tracked.lock() {
+            tracked.modules.reserve(additional);
+        }
+    }
+
+    /// Hands the bookkeeping to the notifications, unless it was already.
+    fn register_tracked(&self) {
+        if Arc::weak_count(&self.tracked) > 0 {
+            return;
+        }
+
+        if let Ok(mut guards) = GUARDS.lock() {
+            guards.retain(|tracked| tracked.strong_count() > 0);
+            guards.push(Arc::downgrade(&self.tracked));
+        }
+    }
+
+    /// Forget about the module owning `target`, as its hook is gone.
+    pub(crate) fn untrack_module(&mut self, target: *mut c_void) {
+        if let Ok(mut tracked) = self.tracked.lock() {
+            tracked.modules.remove(&(target as usize));
+        }
+    }
+
+    /// Remove the hooks whose module was unloaded from the engine, so that the registry never hands them to
+    /// the engine again, e.g. through [`DetourGuard::enable_all_hooks`].
+    pub(crate) fn prune_unloaded(&mut self) {
+        let unloaded = match self.tracked.lock() {
+            Ok(mut tracked) if !tracked.unloaded.is_empty() => take(&mut tracked.unloaded),
+            _ => return,
+        };
+
+        for (target, image) in unloaded {
+            let target = target as *mut c_void;
+            self.remove_unloaded(target, image);
+
+            let Some(hook) = self.entry_mut(target) else {
+                continue;
+            };
+
+            // Whether the hook was enabled is kept, so that [`DetourGuard::rehook_module`] restores it.
+            hook.removed = true;
+            hook.unloaded = true;
+
+            logging::info!("Removed hook for {target:p}, as its module was unloaded");
+            self.notify(HookEvent::Removed { target });
+        }
+    }
+
+    /// Remove the hook of `target` from the engine, once `image`, which owned it, was unloaded.
+    ///
+    /// The engine restores the original code while removing an enabled hook, which must land neither in
+    /// unmapped memory, nor in another module mapped at its address meanwhile.
+    fn remove_unloaded(&mut self, target: *mut c_void, image: Image) {
+        // Loaded again at the same base, the image holds the very code the engine restores.
+        if Module::from_address(target).is_ok_and(|module| Image::of(&module) == image) {
+            let _ = self.engine.remove_hook(target);
+            return;
+        }
+
+        // Otherwise, the engine restores the original code into scratch memory mapped in place of the image,
+        // covering the hot-patch area in front of `target` as well.
+        let scratch = unsafe {
+            VirtualAlloc(
+                (target as usize).saturating_sub(SCRATCH_BEFORE) as _,
+                SCRATCH_SIZE,
+                MEM_COMMIT | MEM_RESERVE,
+                PAGE_EXECUTE_READWRITE,
+            )
+        };
+
+        if scratch.is_null() {
+            // Something else lives there now, which must not be overwritten, so the engine keeps the hook.
+            logging::info!("Leaving the hook for {target:p} to the engine, as its memory was reused");
+            return;
+        }
+
+        let _ = self.engine.remove_hook(target);
+        unsafe { VirtualFree(scratch, 0, MEM_RELEASE) };
+    }
+}
@@ -0,0 +1,78 @@
+use winapi::shared::windef::HWND;
+
+use crate::{
+    error::{Error, Result},
+    guard::DetourGuard,
+    logging,
+    window::{OriginalWndProc, WindowProcedure, WndProcHook},
+};
+
+impl<'a> DetourGuard<'a> {
+    /// Replaces the window procedure of `window` with `detour`, keeping the [`WndProcHook`] alongside the
+    /// hooks, so that [`DetourGuard::reset`], and closing the [`DetourGuard`] restore the original procedure.
+    ///
+    /// # Arguments
+    ///
+    /// * `window` - The window to subclass.
+    /// * `detour` - The window procedure to install, which should forward the messages it doesn't handle
+    ///   through the returned [`OriginalWndProc`].
+    ///
+    /// # Safety
+    ///
+    /// Same as [`WndProcHook::new`].
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(OriginalWndProc)` with the replaced procedure, if it was succesfully replaced.
+    /// - `Err(minhook_detours_rs::error::Error::WindowProcedure)` otherwise, with the last Win32 error.
+    pub unsafe fn subclass_window(
+        &mut self,
+        window: HWND,
+        detour: WindowProcedure,
+    ) -> Result<OriginalWndProc> {
+        let _span = logging::span!("subclass_window", window = ?window);
+        self.ensure_usable()?;
+
+        let hook = unsafe { WndProcHook::new(window, detour)? };
+        let original = hook.original();
+        self.window_hooks.push(hook);
+
+        Ok(original)
+    }
+
+    /// Restores the original procedure of the latest [`WndProcHook`] of `window`, and forgets about it.
+    ///
+    /// # Arguments
+    ///
+    /// * `window` - The subclassed window.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if the original procedure was restored.
+    /// - `Err(minhook_detours_rs::error::Error::NotCreated)` if `window` wasn't subclassed through the
+    ///   [`DetourGuard`].
+    pub fn remove_subclass(&mut self, window: HWND) -> Result<()> {
+        let _span = logging::span!("remove_subclass", window = ?window);
+
+        let index = self
+            .window_hooks
+            .iter()
+            .rposition(|hook| hook.target() == window)
+            .ok_or(Error::NotCreated)?;
+
+        // Dropping the hook restores the original procedure.
+        self.window_hooks.remove(index);
+
+        Ok(())
+    }
+
+    /// Iterates over every [`WndProcHook`] applied through the [`DetourGuard`], in application order.
+    pub fn window_hooks(&self) -> impl Iterator<Item = &WndProcHook> {
+        self.window_hooks.iter()
+    }
+
+    /// Restores every [`WndProcHook`], latest first, so that windows subclassed twice are undone correctly.
+    pub(crate) fn restore_window_hooks(&mut self) {
+        while self.window_hooks.pop().is_some() {}
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+#[cfg(feature = "stats")]
+use crate::guard::HookStats;
+use crate::{guard::DetourGuard, logging, ring::LogRing};
+
+/// Receiver of what the worker of a [`DetourGuard`] flushes, see [`DetourGuard::start_worker`].
+///
+/// Called on the worker thread only, so it's free to allocate, lock, or write files.
+pub trait WorkerSink: Send + 'static {
+    /// Called with every message drained from the [`LogRing`], oldest first.
+    fn log(&mut self, message: &str);
+
+    /// Called with the counters of every hook that has [`HookStats`], once per flush.
+    #[cfg(feature = "stats")]
+    fn stats(&mut self, stats: &[WorkerStats]) {
+        let _ = stats;
+    }
+
+    /// Called once the worker stops, after its last flush.
+    fn shutdown(&mut self) {}
+}
+
+/// The counters of a hook, as handed to [`WorkerSink::stats`].
+///
+/// Addresses are plain integers, so that the list can be handed to the worker thread.
+#[cfg(feature = "stats")]
+#[derive(Debug, Clone)]
+pub struct WorkerStats {
+    /// The hooked function.
+    pub target: usize,
+    /// The function the hook redirects to.
+    pub detour: usize,
+    pub enabled: bool,
+    pub name: Option<String>,
+    pub group: Option<String>,
+    pub stats: &'static HookStats,
+}
+
+/// A [`LogRing`] of any capacity.
+trait Drain: Sync {
+    fn drain_into(&self, sink: &mut dyn FnMut(&str)) -> usize;
+}
+
+impl<const N: usize> Drain for LogRing<N> {
+    fn drain_into(&self, sink: &mut dyn FnMut(&str)) -> usize {
+        self.drain(sink)
+    }
+}
+
+/// The thread flushing to a [`WorkerSink`], see [`DetourGuard::start_worker`].
+#[derive(Debug)]
+pub(crate) struct Worker {
+    thread: JoinHandle<()>,
+    /// Whether the worker should stop, along with what wakes it up early for it.
+    stop: Arc<(Mutex<bool>, Condvar)>,
+    /// The hooks that have stats, as last published by the [`DetourGuard`].
+    #[cfg(feature = "stats")]
+    hooks: Arc<Mutex<Vec<WorkerStats>>>,
+}
+
+impl<'a> DetourGuard<'a> {
+    /// Starts a thread that flushes the messages of `ring`, and the stats of the hooks, to `sink` every
+    /// `interval`, so that detours only ever push to the [`LogRing`]. Replaces the worker already running, if
+    /// any.
+    ///
+    /// The worker flushes one last time, and calls [`WorkerSink::shutdown`] once stopped through
+    /// [`DetourGuard::stop_worker`], or once the [`DetourGuard`] closes.
+    ///
+    /// # Arguments
+    ///
+    /// * `ring` - The channel the detours log to.
+    /// * `sink` - Receives the messages, and the stats.
+    /// * `interval` - The time between flushes.
+    pub fn start_worker<const N: usize>(
+        &mut self,
+        ring: &'static LogRing<N>,
+        mut sink: impl WorkerSink,
+        interval: Duration,
+    ) {
+        self.stop_worker();
+
+        let stop = Arc::new((Mutex::new(false), Condvar::new()));
+        #[cfg(feature = "stats")]
+        let hooks = Arc::new(Mutex::new(Vec::new()));
+
+        let thread = {
+            let stop = stop.clone();
+            #[cfg(feature = "stats")]
+            let hooks = hooks.clone();
+
+            thread::spawn(move || {
+                let (stopping, wake) = &*stop;
+
+                loop {
+                    let stopping = {
+                        let stopping = stopping.lock().unwrap_or_else(|error| error.into_inner());
+                        let (stopping, _) = wake
+                            .wait_timeout_while(stopping, interval, |stopping| !*stopping)
+                            .unwrap_or_else(|error| error.into_inner());
+                        *stopping
+                    };
+
+                    ring.drain_into(&mut |message| sink.log(message));
+
+                    #[cfg(feature = "stats")]
+                    {
+                        // Copied out, so that publishing never waits for the sink.
+                        let hooks = hooks.lock().map(|hooks| hooks.clone()).unwrap_or_default();
+                        sink.stats(&hooks);
+                    }
+
+                    if stopping {
+                        break;
+                    }
+                }
+
+                sink.shutdown();
+            })
+        };
+
+        self.worker = Some(Worker {
+            thread,
+            stop,
+            #[cfg(feature = "stats")]
+            hooks,
+        });
+
+        #[cfg(feature = "stats")]
+        self.publish_stats();
+
+        // We successfully started the worker!
+        logging::debug!("Started worker, flushing every {interval:?}");
+    }
+
+    /// Stops the worker started through [`DetourGuard::start_worker`], if any, waiting for its last flush.
+    ///
+    /// While the current thread holds the loader lock, the worker couldn't exit, so it's only told to stop,
+    /// see [`DetourGuard::is_loader_lock_held`].
+    pub fn stop_worker(&mut self) {
+        let Some(worker) = self.worker.take() else {
+            return;
+        };
+
+        let (stopping, wake) = &*worker.stop;
+        *stopping.lock().unwrap_or_else(|error| error.into_inner()) = true;
+        wake.notify_all();
+
+        if Self::is_loader_lock_held() {
+            logging::debug!("Left the worker to stop on its own, as the loader lock is held");
+            return;
+        }
+
+        let _ = worker.thread.join();
+
+        // We successfully stopped the worker!
+        logging::debug!("Stopped worker");
+    }
+
+    /// Whether a worker started through [`DetourGuard::start_worker`] is running.
+    pub fn is_worker_running(&self) -> bool {
+        self.worker.is_some()
+    }
+
+    /// Hands the hooks that have stats to the worker, if any.
+    #[cfg(feature = "stats")]
+    pub(crate) fn publish_stats(&self) {
+        let Some(worker) = &self.worker else {
+            return;
+        };
+
+        let stats = self
+            .stats()
+            .map(|(info, stats)| WorkerStats {
+                target: info.target as usize,
+                detour: info.detour as usize,
+                enabled: info.enabled,
+                name: info.name.clone(),
+                group: info.group.clone(),
+                stats,
+            })
+            .collect();
+        if let Ok(mut hooks) = worker.hooks.lock() {
+            *hooks = stats;
+        }
+    }
+}
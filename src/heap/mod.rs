@@ -0,0 +1,149 @@
+//! Private heap.
+//!
+//! Responsible for keeping the allocations of the payload apart from the heap of the host, so that hooking
+//! `HeapAlloc`, or `RtlAllocateHeap` doesn't recurse into the crate's own allocations, and so that patching
+//! code while a suspended thread holds the lock of the process heap doesn't deadlock.
+//!
+//! Rust allocates through the global allocator, so install [`PrivateHeap`] as such in the payload, which moves
+//! the registry of the [`crate::guard::DetourGuard`], the stats of the hooks, and the allocations of the
+//! detours themselves over to it. The stubs the crate places in front of detours are allocated straight
+//! from `VirtualAlloc` either way.
+//!
+//! ```ignore
+//! #[global_allocator]
+//! static ALLOCATOR: PrivateHeap = PrivateHeap;
+//! ```
+//!
+//! The private heap is still served by `RtlAllocateHeap`, so detours of the heap functions should pass calls
+//! on it straight to the original function, see [`PrivateHeap::is_private`].
+
+use std::{
+    alloc::{GlobalAlloc, Layout},
+    cmp::min,
+    ptr::{copy_nonoverlapping, null_mut},
+    sync::atomic::{AtomicPtr, Ordering},
+};
+use winapi::{
+    ctypes::c_void,
+    shared::minwindef::DWORD,
+    um::{
+        heapapi::{HeapAlloc, HeapCreate, HeapDestroy, HeapFree, HeapReAlloc},
+        winnt::{HANDLE, HEAP_ZERO_MEMORY},
+    },
+};
+
+/// The alignment every block of the heap has, `MEMORY_ALLOCATION_ALIGNMENT`.
+const MIN_ALIGN: usize = if cfg!(target_pointer_width = "64") { 16 } else { 8 };
+
+static HEAP: AtomicPtr<c_void> = AtomicPtr::new(null_mut());
+
+/// Global allocator serving every allocation from a heap of its own, created upon the first one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrivateHeap;
+
+impl PrivateHeap {
+    /// The handle of the private heap, creating it if it wasn't yet.
+    ///
+    /// # Returns
+    ///
+    /// - The handle, if the heap was succesfully created.
+    /// - A null handle otherwise.
+    pub fn handle() -> HANDLE {
+        let heap = HEAP.load(Ordering::Acquire);
+        if !heap.is_null() {
+            return heap;
+        }
+
+        let created = unsafe { HeapCreate(0, 0, 0) };
+        if created.is_null() {
+            return null_mut();
+        }
+
+        // Another thread may have raced us to it, in which case theirs is kept.
+        match HEAP.compare_exchange(null_mut(), created, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => created,
+            Err(existing) => {
+                unsafe { HeapDestroy(created) };
+                existing
+            }
+        }
+    }
+
+    /// Whether `heap` is the private heap, e.g. for a detour of `RtlAllocateHeap` to forward the allocations
+    /// of the payload untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `heap` - The heap passed to the heap function.
+    pub fn is_private(heap: HANDLE) -> bool {
+        !heap.is_null() && heap == HEAP.load(Ordering::Acquire)
+    }
+}
+
+unsafe impl GlobalAlloc for PrivateHeap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        unsafe { allocate(layout, 0) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        unsafe { allocate(layout, HEAP_ZERO_MEMORY) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let block = if layout.align() <= MIN_ALIGN {
+            ptr
+        } else {
+            unsafe { (ptr as *mut *mut u8).sub(1).read() }
+        };
+
+        unsafe { HeapFree(Self::handle(), 0, block as _) };
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if layout.align() <= MIN_ALIGN {
+            return unsafe { HeapReAlloc(Self::handle(), 0, ptr as _, new_size) as *mut u8 };
+        }
+
+        // Over-aligned blocks may land at another offset of the new block, so they're moved by hand.
+        let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+        let new = unsafe { self.alloc(new_layout) };
+        if !new.is_null() {
+            unsafe {
+                copy_nonoverlapping(ptr, new, min(layout.size(), new_size));
+                self.dealloc(ptr, layout);
+            }
+        }
+
+        new
+    }
+}
+
+/// Allocates a block fitting `layout`, storing the pointer to free right before the returned one for
+/// alignments past [`MIN_ALIGN`].
+unsafe fn allocate(layout: Layout, flags: DWORD) -> *mut u8 {
+    let heap = PrivateHeap::handle();
+    if heap.is_null() {
+        return null_mut();
+    }
+
+    if layout.align() <= MIN_ALIGN {
+        return unsafe { HeapAlloc(heap, flags, layout.size()) as *mut u8 };
+    }
+
+    let Some(size) = layout.size().checked_add(layout.align()) else {
+        return null_mut();
+    };
+
+    let block = unsafe { HeapAlloc(heap, flags, size) as *mut u8 };
+    if block.is_null() {
+        return null_mut();
+    }
+
+    // The block is aligned to `MIN_ALIGN` at least, so there's always room for the pointer.
+    let offset = layout.align() - (block as usize & (layout.align() - 1));
+    unsafe {
+        let aligned = block.add(offset);
+        (aligned as *mut *mut u8).sub(1).write(block);
+        aligned
+    }
+}
@@ -0,0 +1,214 @@
+//! Hot-swappable detours.
+//!
+//! Responsible for a development mode where detours live in a separate DLL, which can be rebuilt, and reloaded
+//! while the hooks stay installed. Hooks are created as rebindable, and re-pointed to the freshly loaded
+//! implementations on every reload, see [`crate::guard::DetourGuard::rebind`].
+//!
+//! The DLL is loaded from a copy, so that the build can overwrite it meanwhile. Previous copies are never
+//! unloaded, as calls may still be in flight inside of them, so their files are only deleted once no process
+//! has them loaded anymore, by the next [`HotSwap::load`].
+
+use std::{
+    fs,
+    os::raw::c_void,
+    path::{Path, PathBuf},
+};
+use winapi::um::libloaderapi::{FreeLibrary, LoadLibraryW};
+
+use crate::{
+    error::{Error, Result, ResultExt},
+    guard::DetourGuard,
+    logging,
+    pe::{Module, to_wide},
+};
+
+/// The subdirectory of the temporary directory the copies of the DLLs are loaded from.
+const COPIES: &str = "minhook-detours-hot-swap";
+
+/// A DLL of detours, whose hooks follow it across reloads.
+#[derive(Debug)]
+pub struct HotSwap {
+    path: PathBuf,
+    generation: u32,
+    module: Module,
+    bindings: Vec<(*mut c_void, String)>,
+}
+
+impl HotSwap {
+    /// Loads the DLL of detours at `path`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the DLL, as written by the build.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        // Copies of processes that are gone aren't loaded anywhere anymore.
+        remove_stale_copies(&path);
+
+        let module = load_copy(&path, 0)?;
+
+        Ok(Self {
+            path,
+            generation: 0,
+            module,
+            bindings: Vec::new(),
+        })
+    }
+
+    /// The number of times the DLL was reloaded.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Hooks `target` with the export `symbol` of the DLL, and keeps it bound to that export across reloads.
+    ///
+    /// # Arguments
+    ///
+    /// * `guard` - The guard to register the hook in.
+    /// * `target` - The function to be hooked.
+    /// * `symbol` - The name of the detour, as exported by the DLL.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(&T)` if the hook was succesfully registered. The lifetime of the reference is the lifetime of the [`DetourGuard`].
+    /// - `Err(minhook_detours_rs::error::Error)` if the export, or the operation failed.
+    pub fn hook<'a, T>(
+        &mut self,
+        guard: &mut DetourGuard<'a>,
+        target: *mut c_void,
+        symbol: &str,
+    ) -> Result<&'a T> {
+        let detour = self.module.export(symbol).for_symbol(symbol)?;
+        let original = guard.create_rebindable_hook(target, detour)?;
+
+        self.bindings.push((target, symbol.to_owned()));
+
+        Ok(original)
+    }
+
+    /// Loads the current build of the DLL, and re-points every hook bound through [`HotSwap::hook`] to its
+    /// exports.
+    ///
+    /// Every export is resolved before any hook is re-pointed, so that a build missing one of them leaves
+    /// the hooks on the previous build. Hooks failing to be re-pointed, e.g. as they were removed meanwhile,
+    /// put the ones already re-pointed back on the previous build as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `guard` - The guard the hooks were registered in.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if every hook was succesfully re-pointed.
+    /// - `Err(minhook_detours_rs::error::Error)` if the build couldn't be loaded, or a hook couldn't be
+    ///   re-pointed, in which case every hook stays on the previous build.
+    pub fn reload(&mut self, guard: &mut DetourGuard<'_>) -> Result<()> {
+        let _span = logging::span!("hot_swap_reload", generation = self.generation + 1);
+
+        let module = load_copy(&self.path, self.generation + 1)?;
+
+        let detours = self
+            .bindings
+            .iter()
+            .map(|(target, symbol)| Ok((*target, module.export(symbol).for_symbol(symbol)?)))
+            .collect::<Result<Vec<_>>>();
+
+        let detours = match detours {
+            Ok(detours) => detours,
+            Err(error) => {
+                // Nothing points into the new build, so it can go right away.
+                unsafe { FreeLibrary(module.base() as _) };
+                return Err(error);
+            }
+        };
+
+        let mut rebound = Vec::with_capacity(detours.len());
+        for (target, detour) in detours {
+            let previous = guard.hook_info(target).map(|hook| hook.detour());
+
+            if let Err(error) = guard.rebind(target, detour) {
+                // The new build may already run on the hooks re-pointed so far, so it stays loaded.
+                for (target, previous) in rebound.into_iter().rev() {
+                    let _ = guard.rebind(target, previous);
+                }
+
+                return Err(error);
+            }
+
+            if let Some(previous) = previous {
+                rebound.push((target, previous));
+            }
+        }
+
+        self.module = module;
+        self.generation += 1;
+
+        // We succesfully swapped the detours!
+        logging::info!("Reloaded {} as generation {}", self.path.display(), self.generation);
+        Ok(())
+    }
+}
+
+/// Copies the DLL at `path` aside, and loads the copy, so that `path` stays writable.
+fn load_copy(path: &Path, generation: u32) -> Result<Module> {
+    // The copy is always a new module, which can't be loaded while the loader lock is held.
+    if DetourGuard::is_loader_lock_held() {
+        return Err(Error::LoaderLock);
+    }
+
+    let stem = path.file_stem().ok_or(Error::InvalidModule)?.to_string_lossy();
+    let directory = std::env::temp_dir().join(COPIES);
+    let copy = directory.join(format!("{stem}-{}-{generation}.dll", std::process::id()));
+
+    fs::create_dir_all(&directory)
+        .and_then(|_| fs::copy(path, &copy))
+        .map_err(|_| Error::ModuleNotFound)
+        .for_symbol(path.display().to_string())?;
+
+    let handle = unsafe { LoadLibraryW(to_wide(&copy.to_string_lossy()).as_ptr()) };
+
+    let module = unsafe { Module::from_handle(handle) }.for_symbol(copy.display().to_string());
+    if module.is_err() {
+        if !handle.is_null() {
+            unsafe { FreeLibrary(handle) };
+        }
+        let _ = fs::remove_file(&copy);
+    }
+
+    module
+}
+
+/// Deletes the copies of the DLL at `path` left behind by other processes.
+///
+/// Copies still loaded by a running process can't be deleted, so they are skipped until a later call.
+fn remove_stale_copies(path: &Path) {
+    let Some(stem) = path.file_stem() else {
+        return;
+    };
+
+    let Ok(entries) = fs::read_dir(std::env::temp_dir().join(COPIES)) else {
+        return;
+    };
+
+    let prefix = format!("{}-", stem.to_string_lossy());
+
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+
+        // Copies are named `{stem}-{process}-{generation}.dll`.
+        let process = name
+            .to_string_lossy()
+            .strip_prefix(&prefix)
+            .and_then(|name| name.strip_suffix(".dll"))
+            .and_then(|name| name.split_once('-'))
+            .and_then(|(process, generation)| {
+                generation.parse::<u32>().ok()?;
+                process.parse::<u32>().ok()
+            });
+
+        if process.is_some_and(|process| process != std::process::id()) {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! Delay-load import hooking.
+//!
+//! Responsible for redirecting the calls a module makes to a delay-loaded API, by swapping the slot of its
+//! delay-load import table rather than patching code. Slots that weren't snapped yet, i.e. still pointing at
+//! the module's own delay-load helper, are resolved right away, without waiting for the first call.
+
+use std::{ffi::CString, os::raw::c_void};
+use winapi::{
+    shared::minwindef::HMODULE,
+    um::libloaderapi::{FreeLibrary, GetModuleHandleA, GetProcAddress, LoadLibraryA},
+};
+
+use crate::{
+    error::{Error, Result},
+    guard::DetourGuard,
+    logging,
+    mem,
+    pe::Module,
+};
+
+/// A delay-load import redirected to a detour, restored once dropped.
+///
+/// Unsnapped slots are restored to their thunk, so that the delay-load helper still runs on the first call.
+#[derive(Debug)]
+pub struct DelayImportHook {
+    slot: *mut *mut c_void,
+    detour: *mut c_void,
+    original: *mut c_void,
+    /// The value of the slot before it was swapped.
+    previous: *mut c_void,
+    /// The reference to the delay-loaded module taken to resolve an unsnapped slot, keeping `original` alive.
+    library: Option<HMODULE>,
+}
+
+impl DelayImportHook {
+    /// Redirects the calls `module` makes to `symbol` of the delay-loaded `dll` to `detour`.
+    ///
+    /// # Arguments
+    ///
+    /// * `module` - The name of the importing module, e.g. `"game.exe"`.
+    /// * `dll` - The name of the delay-loaded module, compared case-insensitively, e.g. `"user32.dll"`.
+    /// * `symbol` - The name of the import.
+    /// * `detour` - The function to call instead.
+    ///
+    /// # Safety
+    ///
+    /// `detour` must have the signature of the import.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(DelayImportHook)` if the slot was succesfully swapped.
+    /// - `Err(minhook_detours_rs::error::Error::InvalidImport)` if `module` doesn't delay-load the import.
+    /// - `Err(minhook_detours_rs::error::Error)` if the import couldn't be resolved.
+    pub unsafe fn new(module: &str, dll: &str, symbol: &str, detour: *mut c_void) -> Result<Self> {
+        let _span = logging::span!("delay_import_hook", dll = dll, symbol = symbol);
+
+        if detour.is_null() {
+            return Err(Error::InvalidTarget);
+        }
+
+        let module = Module::from_name(module)?;
+        let slot = module.delay_import_slot(dll, symbol)?;
+
+        // Unsnapped slots point at a thunk of the importing module, calling into the delay-load helper.
+        let previous = unsafe { slot.read_volatile() };
+        let (original, library) = if module.contains(previous as _) {
+            let (original, library) = resolve(dll, symbol)?;
+            (original, Some(library))
+        } else {
+            (previous, None)
+        };
+
+        if let Err(error) = unsafe { write_slot(slot, detour) } {
+            if let Some(library) = library {
+                unsafe { FreeLibrary(library) };
+            }
+            return Err(error);
+        }
+
+        // We succesfully redirected the import!
+        logging::debug!("Redirected delay-load import {dll}!{symbol} to {detour:p}");
+        Ok(Self {
+            slot,
+            detour,
+            original,
+            previous,
+            library,
+        })
+    }
+
+    /// The function the import resolved to, which the detour can forward to.
+    pub fn original(&self) -> *mut c_void {
+        self.original
+    }
+
+    /// The slot of the delay-load import table that was swapped.
+    pub fn slot(&self) -> *mut *mut c_void {
+        self.slot
+    }
+}
+
+impl Drop for DelayImportHook {
+    fn drop(&mut self) {
+        // The slot was swapped again since, so restoring would unlink whoever did. The module stays
+        // referenced, as whoever did may still forward to `original` through the detour.
+        if unsafe { self.slot.read_volatile() } != self.detour {
+            return;
+        }
+
+        if let Err(e) = unsafe { write_slot(self.slot, self.previous) } {
+            logging::warn!("DelayImportHook drop failed: {e}");
+            return;
+        }
+
+        // Nothing points into the module on our behalf anymore.
+        if let Some(library) = self.library.take() {
+            unsafe { FreeLibrary(library) };
+        }
+    }
+}
+
+/// Loads `dll`, and resolves `symbol` out of it, as the delay-load helper would on the first call.
+///
+/// The returned module reference has to be released through `FreeLibrary`, once nothing uses the symbol.
+fn resolve(dll: &str, symbol: &str) -> Result<(*mut c_void, HMODULE)> {
+    let dll = CString::new(dll).map_err(|_| Error::InvalidModule)?;
+    let symbol = CString::new(symbol).map_err(|_| Error::InvalidExport)?;
+
+    // Loading a module the process doesn't have yet would deadlock, or run its `DllMain` out of order.
+    let loaded = unsafe { GetModuleHandleA(dll.as_ptr()) };
+    if loaded.is_null() && DetourGuard::is_loader_lock_held() {
+        return Err(Error::LoaderLock);
+    }
+
+    let module = unsafe { LoadLibraryA(dll.as_ptr()) };
+    if module.is_null() {
+        return Err(Error::ModuleNotFound);
+    }
+
+    let address = unsafe { GetProcAddress(module, symbol.as_ptr()) };
+    if address.is_null() {
+        unsafe { FreeLibrary(module) };
+        return Err(Error::InvalidExport);
+    }
+
+    Ok((address as *mut c_void, module))
+}
+
+/// Writes `value` to `slot`, which usually lives in read-only memory once the module is loaded.
+///
+/// # Safety
+///
+/// `slot` must be a slot of an import table of a loaded module.
+unsafe fn write_slot(slot: *mut *mut c_void, value: *mut c_void) -> Result<()> {
+    unsafe { mem::write(slot as *mut c_void, value) }
+}
@@ -0,0 +1,91 @@
+//! `windows-sys` interoperability.
+//!
+//! Responsible for accepting, and returning the handle, and function pointer types of `windows-sys` at the
+//! crate's API boundary, so that they don't have to be transmuted into raw pointers by hand.
+//!
+//! Originals can be requested as [`FARPROC`] directly, e.g. `guard.create_hook::<FARPROC>(...)`.
+
+use std::{os::raw::c_void, ptr::null_mut};
+pub use windows_sys::{
+    Win32::Foundation::{FARPROC, HANDLE, HMODULE},
+    core::PCWSTR,
+};
+
+use crate::{
+    error::{Error, Result},
+    guard::DetourGuard,
+    pe::Module,
+    scan::{Pattern, scan_module_handle},
+    target::Target,
+};
+
+/// Convert a [`FARPROC`] into the raw pointer the engine operates on, where `None` becomes null.
+pub fn farproc_to_ptr(proc: FARPROC) -> *mut c_void {
+    proc.map_or(null_mut(), |proc| proc as *mut c_void)
+}
+
+/// Convert a null-terminated wide string into an owned [`String`], replacing invalid UTF-16.
+///
+/// # Safety
+///
+/// `value` must be non-null, and point to a null-terminated UTF-16 string.
+pub unsafe fn pcwstr_to_string(value: PCWSTR) -> String {
+    let length = (0..).take_while(|&index| unsafe { *value.add(index) } != 0).count();
+
+    String::from_utf16_lossy(unsafe { std::slice::from_raw_parts(value, length) })
+}
+
+/// Look up the module whose image contains `address`.
+///
+/// # Returns
+///
+/// - `Ok(HMODULE)` if `address` belongs to a loaded module.
+/// - `Err(minhook_detours_rs::error::Error::InvalidModule)` otherwise.
+pub fn owning_module(address: *const c_void) -> Result<HMODULE> {
+    Ok(Module::from_address(address)?.handle() as HMODULE)
+}
+
+/// Scan the executable sections of a loaded module for `pattern`.
+///
+/// # Safety
+///
+/// `module` must be the base address of a module mapped in the current process.
+pub unsafe fn scan_hmodule(module: HMODULE, pattern: &Pattern) -> Result<Vec<*mut c_void>> {
+    unsafe { scan_module_handle(module as _, pattern) }
+}
+
+impl From<FARPROC> for Target {
+    fn from(proc: FARPROC) -> Self {
+        Self::Address(farproc_to_ptr(proc))
+    }
+}
+
+impl Target {
+    /// Describe an export of a loaded module, whose name is a wide string.
+    ///
+    /// # Safety
+    ///
+    /// `module` must be non-null, and point to a null-terminated UTF-16 string.
+    pub unsafe fn export_w(module: PCWSTR, symbol: impl Into<String>) -> Self {
+        Self::export(unsafe { pcwstr_to_string(module) }, symbol)
+    }
+}
+
+impl<'a> DetourGuard<'a> {
+    /// Registers entry for the function pointed to by `target` in the hooking engine's internal registry.
+    ///
+    /// Refer to [`DetourGuard::create_hook`] for further explaination.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(&T)` if the hook was succesfully registered.
+    /// - `Err(minhook_detours_rs::error::Error::InvalidTarget)` if `target` is `None`.
+    /// - `Err(minhook_detours_rs::error::Error)` if the operation failed.
+    pub fn create_hook_proc<T>(&mut self, target: FARPROC, detour: FARPROC) -> Result<&'a T> {
+        if target.is_none() {
+            return Err(Error::InvalidTarget);
+        }
+
+        self.create_hook(farproc_to_ptr(target), farproc_to_ptr(detour))
+    }
+}
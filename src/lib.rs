@@ -1,3 +1,45 @@
 #![cfg(target_os = "windows")]
+mod arch;
+#[cfg(feature = "bench")]
+pub mod bench;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod compat;
+pub mod detour;
+mod engine;
 pub mod error;
+#[cfg(feature = "etw")]
+pub mod etw;
+#[cfg(feature = "graphics")]
+pub mod graphics;
 pub mod guard;
+pub mod heap;
+#[cfg(feature = "hot-swap")]
+pub mod hot_swap;
+pub mod import;
+#[cfg(feature = "windows-sys")]
+pub mod interop;
+mod logging;
+#[cfg(feature = "manifest")]
+pub mod manifest;
+pub mod mem;
+#[cfg(all(feature = "monitor", any(target_arch = "x86", target_arch = "x86_64")))]
+pub mod monitor;
+mod pe;
+#[cfg(feature = "registry")]
+pub mod registry;
+pub mod ring;
+pub mod scan;
+#[cfg(feature = "symbol")]
+pub mod symbol;
+#[cfg(feature = "hook-table")]
+pub mod table;
+pub mod target;
+#[cfg(feature = "test-support")]
+pub mod test_support;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod tls;
+#[cfg(feature = "window")]
+pub mod window;
+pub mod wow64;
@@ -0,0 +1,96 @@
+//! Logging.
+//!
+//! Responsible for forwarding the crate's activity to the `log`, and `tracing` crates when the matching
+//! features are enabled, and compiling down to nothing otherwise.
+//!
+//! With both features enabled, records only go through `tracing`, which forwards them to `log` as long as no
+//! subscriber is set, so that they aren't emitted twice.
+
+use std::{fmt, os::raw::c_void};
+
+macro_rules! debug {
+    ($($arg:tt)*) => {{
+        #[cfg(all(feature = "log", not(feature = "tracing")))]
+        ::log::debug!(target: "minhook_detours_rs", $($arg)*);
+        #[cfg(feature = "tracing")]
+        ::tracing::debug!(target: "minhook_detours_rs", $($arg)*);
+        #[cfg(not(any(feature = "log", feature = "tracing")))]
+        let _ = format_args!($($arg)*);
+    }};
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => {{
+        #[cfg(all(feature = "log", not(feature = "tracing")))]
+        ::log::info!(target: "minhook_detours_rs", $($arg)*);
+        #[cfg(feature = "tracing")]
+        ::tracing::info!(target: "minhook_detours_rs", $($arg)*);
+        #[cfg(not(any(feature = "log", feature = "tracing")))]
+        let _ = format_args!($($arg)*);
+    }};
+}
+
+/// Report something that went wrong without a caller to return it to, e.g. while dropping. Without either
+/// feature, the record still goes to stderr, rather than nowhere.
+macro_rules! warn {
+    ($($arg:tt)*) => {{
+        #[cfg(all(feature = "log", not(feature = "tracing")))]
+        ::log::warn!(target: "minhook_detours_rs", $($arg)*);
+        #[cfg(feature = "tracing")]
+        ::tracing::warn!(target: "minhook_detours_rs", $($arg)*);
+        #[cfg(not(any(feature = "log", feature = "tracing")))]
+        ::std::eprintln!($($arg)*);
+    }};
+}
+
+/// Whether debug records of the crate would be emitted, so that building costly ones can be skipped otherwise.
+pub(crate) fn debug_enabled() -> bool {
+    #[cfg(feature = "tracing")]
+    if ::tracing::enabled!(target: "minhook_detours_rs", ::tracing::Level::DEBUG) {
+        return true;
+    }
+
+    // Records reach `log` through `tracing` as long as no subscriber is set.
+    #[cfg(feature = "log")]
+    if ::log::log_enabled!(target: "minhook_detours_rs", ::log::Level::Debug) {
+        return true;
+    }
+
+    false
+}
+
+/// Enter a `tracing` span, exited once the returned value is dropped.
+///
+/// Timing is left to the subscriber, e.g. `FmtSpan::CLOSE` reports the time spent inside of every span.
+macro_rules! span {
+    ($name:literal $(, $($field:tt)*)?) => {{
+        #[cfg(feature = "tracing")]
+        let span = ::tracing::debug_span!(target: "minhook_detours_rs", $name $(, $($field)*)?).entered();
+        #[cfg(not(feature = "tracing"))]
+        let span = $crate::logging::NoSpan;
+        span
+    }};
+}
+
+/// An address in a log, or error message, shown as `module!symbol+offset` along with the raw address with the
+/// `symbol` feature, see [`crate::symbol::symbolize`], and as the raw address otherwise.
+///
+/// Symbolized lazily, so only once the message is actually formatted.
+pub(crate) struct Address(pub(crate) *const c_void);
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(feature = "symbol")]
+        if let Some(symbol) = crate::symbol::symbolize(self.0) {
+            return write!(f, "{symbol} ({:p})", self.0);
+        }
+
+        write!(f, "{:p}", self.0)
+    }
+}
+
+/// Stand-in for an entered span, without the `tracing` feature.
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct NoSpan;
+
+pub(crate) use {debug, info, span, warn};
@@ -0,0 +1,205 @@
+//! Declarative hook manifests.
+//!
+//! Responsible for reading a TOML description of hooks, and wiring each entry to a detour registered in code
+//! by name, so that the active set of hooks can change without rebuilding.
+//!
+//! ```toml
+//! [[hook]]
+//! name = "create_file"
+//! module = "kernel32.dll"
+//! symbol = "CreateFileW"   # Alternatively, `ordinal = 16`, `rva = 0x1234`, or `pattern = "48 8B ?? ?? 57"`.
+//! detour = "create_file_hook"
+//! group = "io"
+//! enabled = true
+//! ```
+
+use serde::Deserialize;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    os::raw::c_void,
+    path::Path,
+    str::FromStr,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+use crate::{
+    error::{Error, Result},
+    guard::DetourGuard,
+    scan::Pattern,
+    target::Target,
+};
+
+/// A parsed hook manifest.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct HookManifest {
+    #[serde(default, rename = "hook")]
+    pub hooks: Vec<HookSpec>,
+}
+
+/// Description of a single hook of a [`HookManifest`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct HookSpec {
+    /// The human-readable name of the hook.
+    pub name: String,
+    /// The module containing the target, e.g. `"kernel32.dll"`.
+    pub module: String,
+    /// Where the target is located inside of `module`.
+    #[serde(flatten)]
+    pub location: Location,
+    /// Displacement of the target, in bytes.
+    #[serde(default)]
+    pub offset: usize,
+    /// The name the detour was registered with, see [`DetourRegistry::register`].
+    pub detour: String,
+    /// The group the hook belongs to.
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Whether the hook is enabled right after being created.
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+}
+
+/// Location of a [`HookSpec`]'s target inside of its module.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[serde(rename_all = "snake_case")]
+pub enum Location {
+    /// The name of an export.
+    Symbol(String),
+    /// The ordinal of an export.
+    Ordinal(u16),
+    /// A relative virtual address.
+    Rva(usize),
+    /// An IDA-style byte pattern, which must match exactly once.
+    Pattern(String),
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl HookManifest {
+    /// Read, and parse the manifest at `path`.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let manifest = std::fs::read_to_string(path)
+            .map_err(|error| Error::InvalidManifest(error.to_string()))?;
+
+        manifest.parse()
+    }
+}
+
+impl FromStr for HookManifest {
+    type Err = Error;
+
+    fn from_str(manifest: &str) -> Result<Self> {
+        toml::from_str(manifest).map_err(|error| Error::InvalidManifest(error.to_string()))
+    }
+}
+
+impl HookSpec {
+    /// Describe the hook's target, to be resolved at hook time.
+    pub fn target(&self) -> Result<Target> {
+        let module = self.module.clone();
+
+        let target = match &self.location {
+            Location::Symbol(symbol) => Target::export(module, symbol.clone()),
+            Location::Ordinal(ordinal) => Target::ordinal(module, *ordinal),
+            Location::Rva(rva) => Target::Rva { module, rva: *rva },
+            Location::Pattern(pattern) => Target::Pattern {
+                module,
+                pattern: Pattern::new(pattern)?,
+            },
+        };
+
+        Ok(target.offset(self.offset))
+    }
+}
+
+/// Detours available to a [`HookManifest`], keyed by name.
+#[derive(Debug, Default)]
+pub struct DetourRegistry {
+    detours: HashMap<String, RegisteredDetour>,
+}
+
+#[derive(Debug)]
+struct RegisteredDetour {
+    detour: *mut c_void,
+    original: &'static AtomicPtr<c_void>,
+}
+
+impl DetourRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make `detour` available to manifests under `name`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name manifests refer to the detour by.
+    /// * `detour` - The place where the function will jump to, while hooked.
+    /// * `original` - Where the pointer to the original function is stored, once the hook is created.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        detour: *mut c_void,
+        original: &'static AtomicPtr<c_void>,
+    ) -> &mut Self {
+        self.detours
+            .insert(name.into(), RegisteredDetour { detour, original });
+        self
+    }
+}
+
+impl<'a> DetourGuard<'a> {
+    /// Creates every hook described by `manifest`, enabling the ones marked as such.
+    ///
+    /// The name, and group of every entry are assigned to the created hook, see [`DetourGuard::set_hook_name`]
+    /// and [`DetourGuard::set_hook_group`].
+    ///
+    /// # Arguments
+    ///
+    /// * `manifest` - The hooks to create.
+    /// * `detours` - The detours the manifest may refer to.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Vec<*mut c_void>)` with the resolved target of every entry, in order.
+    /// - `Err(minhook_detours_rs::error::Error)` on the first entry that failed. Hooks created for previous
+    ///   entries are left in place.
+    pub fn load_manifest(
+        &mut self,
+        manifest: &HookManifest,
+        detours: &DetourRegistry,
+    ) -> Result<Vec<*mut c_void>> {
+        let mut targets = Vec::with_capacity(manifest.hooks.len());
+
+        for spec in &manifest.hooks {
+            let detour = detours
+                .detours
+                .get(&spec.detour)
+                .ok_or_else(|| Error::UnknownDetour(spec.detour.clone()))?;
+
+            let (target, original) =
+                self.create_hook_at::<*mut c_void>(&spec.target()?, detour.detour)?;
+            detour.original.store(*original, Ordering::Release);
+
+            self.set_hook_name(target, spec.name.clone())?;
+            if let Some(group) = &spec.group {
+                self.set_hook_group(target, group.clone())?;
+            }
+
+            if spec.enabled {
+                self.enable_hook(target)?;
+            }
+
+            targets.push(target);
+        }
+
+        Ok(targets)
+    }
+}
@@ -0,0 +1,370 @@
+//! Memory helpers.
+//!
+//! Responsible for the small data patches that often go along with detours, e.g. flipping a flag living in
+//! a read-only section, or swapping a pointer of a vtable.
+
+use std::{
+    mem::{size_of, zeroed},
+    os::raw::c_void,
+    ptr::copy_nonoverlapping,
+};
+use winapi::um::{
+    errhandlingapi::GetLastError,
+    memoryapi::{VirtualAlloc, VirtualProtect, VirtualQuery},
+    processthreadsapi::{FlushInstructionCache, GetCurrentProcess},
+    winnt::{
+        MEM_COMMIT, MEM_FREE, MEM_RESERVE, MEMORY_BASIC_INFORMATION, PAGE_EXECUTE,
+        PAGE_EXECUTE_READ, PAGE_EXECUTE_READWRITE, PAGE_EXECUTE_WRITECOPY, PAGE_GUARD, PAGE_NOACCESS,
+        PAGE_READWRITE,
+    },
+};
+
+use crate::{
+    error::{Error, Result},
+    logging,
+};
+
+mod patch;
+
+pub use patch::Patch;
+
+/// The protections under which the pages of a range can be executed.
+const EXECUTABLE: u32 =
+    PAGE_EXECUTE | PAGE_EXECUTE_READ | PAGE_EXECUTE_READWRITE | PAGE_EXECUTE_WRITECOPY;
+
+/// The granularity at which Windows hands out allocations, whatever the architecture.
+const ALLOCATION_GRANULARITY: usize = 0x10000;
+
+/// The distance a relative jump reaches, short of the allocation granularity, so that the whole allocation
+/// stays reachable.
+#[cfg(target_pointer_width = "64")]
+const NEAR: usize = 0x7FFF0000;
+
+/// The whole address space is reachable through a relative jump on 32-bit processes.
+#[cfg(target_pointer_width = "32")]
+const NEAR: usize = usize::MAX;
+
+/// A range of memory whose protection was changed, restored once dropped.
+///
+/// The range may span several regions, each of whose protection is restored on its own.
+#[derive(Debug)]
+pub struct ProtectGuard {
+    address: *mut c_void,
+    size: usize,
+    /// `(address, size, protection)` of every region of the range, as it was before the change.
+    regions: Vec<(usize, usize, u32)>,
+}
+
+impl ProtectGuard {
+    /// Makes `size` bytes at `address` writable, until the [`ProtectGuard`] is dropped.
+    ///
+    /// Executable pages stay executable, as other threads may be running their code meanwhile.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The start of the range.
+    /// * `size` - The size of the range, in bytes.
+    ///
+    /// # Safety
+    ///
+    /// The range must not be freed while the [`ProtectGuard`] is alive.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(ProtectGuard)` if the range was succesfully made writable.
+    /// - `Err(minhook_detours_rs::error::Error::InvalidTarget)` if the range isn't entirely accessible.
+    /// - `Err(minhook_detours_rs::error::Error::MemoryProtection)` otherwise, with the last Win32 error.
+    pub unsafe fn make_writable(address: *mut c_void, size: usize) -> Result<Self> {
+        unsafe {
+            Self::protect_with(address, size, |previous| match previous & EXECUTABLE {
+                0 => PAGE_READWRITE,
+                _ => PAGE_EXECUTE_READWRITE,
+            })
+        }
+    }
+
+    /// Changes the protection of `size` bytes at `address` to `protection`, until the [`ProtectGuard`] is
+    /// dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The start of the range.
+    /// * `size` - The size of the range, in bytes.
+    /// * `protection` - The new protection, e.g. `PAGE_READONLY`.
+    ///
+    /// # Safety
+    ///
+    /// The range must not be freed while the [`ProtectGuard`] is alive, and code relying on its previous
+    /// protection, e.g. code executing from it, must not run meanwhile.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(ProtectGuard)` if the protection was succesfully changed.
+    /// - `Err(minhook_detours_rs::error::Error)` otherwise.
+    pub unsafe fn protect(address: *mut c_void, size: usize, protection: u32) -> Result<Self> {
+        unsafe { Self::protect_with(address, size, |_| protection) }
+    }
+
+    /// Changes the protection of every region of the range to the one `protection` picks from its current
+    /// protection.
+    unsafe fn protect_with(
+        address: *mut c_void,
+        size: usize,
+        protection: impl Fn(u32) -> u32,
+    ) -> Result<Self> {
+        if !is_accessible(address, size) {
+            return Err(Error::InvalidTarget);
+        }
+
+        // Regions changed so far are restored by dropping the guard, should a later one fail.
+        let mut guard = Self {
+            address,
+            size,
+            regions: Vec::new(),
+        };
+
+        let end = address as usize + size;
+        let mut current = address as usize;
+
+        while current < end {
+            let region = query(current as _).ok_or(Error::InvalidTarget)?;
+            let region_end = (region.BaseAddress as usize + region.RegionSize).min(end);
+            let region_size = region_end - current;
+
+            let mut previous = 0;
+            let changed = unsafe {
+                VirtualProtect(
+                    current as _,
+                    region_size,
+                    protection(region.Protect),
+                    &mut previous,
+                )
+            };
+            if changed == 0 {
+                return Err(Error::MemoryProtection(unsafe { GetLastError() }));
+            }
+
+            guard.regions.push((current, region_size, previous));
+            current = region_end;
+        }
+
+        // We succesfully changed the protection!
+        logging::debug!("Changed the protection of {size} bytes at {address:p}");
+        Ok(guard)
+    }
+
+    /// The start of the range.
+    pub fn address(&self) -> *mut c_void {
+        self.address
+    }
+
+    /// The size of the range, in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The protection of the first region of the range, restored once dropped.
+    pub fn previous(&self) -> u32 {
+        self.regions.first().map_or(0, |(_, _, previous)| *previous)
+    }
+
+    /// `(address, size, protection)` of every region of the range, restored once dropped.
+    pub fn regions(&self) -> &[(usize, usize, u32)] {
+        &self.regions
+    }
+}
+
+impl Drop for ProtectGuard {
+    fn drop(&mut self) {
+        for &(address, size, previous) in self.regions.iter().rev() {
+            let mut protection = 0;
+            let restored = unsafe { VirtualProtect(address as _, size, previous, &mut protection) };
+
+            if restored == 0 {
+                let e = unsafe { GetLastError() };
+                logging::warn!("ProtectGuard drop failed: {e}");
+                continue;
+            }
+
+            // Code may have been patched through the region.
+            if previous & EXECUTABLE != 0 {
+                unsafe { FlushInstructionCache(GetCurrentProcess(), address as _, size) };
+            }
+        }
+    }
+}
+
+/// Whether `size` bytes at `address` are committed, and readable.
+///
+/// # Arguments
+///
+/// * `address` - The start of the range.
+/// * `size` - The size of the range, in bytes.
+pub fn is_accessible(address: *const c_void, size: usize) -> bool {
+    // Ranges wrapping around the address space can't be accessible.
+    let Some(end) = (address as usize).checked_add(size) else {
+        return false;
+    };
+    let mut current = address as usize;
+
+    // The range may span several regions, each with their own protection.
+    while current < end {
+        let Some(region) = query(current as _) else {
+            return false;
+        };
+
+        if region.State != MEM_COMMIT || region.Protect & (PAGE_NOACCESS | PAGE_GUARD) != 0 {
+            return false;
+        }
+
+        current = region.BaseAddress as usize + region.RegionSize;
+    }
+
+    true
+}
+
+/// Copies `size` bytes at `address`, checking that they're accessible first.
+///
+/// # Arguments
+///
+/// * `address` - The start of the range.
+/// * `size` - The size of the range, in bytes.
+///
+/// # Safety
+///
+/// The range must not be freed, or made inaccessible by another thread between the check, and the copy.
+///
+/// # Returns
+///
+/// - `Ok(Vec<u8>)` with the bytes, if they were succesfully read.
+/// - `Err(minhook_detours_rs::error::Error::InvalidTarget)` if the range isn't entirely accessible.
+pub unsafe fn read_bytes(address: *const c_void, size: usize) -> Result<Vec<u8>> {
+    if address.is_null() || !is_accessible(address, size) {
+        return Err(Error::InvalidTarget);
+    }
+
+    let mut bytes = vec![0; size];
+    unsafe { copy_nonoverlapping(address as *const u8, bytes.as_mut_ptr(), size) };
+
+    Ok(bytes)
+}
+
+/// Reads a `T` at `address`, checking that it's accessible first. The address doesn't need to be aligned.
+///
+/// # Safety
+///
+/// The bytes at `address` must be a valid `T`, and stay accessible while they're read, see [`read_bytes`].
+pub unsafe fn read<T: Copy>(address: *const c_void) -> Result<T> {
+    let bytes = unsafe { read_bytes(address, size_of::<T>())? };
+
+    Ok(unsafe { (bytes.as_ptr() as *const T).read_unaligned() })
+}
+
+/// Overwrites the bytes at `address` with `bytes`, making them writable for the duration of the write.
+///
+/// # Arguments
+///
+/// * `address` - The start of the range.
+/// * `bytes` - The bytes to write.
+///
+/// # Safety
+///
+/// Nothing may be reading, or executing the range while it's written.
+///
+/// # Returns
+///
+/// - `Ok(())` if the bytes were succesfully written.
+/// - `Err(minhook_detours_rs::error::Error)` if the range couldn't be made writable.
+pub unsafe fn write_bytes(address: *mut c_void, bytes: &[u8]) -> Result<()> {
+    if address.is_null() {
+        return Err(Error::InvalidTarget);
+    }
+
+    let _writable = unsafe { ProtectGuard::make_writable(address, bytes.len())? };
+    unsafe { copy_nonoverlapping(bytes.as_ptr(), address as *mut u8, bytes.len()) };
+
+    Ok(())
+}
+
+/// Overwrites the `T` at `address` with `value`, making it writable for the duration of the write. The
+/// address doesn't need to be aligned.
+///
+/// The write isn't atomic, so threads reading the value concurrently, e.g. calling through a vtable, may
+/// observe half of it.
+///
+/// # Safety
+///
+/// Nothing may be reading, or executing the range while it's written.
+pub unsafe fn write<T: Copy>(address: *mut c_void, value: T) -> Result<()> {
+    if address.is_null() {
+        return Err(Error::InvalidTarget);
+    }
+
+    let _writable = unsafe { ProtectGuard::make_writable(address, size_of::<T>())? };
+    unsafe { (address as *mut T).write_unaligned(value) };
+
+    Ok(())
+}
+
+/// Allocates `size` bytes of read-write memory within +-2GiB of `address`, so that code placed there, and code
+/// at `address` reach each other through relative jumps.
+///
+/// The memory is freed with `VirtualFree(allocation, 0, MEM_RELEASE)`.
+///
+/// # Arguments
+///
+/// * `address` - The address the allocation should be near of, e.g. the target of a hook.
+/// * `size` - The size of the allocation, in bytes.
+///
+/// # Returns
+///
+/// - `Ok(*mut c_void)` with the allocation, if it was succesfully made.
+/// - `Err(minhook_detours_rs::error::Error::FailedAllocatingMemory)` if no free region is near enough.
+pub fn alloc_near(address: *const c_void, size: usize) -> Result<*mut c_void> {
+    let low = (address as usize)
+        .saturating_sub(NEAR)
+        .max(ALLOCATION_GRANULARITY);
+    let high = (address as usize).saturating_add(NEAR);
+
+    // Walk the regions upwards from the lowest reachable one, until one is free, and large enough.
+    let mut current = align_up(low);
+    while current.saturating_add(size) <= high {
+        let Some(region) = query(current as _) else {
+            break;
+        };
+
+        let end = region.BaseAddress as usize + region.RegionSize;
+        if region.State == MEM_FREE && end - current >= size {
+            let allocation = unsafe {
+                VirtualAlloc(current as _, size, MEM_COMMIT | MEM_RESERVE, PAGE_READWRITE)
+            };
+
+            if !allocation.is_null() {
+                // We succesfully found a spot!
+                logging::debug!("Allocated {size} bytes at {allocation:p}, near {address:p}");
+                return Ok(allocation);
+            }
+        }
+
+        current = align_up(end);
+    }
+
+    Err(Error::FailedAllocatingMemory)
+}
+
+/// Whether `first`, and `second` are within reach of each other through a relative jump.
+pub(crate) fn is_near(first: *const c_void, second: *const c_void) -> bool {
+    (first as usize).abs_diff(second as usize) <= NEAR
+}
+
+fn align_up(address: usize) -> usize {
+    address.saturating_add(ALLOCATION_GRANULARITY - 1) & !(ALLOCATION_GRANULARITY - 1)
+}
+
+fn query(address: *const c_void) -> Option<MEMORY_BASIC_INFORMATION> {
+    let mut region: MEMORY_BASIC_INFORMATION = unsafe { zeroed() };
+    let size = size_of::<MEMORY_BASIC_INFORMATION>();
+
+    let written = unsafe { VirtualQuery(address as _, &mut region, size) };
+    (written == size).then_some(region)
+}
@@ -0,0 +1,216 @@
+use std::os::raw::c_void;
+
+use crate::{
+    error::{Error, Result},
+    logging,
+    mem::{read_bytes, write_bytes},
+};
+
+/// Raw bytes written over memory, e.g. to skip a check, or change a constant, restored once dropped.
+///
+/// Protection is handled for the duration of each write, and instruction caches are flushed for code, but
+/// threads aren't frozen: patch code that may run meanwhile with care, or through a hook instead.
+#[derive(Debug)]
+pub struct Patch {
+    address: *mut c_void,
+    bytes: Vec<u8>,
+    original: Vec<u8>,
+    applied: bool,
+}
+
+impl Patch {
+    /// Writes `bytes` at `address`, remembering the bytes they replace.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - Where to write the bytes.
+    /// * `bytes` - The bytes to write.
+    ///
+    /// # Safety
+    ///
+    /// Nothing may be reading, or executing the range while it's written, and the range must stay valid until
+    /// the [`Patch`] is restored, or dropped.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Patch)` if the bytes were succesfully written.
+    /// - `Err(minhook_detours_rs::error::Error)` if the range isn't accessible, or couldn't be made writable.
+    pub unsafe fn apply(address: *mut c_void, bytes: &[u8]) -> Result<Self> {
+        if bytes.is_empty() {
+            return Err(Error::InvalidTarget);
+        }
+
+        let original = unsafe { read_bytes(address, bytes.len())? };
+        unsafe { write_bytes(address, bytes)? };
+
+        // We succesfully patched the bytes!
+        logging::debug!("Patched {} bytes at {address:p}", bytes.len());
+        Ok(Self {
+            address,
+            bytes: bytes.to_vec(),
+            original,
+            applied: true,
+        })
+    }
+
+    /// Overwrites `len` bytes at `address` with no-op instructions, e.g. to skip a call, or a check.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The start of the instructions to skip.
+    /// * `len` - The size of the instructions to skip, in bytes. A multiple of 4 on ARM64.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`Patch::apply`]. The range must cover whole instructions.
+    pub unsafe fn nop(address: *mut c_void, len: usize) -> Result<Self> {
+        unsafe { Self::apply(address, &nop(len)?) }
+    }
+
+    /// Overwrites the instruction at `address` with a return, e.g. to turn a function into a no-op.
+    ///
+    /// The return value is whatever the return register holds, and stack arguments aren't popped, so only
+    /// use it at the start of functions returning nothing, with a caller-cleaned calling convention.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`Patch::apply`].
+    pub unsafe fn ret(address: *mut c_void) -> Result<Self> {
+        unsafe { Self::apply(address, &ret()) }
+    }
+
+    /// Overwrites the instructions at `address` with an unconditional jump to `destination`.
+    ///
+    /// A relative jump is used whenever `destination` is in range of one, an absolute one otherwise, which
+    /// overwrites up to 14 bytes on x64, and 16 bytes on ARM64.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`Patch::apply`]. The overwritten range must cover whole instructions.
+    pub unsafe fn jmp(address: *mut c_void, destination: *const c_void) -> Result<Self> {
+        unsafe { Self::apply(address, &jmp(address, destination)) }
+    }
+
+    /// Where the bytes were written.
+    pub fn address(&self) -> *mut c_void {
+        self.address
+    }
+
+    /// The bytes written.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// The bytes that were replaced, and are written back once restored.
+    pub fn original(&self) -> &[u8] {
+        &self.original
+    }
+
+    /// Whether the bytes are still written, i.e. the [`Patch`] wasn't restored yet.
+    pub fn is_applied(&self) -> bool {
+        self.applied
+    }
+
+    /// Writes the original bytes back. Restoring again does nothing.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if the original bytes were succesfully written back, or already were.
+    /// - `Err(minhook_detours_rs::error::Error)` if the range couldn't be made writable.
+    pub fn restore(&mut self) -> Result<()> {
+        if !self.applied {
+            return Ok(());
+        }
+
+        // Upheld by the contract of [`Patch::apply`].
+        unsafe { write_bytes(self.address, &self.original)? };
+        self.applied = false;
+
+        // We succesfully restored the bytes!
+        logging::debug!("Restored {} bytes at {:p}", self.original.len(), self.address);
+        Ok(())
+    }
+}
+
+impl Drop for Patch {
+    fn drop(&mut self) {
+        if let Err(e) = self.restore() {
+            logging::warn!("Patch drop failed: {e}");
+        }
+    }
+}
+
+/// `nop`, repeated over `len` bytes.
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+fn nop(len: usize) -> Result<Vec<u8>> {
+    Ok(vec![0x90; len])
+}
+
+/// `nop`, repeated over `len` bytes.
+#[cfg(target_arch = "aarch64")]
+fn nop(len: usize) -> Result<Vec<u8>> {
+    // Instructions are 4 bytes, so anything else would split one.
+    if len % 4 != 0 {
+        return Err(Error::InvalidTarget);
+    }
+
+    Ok(0xD503201Fu32.to_le_bytes().repeat(len / 4))
+}
+
+/// `ret`.
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+fn ret() -> Vec<u8> {
+    vec![0xC3]
+}
+
+/// `ret`.
+#[cfg(target_arch = "aarch64")]
+fn ret() -> Vec<u8> {
+    0xD65F03C0u32.to_le_bytes().to_vec()
+}
+
+/// `jmp rel32` if in range, `jmp [rip]` followed by `destination` otherwise.
+#[cfg(target_arch = "x86_64")]
+fn jmp(address: *mut c_void, destination: *const c_void) -> Vec<u8> {
+    let offset = (destination as i64).wrapping_sub(address as i64 + 5);
+
+    if let Ok(offset) = i32::try_from(offset) {
+        let mut code = vec![0xE9];
+        code.extend_from_slice(&offset.to_le_bytes());
+        return code;
+    }
+
+    let mut code = vec![0xFF, 0x25, 0x00, 0x00, 0x00, 0x00];
+    code.extend_from_slice(&(destination as u64).to_le_bytes());
+    code
+}
+
+/// `jmp rel32`, which reaches the whole address space.
+#[cfg(target_arch = "x86")]
+fn jmp(address: *mut c_void, destination: *const c_void) -> Vec<u8> {
+    let offset = (destination as u32).wrapping_sub((address as u32).wrapping_add(5));
+
+    let mut code = vec![0xE9];
+    code.extend_from_slice(&offset.to_le_bytes());
+    code
+}
+
+/// `b imm26` if in range, `ldr x16, #8; br x16` followed by `destination` otherwise, as `x16` is reserved
+/// for veneers.
+#[cfg(target_arch = "aarch64")]
+fn jmp(address: *mut c_void, destination: *const c_void) -> Vec<u8> {
+    let offset = (destination as i64).wrapping_sub(address as i64);
+
+    // The immediate is a signed word offset of 26 bits, i.e. +-128MiB.
+    if offset % 4 == 0 && (-(1 << 27)..(1 << 27)).contains(&offset) {
+        let instruction = 0x14000000u32 | ((offset >> 2) as u32 & 0x03FFFFFF);
+        return instruction.to_le_bytes().to_vec();
+    }
+
+    let mut code = [0x58000050u32, 0xD61F0200]
+        .iter()
+        .flat_map(|instruction| instruction.to_le_bytes())
+        .collect::<Vec<_>>();
+    code.extend_from_slice(&(destination as u64).to_le_bytes());
+    code
+}
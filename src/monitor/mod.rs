@@ -0,0 +1,281 @@
+//! API monitoring.
+//!
+//! Responsible for tracing every export of a module, e.g. for reverse-engineering sessions. Every hookable
+//! export gets a detour generated at runtime, which counts, and logs the call, then forwards it untouched to
+//! the original function, whatever its signature.
+//!
+//! Calls are logged at the `info` level, with the `log`, or `tracing` features. Exports used by the logger
+//! itself are only counted, not logged, while logging, but should still be excluded for performance.
+
+use std::{
+    cell::Cell,
+    os::raw::c_void,
+    ptr::null_mut,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use winapi::um::{
+    memoryapi::{VirtualAlloc, VirtualProtect},
+    processthreadsapi::{FlushInstructionCache, GetCurrentProcess},
+    winnt::{MEM_COMMIT, MEM_RESERVE, PAGE_EXECUTE_READ, PAGE_READWRITE},
+};
+
+use crate::{
+    error::{Error, Result},
+    guard::DetourGuard,
+    logging,
+    pe::Module,
+};
+
+/// The space reserved for the detour of every export.
+const THUNK_SIZE: usize = 128;
+
+thread_local! {
+    /// Whether the current thread is already logging a call, so that exports used by the logger don't recurse.
+    static LOGGING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// An export traced through [`DetourGuard::trace_module`].
+#[derive(Debug)]
+pub struct TracedExport {
+    module: &'static str,
+    name: String,
+    target: *mut c_void,
+    calls: AtomicU64,
+}
+
+impl TracedExport {
+    /// The name of the export.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The hooked function.
+    pub fn target(&self) -> *mut c_void {
+        self.target
+    }
+
+    /// The number of times the export was called since it was traced.
+    pub fn call_count(&self) -> u64 {
+        self.calls.load(Ordering::Relaxed)
+    }
+}
+
+/// The exports of a module traced through [`DetourGuard::trace_module`].
+#[derive(Debug)]
+pub struct ModuleTrace {
+    module: &'static str,
+    exports: Vec<&'static TracedExport>,
+}
+
+impl ModuleTrace {
+    /// The name of the traced module.
+    pub fn module(&self) -> &str {
+        self.module
+    }
+
+    /// Iterates over every traced export, in export directory order.
+    pub fn exports(&self) -> impl Iterator<Item = &TracedExport> {
+        self.exports.iter().copied()
+    }
+
+    /// Looks for the traced export named `name`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the export.
+    pub fn export(&self, name: &str) -> Option<&TracedExport> {
+        self.exports().find(|export| export.name == name)
+    }
+
+    /// Collects the hooked functions, e.g. for [`DetourGuard::disable_hooks`].
+    pub fn targets(&self) -> Vec<*mut c_void> {
+        self.exports().map(|export| export.target).collect()
+    }
+}
+
+impl<'a> DetourGuard<'a> {
+    /// Hooks every export of a loaded module with a detour that logs the call, and forwards it to the original
+    /// function, then enables them all in a single transaction.
+    ///
+    /// Forwarded exports, exports outside of executable sections, and exports the engine can't hook are skipped.
+    /// The detours are never freed, as calls may still be in flight once the hooks are removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `module` - The name of the module, e.g. `"kernel32.dll"`.
+    /// * `exclude` - The names of the exports to leave alone.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(ModuleTrace)` with the exports that were succesfully traced.
+    /// - `Err(minhook_detours_rs::error::Error)` if the module couldn't be resolved, or enabling failed, in
+    ///   which case every hook created for the trace is removed.
+    pub fn trace_module(&mut self, module: &str, exclude: &[&str]) -> Result<ModuleTrace> {
+        let _span = logging::span!("trace_module", module = module);
+
+        let image = Module::from_name(module)?;
+        let module: &'static str = Box::leak(module.to_owned().into_boxed_str());
+
+        let candidates = image
+            .exports()
+            .filter(|export| !export.forwarded)
+            .filter_map(|export| Some((export.name.to_str().ok()?, export.rva)))
+            .filter(|(name, _)| !exclude.contains(name))
+            .map(|(name, rva)| (name, unsafe { image.base().add(rva as usize) }))
+            .filter(|(_, target)| image.is_executable(*target))
+            .map(|(name, target)| TracedExport {
+                module,
+                name: name.to_owned(),
+                target: target as *mut c_void,
+                calls: AtomicU64::new(0),
+            })
+            .collect::<Vec<_>>();
+
+        if candidates.is_empty() {
+            return Ok(ModuleTrace {
+                module,
+                exports: Vec::new(),
+            });
+        }
+
+        let candidates: &'static [TracedExport] = Box::leak(candidates.into_boxed_slice());
+
+        let size = candidates.len() * THUNK_SIZE;
+        let thunks = unsafe {
+            VirtualAlloc(null_mut(), size, MEM_COMMIT | MEM_RESERVE, PAGE_READWRITE) as *mut u8
+        };
+        if thunks.is_null() {
+            return Err(Error::FailedAllocatingMemory);
+        }
+
+        let mut exports = Vec::new();
+        for (index, export) in candidates.iter().enumerate() {
+            let thunk = unsafe { thunks.add(index * THUNK_SIZE) };
+            let original_cell = unsafe { write_thunk(thunk, export) };
+
+            match self.create_hook::<*mut c_void>(export.target, thunk as _) {
+                Ok(original) => {
+                    unsafe { original_cell.write_unaligned(*original) };
+                    exports.push(export);
+                }
+                Err(e) => logging::debug!("Skipped tracing {module}!{}: {e}", export.name),
+            }
+        }
+
+        let targets = exports.iter().map(|export| export.target).collect::<Vec<_>>();
+
+        let mut protection = 0;
+        let protected =
+            unsafe { VirtualProtect(thunks as _, size, PAGE_EXECUTE_READ, &mut protection) };
+        let enabled = if protected == 0 {
+            Err(Error::FailedAllocatingMemory)
+        } else {
+            unsafe { FlushInstructionCache(GetCurrentProcess(), thunks as _, size) };
+            self.enable_hooks(&targets)
+        };
+
+        if let Err(e) = enabled {
+            for target in targets {
+                let _ = self.remove_hook(target);
+            }
+            return Err(e);
+        }
+
+        // We succesfully traced the module!
+        logging::info!("Tracing {} exports of {module}", exports.len());
+        Ok(ModuleTrace { module, exports })
+    }
+}
+
+/// Reports a call to `export`, before it's forwarded.
+extern "system" fn on_call(export: &TracedExport) {
+    export.calls.fetch_add(1, Ordering::Relaxed);
+
+    LOGGING.with(|busy| {
+        if busy.replace(true) {
+            return;
+        }
+
+        logging::info!("{}!{}", export.module, export.name);
+        busy.set(false);
+    });
+}
+
+/// Writes the detour of `export` at `thunk`, preserving every argument register around the call to
+/// [`on_call`], then jumping to the original function.
+///
+/// # Safety
+///
+/// `thunk` must be writable for [`THUNK_SIZE`] bytes.
+///
+/// # Returns
+///
+/// The cell holding the address of the original function, to be filled in once the hook is created.
+#[cfg(target_arch = "x86_64")]
+unsafe fn write_thunk(thunk: *mut u8, export: &'static TracedExport) -> *mut *mut c_void {
+    let mut code = Vec::with_capacity(THUNK_SIZE);
+
+    // push rcx; push rdx; push r8; push r9
+    code.extend_from_slice(&[0x51, 0x52, 0x41, 0x50, 0x41, 0x51]);
+    // sub rsp, 0x68, which keeps the stack 16-byte aligned, with shadow space, and room for xmm0-3.
+    code.extend_from_slice(&[0x48, 0x83, 0xEC, 0x68]);
+    // movdqu [rsp + 0x20 + 0x10 * n], xmmn
+    for modrm in [0x44, 0x4C, 0x54, 0x5C] {
+        code.extend_from_slice(&[0xF3, 0x0F, 0x7F, modrm, 0x24, 0x20 + (modrm - 0x44) * 2]);
+    }
+    // mov rcx, export
+    code.extend_from_slice(&[0x48, 0xB9]);
+    code.extend_from_slice(&(export as *const TracedExport as u64).to_le_bytes());
+    // mov rax, on_call; call rax
+    code.extend_from_slice(&[0x48, 0xB8]);
+    code.extend_from_slice(&(on_call as usize as u64).to_le_bytes());
+    code.extend_from_slice(&[0xFF, 0xD0]);
+    // movdqu xmmn, [rsp + 0x20 + 0x10 * n]
+    for modrm in [0x44, 0x4C, 0x54, 0x5C] {
+        code.extend_from_slice(&[0xF3, 0x0F, 0x6F, modrm, 0x24, 0x20 + (modrm - 0x44) * 2]);
+    }
+    // add rsp, 0x68; pop r9; pop r8; pop rdx; pop rcx
+    code.extend_from_slice(&[0x48, 0x83, 0xC4, 0x68, 0x41, 0x59, 0x41, 0x58, 0x5A, 0x59]);
+    // jmp [rip], followed by the address of the original function.
+    code.extend_from_slice(&[0xFF, 0x25, 0x00, 0x00, 0x00, 0x00]);
+    let original_cell = code.len();
+    code.extend_from_slice(&0u64.to_le_bytes());
+
+    unsafe {
+        std::ptr::copy_nonoverlapping(code.as_ptr(), thunk, code.len());
+        thunk.add(original_cell) as *mut *mut c_void
+    }
+}
+
+/// Writes the detour of `export` at `thunk`, preserving every general purpose register around the call to
+/// [`on_call`], then jumping to the original function.
+///
+/// # Safety
+///
+/// `thunk` must be writable for [`THUNK_SIZE`] bytes.
+///
+/// # Returns
+///
+/// The cell holding the address of the original function, to be filled in once the hook is created.
+#[cfg(target_arch = "x86")]
+unsafe fn write_thunk(thunk: *mut u8, export: &'static TracedExport) -> *mut *mut c_void {
+    let mut code = Vec::with_capacity(THUNK_SIZE);
+
+    // pushad; push export
+    code.extend_from_slice(&[0x60, 0x68]);
+    code.extend_from_slice(&(export as *const TracedExport as u32).to_le_bytes());
+    // mov eax, on_call; call eax, which pops `export` itself, being `stdcall`.
+    code.push(0xB8);
+    code.extend_from_slice(&(on_call as usize as u32).to_le_bytes());
+    code.extend_from_slice(&[0xFF, 0xD0]);
+    // popad; jmp [original_cell]
+    code.extend_from_slice(&[0x61, 0xFF, 0x25]);
+    let original_cell = code.len() + std::mem::size_of::<u32>();
+    code.extend_from_slice(&(thunk as u32 + original_cell as u32).to_le_bytes());
+    code.extend_from_slice(&0u32.to_le_bytes());
+
+    unsafe {
+        std::ptr::copy_nonoverlapping(code.as_ptr(), thunk, code.len());
+        thunk.add(original_cell) as *mut *mut c_void
+    }
+}
@@ -0,0 +1,446 @@
+//! Portable Executable helpers.
+//!
+//! Responsible for walking the headers of modules that are already mapped in the current process.
+
+use std::{
+    ffi::{CStr, CString, OsStr},
+    mem::size_of,
+    os::{raw::c_void, windows::ffi::OsStrExt},
+    ptr::null_mut,
+};
+use winapi::{
+    shared::minwindef::HMODULE,
+    um::{
+        libloaderapi::{
+            GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS, GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+            GetModuleHandleExW, GetModuleHandleW, GetProcAddress,
+        },
+        winnt::{
+            IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT, IMAGE_DIRECTORY_ENTRY_EXPORT, IMAGE_DOS_HEADER,
+            IMAGE_DOS_SIGNATURE, IMAGE_EXPORT_DIRECTORY, IMAGE_FILE_HEADER, IMAGE_NT_HEADERS,
+            IMAGE_NT_HEADERS32, IMAGE_NT_HEADERS64, IMAGE_NT_OPTIONAL_HDR32_MAGIC,
+            IMAGE_NT_SIGNATURE, IMAGE_SCN_MEM_EXECUTE, IMAGE_SECTION_HEADER,
+        },
+    },
+};
+
+use crate::{
+    error::{Error, Result},
+    wow64::Bitness,
+};
+
+/// A module image mapped in the current process.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Module {
+    base: *const u8,
+}
+
+impl Module {
+    /// Look up an already loaded module by its name, e.g. `"kernel32.dll"`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the module, as accepted by `GetModuleHandleW`.
+    pub(crate) fn from_name(name: &str) -> Result<Self> {
+        let name = to_wide(name);
+
+        let handle = unsafe { GetModuleHandleW(name.as_ptr()) };
+
+        unsafe { Self::from_handle(handle) }
+    }
+
+    /// Look up the loaded module whose image contains `address`.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - Any address inside of the module image.
+    pub(crate) fn from_address(address: *const c_void) -> Result<Self> {
+        let mut handle: HMODULE = null_mut();
+
+        // Don't bump the reference count, as we don't own the module.
+        let succeeded = unsafe {
+            GetModuleHandleExW(
+                GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS
+                    | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+                address as _,
+                &mut handle,
+            )
+        };
+
+        if succeeded == 0 {
+            return Err(Error::InvalidModule);
+        }
+
+        unsafe { Self::from_handle(handle) }
+    }
+
+    /// Wrap a module handle, validating its headers, and that it has the bitness of the current process.
+    ///
+    /// # Safety
+    ///
+    /// `handle` must either be null, or the base address of a module mapped in the current process.
+    pub(crate) unsafe fn from_handle(handle: HMODULE) -> Result<Self> {
+        let module = unsafe { Self::from_handle_any(handle)? };
+
+        // The rest of the helpers read the headers with the layout of the current process.
+        if module.bitness() != Bitness::native() {
+            return Err(Error::BitnessMismatch {
+                expected: Bitness::native(),
+                found: module.bitness(),
+            });
+        }
+
+        Ok(module)
+    }
+
+    /// Wrap a module handle, validating its headers, whichever its bitness.
+    ///
+    /// Only [`Module::bitness`], and [`Module::export_rva`] may be used on the result.
+    ///
+    /// # Safety
+    ///
+    /// `handle` must either be null, or the base address of an image mapped in the current process.
+    pub(crate) unsafe fn from_handle_any(handle: HMODULE) -> Result<Self> {
+        if handle.is_null() {
+            return Err(Error::InvalidModule);
+        }
+
+        let base = handle as *const u8;
+
+        // Validate the DOS header.
+        let dos_header = unsafe { &*(base as *const IMAGE_DOS_HEADER) };
+        if dos_header.e_magic != IMAGE_DOS_SIGNATURE {
+            return Err(Error::InvalidModule);
+        }
+
+        // Validate the NT headers.
+        let nt_headers =
+            unsafe { &*(base.offset(dos_header.e_lfanew as isize) as *const IMAGE_NT_HEADERS) };
+        if nt_headers.Signature != IMAGE_NT_SIGNATURE {
+            return Err(Error::InvalidModule);
+        }
+
+        Ok(Self { base })
+    }
+
+    /// The base address of the module.
+    pub(crate) fn base(&self) -> *const u8 {
+        self.base
+    }
+
+    /// The handle of the module.
+    pub(crate) fn handle(&self) -> HMODULE {
+        self.base as HMODULE
+    }
+
+    /// Resolve an export of the module by its name.
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - The name of the export.
+    pub(crate) fn export(&self, symbol: &str) -> Result<*mut c_void> {
+        let symbol = CString::new(symbol).map_err(|_| Error::InvalidExport)?;
+
+        let address = unsafe { GetProcAddress(self.handle(), symbol.as_ptr()) };
+
+        if address.is_null() {
+            return Err(Error::InvalidExport);
+        }
+
+        Ok(address as *mut c_void)
+    }
+
+    /// Resolve an export of the module by its ordinal.
+    ///
+    /// # Arguments
+    ///
+    /// * `ordinal` - The ordinal of the export.
+    pub(crate) fn export_ordinal(&self, ordinal: u16) -> Result<*mut c_void> {
+        // Equivalent of the `MAKEINTRESOURCEA` macro: ordinals are passed in place of the name.
+        let address = unsafe { GetProcAddress(self.handle(), ordinal as usize as _) };
+
+        if address.is_null() {
+            return Err(Error::InvalidExport);
+        }
+
+        Ok(address as *mut c_void)
+    }
+
+    /// The bitness of the module, out of the magic of its optional header, which is at the same offset for
+    /// either layout.
+    pub(crate) fn bitness(&self) -> Bitness {
+        match self.nt_headers().OptionalHeader.Magic {
+            IMAGE_NT_OPTIONAL_HDR32_MAGIC => Bitness::Bits32,
+            _ => Bitness::Bits64,
+        }
+    }
+
+    /// Resolve the RVA of an export of the module by its name, by walking its export directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - The name of the export.
+    pub(crate) fn export_rva(&self, symbol: &str) -> Result<u32> {
+        self.exports()
+            .find(|export| export.name.to_bytes() == symbol.as_bytes())
+            .map(|export| export.rva)
+            .ok_or(Error::InvalidExport)
+    }
+
+    /// The forwarder of an export of the module, e.g. `NTDLL.RtlAllocateHeap` for `kernel32.dll!HeapAlloc`.
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - The name of the export.
+    pub(crate) fn forwarder(&self, symbol: &str) -> Option<&CStr> {
+        let export = self
+            .exports()
+            .find(|export| export.name.to_bytes() == symbol.as_bytes())?;
+
+        if !export.forwarded {
+            return None;
+        }
+
+        Some(unsafe { CStr::from_ptr(self.base.add(export.rva as usize) as _) })
+    }
+
+    /// Walks the named exports of the module, whichever its bitness.
+    pub(crate) fn exports(&self) -> impl Iterator<Item = Export<'_>> {
+        let nt_headers = self.nt_headers() as *const IMAGE_NT_HEADERS as *const u8;
+
+        let directory = unsafe {
+            match self.bitness() {
+                Bitness::Bits32 => {
+                    (*(nt_headers as *const IMAGE_NT_HEADERS32)).OptionalHeader.DataDirectory
+                        [IMAGE_DIRECTORY_ENTRY_EXPORT as usize]
+                }
+                Bitness::Bits64 => {
+                    (*(nt_headers as *const IMAGE_NT_HEADERS64)).OptionalHeader.DataDirectory
+                        [IMAGE_DIRECTORY_ENTRY_EXPORT as usize]
+                }
+            }
+        };
+
+        let rva = |rva: u32| unsafe { self.base.add(rva as usize) };
+
+        let (names, ordinals, functions): (&[u32], &[u16], &[u32]) =
+            if directory.VirtualAddress == 0 {
+                (&[], &[], &[])
+            } else {
+                let exports =
+                    unsafe { &*(rva(directory.VirtualAddress) as *const IMAGE_EXPORT_DIRECTORY) };
+
+                unsafe {
+                    (
+                        std::slice::from_raw_parts(
+                            rva(exports.AddressOfNames) as *const u32,
+                            exports.NumberOfNames as usize,
+                        ),
+                        std::slice::from_raw_parts(
+                            rva(exports.AddressOfNameOrdinals) as *const u16,
+                            exports.NumberOfNames as usize,
+                        ),
+                        std::slice::from_raw_parts(
+                            rva(exports.AddressOfFunctions) as *const u32,
+                            exports.NumberOfFunctions as usize,
+                        ),
+                    )
+                }
+            };
+
+        // Forwarders point at a string inside of the export directory, rather than at code.
+        let forwarders = directory.VirtualAddress..directory.VirtualAddress + directory.Size;
+
+        names
+            .iter()
+            .zip(ordinals)
+            .filter_map(move |(name, ordinal)| {
+                let function = *functions.get(*ordinal as usize)?;
+
+                Some(Export {
+                    name: unsafe { CStr::from_ptr(rva(*name) as _) },
+                    rva: function,
+                    forwarded: forwarders.contains(&function),
+                })
+            })
+    }
+
+    /// The file name of the module, e.g. `"kernel32.dll"`.
+    #[cfg(feature = "symbol")]
+    pub(crate) fn name(&self) -> Option<String> {
+        use std::{ffi::OsString, os::windows::ffi::OsStringExt, path::Path};
+        use winapi::um::libloaderapi::GetModuleFileNameW;
+
+        let mut path = [0u16; 1024];
+
+        let length =
+            unsafe { GetModuleFileNameW(self.handle(), path.as_mut_ptr(), path.len() as u32) };
+        if length == 0 {
+            return None;
+        }
+
+        let path = OsString::from_wide(&path[..length as usize]);
+        Some(Path::new(&path).file_name()?.to_string_lossy().into_owned())
+    }
+
+    /// Whether `address` falls inside of the image of the module.
+    pub(crate) fn contains(&self, address: *const u8) -> bool {
+        let start = self.base as usize;
+        let size = self.nt_headers().OptionalHeader.SizeOfImage as usize;
+
+        (start..start + size).contains(&(address as usize))
+    }
+
+    /// Looks for the slot of the delay-load import table through which the module calls `symbol` of `dll`.
+    ///
+    /// # Arguments
+    ///
+    /// * `dll` - The name of the delay-loaded module, compared case-insensitively, e.g. `"user32.dll"`.
+    /// * `symbol` - The name of the import.
+    pub(crate) fn delay_import_slot(&self, dll: &str, symbol: &str) -> Result<*mut *mut c_void> {
+        let directory = self.nt_headers().OptionalHeader.DataDirectory
+            [IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT as usize];
+
+        if directory.VirtualAddress == 0 {
+            return Err(Error::InvalidImport);
+        }
+
+        let rva = |rva: u32| unsafe { self.base.add(rva as usize) };
+
+        let mut descriptor = rva(directory.VirtualAddress) as *const DelayLoadDescriptor;
+        loop {
+            let current = unsafe { &*descriptor };
+
+            // The table is terminated by a zeroed descriptor.
+            if current.dll_name_rva == 0 {
+                return Err(Error::InvalidImport);
+            }
+
+            let name = unsafe { CStr::from_ptr(rva(current.dll_name_rva) as _) };
+            if name.to_bytes().eq_ignore_ascii_case(dll.as_bytes()) {
+                break;
+            }
+
+            descriptor = unsafe { descriptor.add(1) };
+        }
+
+        let descriptor = unsafe { &*descriptor };
+        let names = rva(descriptor.import_name_table_rva) as *const usize;
+        let slots = rva(descriptor.import_address_table_rva) as *mut *mut c_void;
+
+        for index in 0.. {
+            let name = unsafe { *names.add(index) };
+
+            if name == 0 {
+                break;
+            }
+
+            // Imports by ordinal have the high bit set, and no name to compare against.
+            if name & IMAGE_ORDINAL_FLAG != 0 {
+                continue;
+            }
+
+            // Skip the hint of `IMAGE_IMPORT_BY_NAME`, to reach its name.
+            let import = unsafe { CStr::from_ptr(rva(name as u32).add(size_of::<u16>()) as _) };
+            if import.to_bytes() == symbol.as_bytes() {
+                return Ok(unsafe { slots.add(index) });
+            }
+        }
+
+        Err(Error::InvalidImport)
+    }
+
+    /// The NT headers of the module.
+    pub(crate) fn nt_headers(&self) -> &IMAGE_NT_HEADERS {
+        unsafe {
+            let dos_header = &*(self.base as *const IMAGE_DOS_HEADER);
+            &*(self.base.offset(dos_header.e_lfanew as isize) as *const IMAGE_NT_HEADERS)
+        }
+    }
+
+    /// The section table of the module.
+    pub(crate) fn sections(&self) -> &[IMAGE_SECTION_HEADER] {
+        let nt_headers = self.nt_headers();
+
+        // Equivalent of the `IMAGE_FIRST_SECTION` macro: the section table follows
+        // the optional header, whose size is specified by the file header.
+        let first_section = unsafe {
+            (nt_headers as *const IMAGE_NT_HEADERS as *const u8)
+                .add(size_of::<u32>())
+                .add(size_of::<IMAGE_FILE_HEADER>())
+                .add(nt_headers.FileHeader.SizeOfOptionalHeader as usize)
+                as *const IMAGE_SECTION_HEADER
+        };
+
+        unsafe {
+            std::slice::from_raw_parts(
+                first_section,
+                nt_headers.FileHeader.NumberOfSections as usize,
+            )
+        }
+    }
+
+    /// The mapped contents of every executable section of the module.
+    pub(crate) fn executable_sections(&self) -> impl Iterator<Item = &[u8]> {
+        self.sections()
+            .iter()
+            .filter(|section| section.Characteristics & IMAGE_SCN_MEM_EXECUTE != 0)
+            .map(|section| {
+                let size = unsafe { *section.Misc.VirtualSize() } as usize;
+
+                unsafe {
+                    std::slice::from_raw_parts(
+                        self.base.add(section.VirtualAddress as usize),
+                        size,
+                    )
+                }
+            })
+    }
+
+    /// Whether `address` falls inside of an executable section of the module.
+    pub(crate) fn is_executable(&self, address: *const u8) -> bool {
+        let address = address as usize;
+
+        self.executable_sections().any(|section| {
+            let start = section.as_ptr() as usize;
+            address >= start && address < start + section.len()
+        })
+    }
+}
+
+/// The high bit of import name table entries, set for imports by ordinal.
+const IMAGE_ORDINAL_FLAG: usize = 1 << (usize::BITS - 1);
+
+/// Equivalent of `IMAGE_DELAYLOAD_DESCRIPTOR`, with every address being an RVA.
+///
+/// Declared in full, so that the table of descriptors can be walked, although only some fields are read.
+#[repr(C)]
+#[allow(dead_code)]
+struct DelayLoadDescriptor {
+    attributes: u32,
+    dll_name_rva: u32,
+    module_handle_rva: u32,
+    import_address_table_rva: u32,
+    import_name_table_rva: u32,
+    bound_import_address_table_rva: u32,
+    unload_information_table_rva: u32,
+    time_date_stamp: u32,
+}
+
+/// A named export of a [`Module`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Export<'m> {
+    pub(crate) name: &'m CStr,
+    pub(crate) rva: u32,
+    /// Whether the export is forwarded to another module, in which case `rva` points at the name of the
+    /// forwardee rather than at code.
+    pub(crate) forwarded: bool,
+}
+
+/// Convert `value` to a null-terminated UTF-16 string, for the wide Win32 APIs.
+pub(crate) fn to_wide(value: &str) -> Vec<u16> {
+    OsStr::new(value)
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect()
+}
@@ -0,0 +1,66 @@
+//! Distributed hook registration.
+//!
+//! Responsible for collecting the hooks declared anywhere in the dependency graph at link time, so that a
+//! single [`DetourGuard::install_registered_hooks`] installs all of them, the way test frameworks collect
+//! their cases. Hooks are declared by adding a [`RegisteredHook`] to [`HOOKS`]:
+//!
+//! ```ignore
+//! use minhook_detours_rs::registry::{distributed_slice, RegisteredHook, HOOKS};
+//!
+//! #[distributed_slice(HOOKS)]
+//! #[linkme(crate = minhook_detours_rs::registry::linkme)]
+//! static PRESENT: RegisteredHook = RegisteredHook {
+//!     name: "present",
+//!     register: |guard| {
+//!         guard.create_and_enable_hook::<PresentFn>(present_target(), present_hook as _)?;
+//!         Ok(())
+//!     },
+//! };
+//! ```
+//!
+//! The order of [`HOOKS`] is unspecified, use [`DetourGuard::add_dependency`] to order hooks that rely on
+//! each other.
+
+pub use linkme::{self, distributed_slice};
+
+use crate::{
+    error::Result,
+    guard::{DetourGuard, HookProvider},
+};
+
+/// Every [`RegisteredHook`] linked into the binary.
+#[distributed_slice]
+pub static HOOKS: [RegisteredHook];
+
+/// A hook, or a set of them, declared through [`HOOKS`].
+pub struct RegisteredHook {
+    /// The name of the declaration, for diagnostics.
+    pub name: &'static str,
+    /// Creates, and optionally enables the hooks in the [`DetourGuard`].
+    pub register: fn(&mut DetourGuard<'_>) -> Result<()>,
+}
+
+impl HookProvider for RegisteredHook {
+    fn register(&self, guard: &mut DetourGuard<'_>) -> Result<()> {
+        (self.register)(guard)
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+}
+
+impl<'a> DetourGuard<'a> {
+    /// Installs every hook declared through [`HOOKS`], see [`DetourGuard::install_providers`].
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if every declaration was succesfully installed.
+    /// - `Err(minhook_detours_rs::error::Error::BatchFailed)` with the index of the declaration that failed in
+    ///   [`HOOKS`] otherwise, in which case none is installed.
+    pub fn install_registered_hooks(&mut self) -> Result<()> {
+        let providers = HOOKS.iter().map(|hook| hook as &dyn HookProvider).collect::<Vec<_>>();
+
+        self.install_providers(&providers)
+    }
+}
@@ -0,0 +1,273 @@
+//! Reentrancy-safe logging channel.
+//!
+//! Responsible for letting detours log without taking locks, nor allocating, so that hooks of e.g.
+//! `NtWriteFile`, or `RtlAllocateHeap` can report what they see without deadlocking on the very function they
+//! hook. Messages are formatted into a fixed-size slot of a [`LogRing`], and written out by a normal thread
+//! through [`LogRing::drain`].
+//!
+//! ```ignore
+//! static LOG: LogRing<1024> = LogRing::new();
+//!
+//! // Inside of a detour.
+//! ring_log!(LOG, "NtWriteFile({handle:?}, {length})");
+//!
+//! // On a thread of its own.
+//! loop {
+//!     LOG.drain(|message| println!("{message}"));
+//!     std::thread::sleep(Duration::from_millis(100));
+//! }
+//! ```
+
+use std::{
+    cell::UnsafeCell,
+    fmt::{self, Write},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// The number of bytes a message may take, longer ones are truncated.
+pub const MESSAGE_CAPACITY: usize = 256;
+
+/// A bounded, lock-free queue of messages, where `N` is the number of messages it holds.
+///
+/// Any number of threads may push at once, while draining is meant for a single thread. Pushing to a full
+/// ring drops the message, rather than waiting, see [`LogRing::dropped`].
+pub struct LogRing<const N: usize> {
+    slots: [Slot; N],
+    /// The position the next message is pushed at.
+    tail: AtomicUsize,
+    /// The position the next message is drained from.
+    head: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+/// A message of a [`LogRing`], along with the position it's ready for, as in Vyukov's bounded queue: its
+/// position while free, and the position plus one while holding a message.
+struct Slot {
+    sequence: AtomicUsize,
+    length: UnsafeCell<usize>,
+    message: UnsafeCell<[u8; MESSAGE_CAPACITY]>,
+}
+
+// Slots are only accessed by whoever claimed them through `sequence`.
+unsafe impl<const N: usize> Sync for LogRing<N> {}
+
+impl<const N: usize> LogRing<N> {
+    /// Creates an empty ring, e.g. for a `static`.
+    ///
+    /// # Panics
+    ///
+    /// If `N` is 0, at compile time for a `static`.
+    pub const fn new() -> Self {
+        assert!(N > 0, "LogRing must hold at least one message");
+
+        let mut slots = [const {
+            Slot {
+                sequence: AtomicUsize::new(0),
+                length: UnsafeCell::new(0),
+                message: UnsafeCell::new([0; MESSAGE_CAPACITY]),
+            }
+        }; N];
+
+        let mut index = 0;
+        while index < N {
+            slots[index].sequence = AtomicUsize::new(index);
+            index += 1;
+        }
+
+        Self {
+            slots,
+            tail: AtomicUsize::new(0),
+            head: AtomicUsize::new(0),
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    /// Pushes `message`, truncated to [`MESSAGE_CAPACITY`] bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message.
+    ///
+    /// # Returns
+    ///
+    /// - `true` if the message was succesfully pushed.
+    /// - `false` if the ring is full, in which case the message is counted as dropped.
+    pub fn push(&self, message: &str) -> bool {
+        let Some(position) = self.claim() else {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return false;
+        };
+
+        let message = truncate(message, MESSAGE_CAPACITY);
+        let slot = &self.slots[position % N];
+        unsafe {
+            (*slot.message.get())[..message.len()].copy_from_slice(message.as_bytes());
+            *slot.length.get() = message.len();
+        }
+
+        // We succesfully pushed the message!
+        slot.sequence.store(position + 1, Ordering::Release);
+        true
+    }
+
+    /// Formats `arguments` straight into a slot, see [`crate::ring_log`], and [`LogRing::push`].
+    pub fn push_fmt(&self, arguments: fmt::Arguments<'_>) -> bool {
+        let mut buffer = Buffer {
+            bytes: [0; MESSAGE_CAPACITY],
+            length: 0,
+        };
+
+        // Overflowing the buffer truncates the message, rather than failing.
+        let _ = buffer.write_fmt(arguments);
+
+        self.push(buffer.as_str())
+    }
+
+    /// Hands every message pushed so far to `sink`, oldest first, e.g. to print it.
+    ///
+    /// Meant for a single, normal thread, rather than for detours, as `sink` is free to allocate, or block.
+    ///
+    /// # Arguments
+    ///
+    /// * `sink` - Called with every message.
+    ///
+    /// # Returns
+    ///
+    /// The number of messages drained.
+    pub fn drain(&self, mut sink: impl FnMut(&str)) -> usize {
+        let mut drained = 0;
+
+        loop {
+            let position = self.head.load(Ordering::Relaxed);
+            let slot = &self.slots[position % N];
+
+            if slot.sequence.load(Ordering::Acquire) != position + 1 {
+                return drained;
+            }
+
+            if self
+                .head
+                .compare_exchange(position, position + 1, Ordering::Relaxed, Ordering::Relaxed)
+                .is_err()
+            {
+                continue;
+            }
+
+            // The slot is free for the push that wraps around to it, even if `sink` panics.
+            let _release = Release {
+                sequence: &slot.sequence,
+                position: position + N,
+            };
+
+            let message = unsafe { &(*slot.message.get())[..*slot.length.get()] };
+            // Messages are truncated at character boundaries, so they stay valid UTF-8.
+            sink(std::str::from_utf8(message).unwrap_or_default());
+            drained += 1;
+        }
+    }
+
+    /// The number of messages dropped so far, as the ring was full.
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Claims the slot at the tail, returning its position, or `None` if the ring is full.
+    fn claim(&self) -> Option<usize> {
+        let mut position = self.tail.load(Ordering::Relaxed);
+
+        loop {
+            let sequence = self.slots[position % N].sequence.load(Ordering::Acquire);
+
+            match sequence.wrapping_sub(position) as isize {
+                0 => match self.tail.compare_exchange_weak(
+                    position,
+                    position + 1,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => return Some(position),
+                    Err(current) => position = current,
+                },
+                // The slot still holds the message of the previous lap.
+                difference if difference < 0 => return None,
+                _ => position = self.tail.load(Ordering::Relaxed),
+            }
+        }
+    }
+}
+
+impl<const N: usize> Default for LogRing<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> fmt::Debug for LogRing<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LogRing")
+            .field("capacity", &N)
+            .field("dropped", &self.dropped())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Frees a drained slot once dropped, see [`LogRing::drain`].
+struct Release<'s> {
+    sequence: &'s AtomicUsize,
+    position: usize,
+}
+
+impl Drop for Release<'_> {
+    fn drop(&mut self) {
+        self.sequence.store(self.position, Ordering::Release);
+    }
+}
+
+/// A message being formatted on the stack, see [`LogRing::push_fmt`].
+struct Buffer {
+    bytes: [u8; MESSAGE_CAPACITY],
+    length: usize,
+}
+
+impl Buffer {
+    fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes[..self.length]).unwrap_or_default()
+    }
+}
+
+impl Write for Buffer {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        let string = truncate(string, MESSAGE_CAPACITY - self.length);
+        self.bytes[self.length..self.length + string.len()].copy_from_slice(string.as_bytes());
+        self.length += string.len();
+
+        if self.length == MESSAGE_CAPACITY { Err(fmt::Error) } else { Ok(()) }
+    }
+}
+
+/// The longest prefix of `string` that fits in `capacity` bytes, without splitting a character.
+fn truncate(string: &str, capacity: usize) -> &str {
+    if string.len() <= capacity {
+        return string;
+    }
+
+    let mut end = capacity;
+    while !string.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    &string[..end]
+}
+
+/// Formats a message into a [`LogRing`], without locking, nor allocating, as long as the arguments don't
+/// either when formatted.
+///
+/// ```ignore
+/// ring_log!(LOG, "NtWriteFile({handle:?}, {length})");
+/// ```
+#[macro_export]
+macro_rules! ring_log {
+    ($ring:expr, $($arguments:tt)+) => {
+        $ring.push_fmt(::std::format_args!($($arguments)+))
+    };
+}
@@ -0,0 +1,156 @@
+//! Signature scanning.
+//!
+//! Responsible for locating code inside of loaded modules by IDA-style byte patterns, e.g. `"48 8B ?? ?? 57"`,
+//! so that the results can be fed into [`crate::guard::DetourGuard::create_hook`].
+//!
+//! Module scans only report matches where an instruction may start, which is anywhere on x86, and x64, and
+//! on 4-byte boundaries on ARM64.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    os::raw::c_void,
+    str::FromStr,
+};
+use winapi::shared::minwindef::HMODULE;
+
+use crate::{
+    error::{Error, Result},
+    pe::Module,
+};
+
+/// The alignment of instructions on the current architecture.
+#[cfg(target_arch = "aarch64")]
+pub const INSTRUCTION_ALIGNMENT: usize = 4;
+/// The alignment of instructions on the current architecture.
+#[cfg(not(target_arch = "aarch64"))]
+pub const INSTRUCTION_ALIGNMENT: usize = 1;
+
+/// An IDA-style byte pattern.
+///
+/// Every token is either a hexadecimal byte (`48`), or a wildcard (`?` or `??`) matching any byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    bytes: Vec<Option<u8>>,
+}
+
+impl Pattern {
+    /// Parse an IDA-style byte pattern.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - Whitespace separated tokens, e.g. `"48 8B ?? ?? 57"`.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Pattern)` if the pattern was succesfully parsed.
+    /// - `Err(minhook_detours_rs::error::Error::InvalidPattern)` if a token is malformed, or there are no tokens.
+    pub fn new(pattern: &str) -> Result<Self> {
+        let bytes = pattern
+            .split_whitespace()
+            .map(|token| match token {
+                "?" | "??" => Ok(None),
+                _ if token.len() == 2 && token.chars().all(|c| c.is_ascii_hexdigit()) => {
+                    Ok(Some(u8::from_str_radix(token, 16).unwrap()))
+                }
+                _ => Err(Error::InvalidPattern),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // A pattern without tokens would match everywhere.
+        if bytes.is_empty() {
+            return Err(Error::InvalidPattern);
+        }
+
+        Ok(Self { bytes })
+    }
+
+    /// Whether `bytes` starts with a sequence matching the pattern.
+    pub fn matches(&self, bytes: &[u8]) -> bool {
+        bytes.len() >= self.bytes.len()
+            && self
+                .bytes
+                .iter()
+                .zip(bytes)
+                .all(|(expected, actual)| expected.is_none_or(|expected| expected == *actual))
+    }
+
+    /// Iterate over the offsets inside of `haystack` where the pattern matches.
+    pub fn find_in<'h>(&'h self, haystack: &'h [u8]) -> impl Iterator<Item = usize> + 'h {
+        haystack
+            .windows(self.bytes.len())
+            .enumerate()
+            .filter(|(_, window)| self.matches(window))
+            .map(|(offset, _)| offset)
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = Error;
+
+    fn from_str(pattern: &str) -> Result<Self> {
+        Self::new(pattern)
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (index, byte) in self.bytes.iter().enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+
+            match byte {
+                Some(byte) => write!(f, "{byte:02X}")?,
+                None => f.write_str("??")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Scan the executable sections of a loaded module for `pattern`.
+///
+/// # Arguments
+///
+/// * `module` - The name of the module, e.g. `"game.exe"`.
+/// * `pattern` - The pattern to look for.
+///
+/// # Returns
+///
+/// - `Ok(Vec<*mut c_void>)` with the address of every match, ready to be used as a hook target.
+/// - `Err(minhook_detours_rs::error::Error)` if the module couldn't be resolved.
+pub fn scan_module(module: &str, pattern: &Pattern) -> Result<Vec<*mut c_void>> {
+    let module = Module::from_name(module)?;
+
+    Ok(scan(&module, pattern))
+}
+
+/// Scan the executable sections of a loaded module for `pattern`.
+///
+/// # Arguments
+///
+/// * `module` - The handle of the module.
+/// * `pattern` - The pattern to look for.
+///
+/// # Safety
+///
+/// `module` must be the base address of a module mapped in the current process.
+pub unsafe fn scan_module_handle(module: HMODULE, pattern: &Pattern) -> Result<Vec<*mut c_void>> {
+    let module = unsafe { Module::from_handle(module)? };
+
+    Ok(scan(&module, pattern))
+}
+
+fn scan(module: &Module, pattern: &Pattern) -> Vec<*mut c_void> {
+    module
+        .executable_sections()
+        .flat_map(|section| {
+            pattern
+                .find_in(section)
+                .map(move |offset| section[offset..].as_ptr())
+                .filter(|address| address.addr() % INSTRUCTION_ALIGNMENT == 0)
+                .map(|address| address as *mut c_void)
+        })
+        .collect()
+}
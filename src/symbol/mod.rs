@@ -0,0 +1,71 @@
+//! Symbolization.
+//!
+//! Responsible for turning addresses into `module!symbol+offset`, or `module+offset` from the exports of the
+//! modules mapped in the current process, so that diagnostics stay readable without a matching memory map.
+
+use std::{fmt, os::raw::c_void};
+
+use crate::pe::Module;
+
+/// An address, relative to the module it falls in, and to the closest export before it, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    /// The file name of the module, e.g. `"kernel32.dll"`.
+    pub module: String,
+    /// The closest export at, or before the address.
+    pub symbol: Option<String>,
+    /// The offset of the address from `symbol`, or from the base of `module` without one.
+    pub offset: usize,
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.symbol {
+            Some(symbol) => write!(f, "{}!{symbol}", self.module)?,
+            None => write!(f, "{}", self.module)?,
+        }
+
+        if self.offset != 0 {
+            write!(f, "+{:#x}", self.offset)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolve `address` to the module it falls in, and to the closest export before it.
+///
+/// Only exports are known, so an address inside of an internal function is reported relative to whichever
+/// export precedes it, or to the module when none does.
+///
+/// # Arguments
+///
+/// * `address` - Any address, e.g. the target of a hook.
+///
+/// # Returns
+///
+/// - `Some(Symbol)` if `address` falls inside of a module mapped in the current process.
+/// - `None` otherwise, e.g. for dynamically generated code.
+pub fn symbolize(address: *const c_void) -> Option<Symbol> {
+    let module = Module::from_address(address).ok()?;
+    let rva = (address as usize).checked_sub(module.base() as usize)?;
+
+    let closest = module
+        .exports()
+        .filter(|export| !export.forwarded && export.rva as usize <= rva)
+        .max_by_key(|export| export.rva);
+
+    let (symbol, offset) = match closest {
+        Some(export) => (
+            Some(export.name.to_string_lossy().into_owned()),
+            rva - export.rva as usize,
+        ),
+        None => (None, rva),
+    };
+
+    Some(Symbol {
+        module: module.name()?,
+        symbol,
+        offset,
+    })
+}
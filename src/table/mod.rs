@@ -0,0 +1,134 @@
+//! Crash-dump-discoverable hook table.
+//!
+//! Responsible for mirroring the registry of the [`crate::guard::DetourGuard`] into a fixed-layout block of
+//! static memory, so that post-mortem tooling can tell which hooks were installed from a raw minidump,
+//! without running any of the crate's code.
+//!
+//! The block is exported as `MINHOOK_DETOURS_HOOK_TABLE`, and can also be found by scanning for its magic,
+//! `MHDHOOKS`. Its layout is that of [`HookTable`], with every integer in the native byte order:
+//!
+//! | Offset | Size               | Field                                   |
+//! |--------|--------------------|-----------------------------------------|
+//! | 0x00   | 8                  | `magic`                                 |
+//! | 0x08   | 4                  | `version`, currently `1`                |
+//! | 0x0C   | 4                  | `capacity`, in entries                  |
+//! | 0x10   | 4                  | `count`, of the entries in use          |
+//! | 0x14   | 4                  | Padding                                 |
+//! | 0x18   | `capacity` * 24    | `entries`, as [`HookTableEntry`]-s      |
+//!
+//! Hooks past the capacity aren't recorded.
+
+use std::{
+    os::raw::c_void,
+    sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering},
+};
+
+use crate::guard::HookEvent;
+
+/// The number of hooks the table can describe.
+pub const HOOK_TABLE_CAPACITY: usize = 256;
+
+/// Set in [`HookTableEntry::flags`] while the hook is enabled.
+pub const HOOK_FLAG_ENABLED: u32 = 1 << 0;
+/// Set in [`HookTableEntry::flags`] once the hook was removed.
+pub const HOOK_FLAG_REMOVED: u32 = 1 << 1;
+
+/// The block of memory that post-mortem tooling looks for.
+#[repr(C)]
+pub struct HookTable {
+    pub magic: [u8; 8],
+    pub version: u32,
+    pub capacity: u32,
+    pub count: AtomicU32,
+    entries: [HookTableEntry; HOOK_TABLE_CAPACITY],
+}
+
+/// Description of a single hook.
+#[repr(C)]
+pub struct HookTableEntry {
+    pub target: AtomicU64,
+    pub detour: AtomicU64,
+    pub flags: AtomicU32,
+    _reserved: u32,
+}
+
+impl HookTable {
+    /// The entries in use.
+    pub fn entries(&self) -> &[HookTableEntry] {
+        let count = (self.count.load(Ordering::Acquire) as usize).min(HOOK_TABLE_CAPACITY);
+
+        &self.entries[..count]
+    }
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_ENTRY: HookTableEntry = HookTableEntry {
+    target: AtomicU64::new(0),
+    detour: AtomicU64::new(0),
+    flags: AtomicU32::new(0),
+    _reserved: 0,
+};
+
+/// The table of the process.
+#[used]
+#[unsafe(no_mangle)]
+pub static MINHOOK_DETOURS_HOOK_TABLE: HookTable = HookTable {
+    magic: *b"MHDHOOKS",
+    version: 1,
+    capacity: HOOK_TABLE_CAPACITY as u32,
+    count: AtomicU32::new(0),
+    entries: [EMPTY_ENTRY; HOOK_TABLE_CAPACITY],
+};
+
+/// Serializes writers, as the entry count and the entry have to be updated together.
+static WRITER: AtomicUsize = AtomicUsize::new(0);
+
+/// Mirror `event` into [`MINHOOK_DETOURS_HOOK_TABLE`].
+pub(crate) fn record(event: &HookEvent<'_>) {
+    // A single guard exists at a time, so contention only comes from misuse. Spin rather than lock, so that
+    // nothing is ever allocated.
+    while WRITER
+        .compare_exchange_weak(0, 1, Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+        std::hint::spin_loop();
+    }
+
+    let table = &MINHOOK_DETOURS_HOOK_TABLE;
+
+    match *event {
+        HookEvent::Created { target, detour } => {
+            let index = table.count.load(Ordering::Relaxed) as usize;
+
+            if let Some(entry) = table.entries.get(index) {
+                entry.target.store(target as u64, Ordering::Relaxed);
+                entry.detour.store(detour as u64, Ordering::Relaxed);
+                entry.flags.store(0, Ordering::Relaxed);
+                table.count.store(index as u32 + 1, Ordering::Release);
+            }
+        }
+        HookEvent::Enabled { target } => update(target, |flags| flags | HOOK_FLAG_ENABLED),
+        HookEvent::Disabled { target } => update(target, |flags| flags & !HOOK_FLAG_ENABLED),
+        HookEvent::Removed { target } => update(target, |_| HOOK_FLAG_REMOVED),
+        HookEvent::EngineUninitialized => table.count.store(0, Ordering::Release),
+        HookEvent::EngineInitialized
+        | HookEvent::Failed { .. }
+        | HookEvent::DroppedWithLiveHooks { .. } => {}
+    }
+
+    WRITER.store(0, Ordering::Release);
+}
+
+/// Update the flags of the latest live entry for `target`.
+fn update(target: *mut c_void, flags: impl Fn(u32) -> u32) {
+    let entry = MINHOOK_DETOURS_HOOK_TABLE.entries().iter().rev().find(|entry| {
+        entry.target.load(Ordering::Relaxed) == target as u64
+            && entry.flags.load(Ordering::Relaxed) & HOOK_FLAG_REMOVED == 0
+    });
+
+    if let Some(entry) = entry {
+        entry
+            .flags
+            .store(flags(entry.flags.load(Ordering::Relaxed)), Ordering::Relaxed);
+    }
+}
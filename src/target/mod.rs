@@ -0,0 +1,289 @@
+//! Hook target specifications.
+//!
+//! Responsible for describing hook targets relative to the module they live in, e.g. `"kernel32.dll!CreateFileW+0x40"`,
+//! and resolving them at hook time, validating that displaced targets stay inside of the code of their owning module.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    os::raw::c_void,
+    str::FromStr,
+};
+
+use crate::{
+    error::{Error, Result},
+    logging,
+    pe::Module,
+    scan::{Pattern, scan_module},
+};
+
+/// Description of a function to be hooked, resolved into an address at hook time.
+///
+/// Exports, and ordinals can be parsed from `module!symbol+offset`, and `module!#ordinal+offset`, where the offset
+/// is optional, and either decimal or `0x`-prefixed hexadecimal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// An address that is already known.
+    Address(*mut c_void),
+    /// An export of a loaded module, by its name.
+    ///
+    /// Decorated MSVC names, e.g. `"?TakeDamage@Player@@QEAAXH@Z"`, are looked up as they are.
+    Export { module: String, symbol: String },
+    /// An export of a loaded module, by its ordinal.
+    Ordinal { module: String, ordinal: u16 },
+    /// A relative virtual address inside of a loaded module.
+    Rva { module: String, rva: usize },
+    /// An IDA-style byte pattern, which must match exactly once inside of the code of a loaded module.
+    Pattern { module: String, pattern: Pattern },
+    /// Another target, displaced by `offset` bytes, e.g. an inner label of a well-known function.
+    Offset { target: Box<Target>, offset: usize },
+}
+
+impl Target {
+    /// Describe an export of a loaded module.
+    ///
+    /// # Arguments
+    ///
+    /// * `module` - The name of the module, e.g. `"kernel32.dll"`.
+    /// * `symbol` - The name of the export, e.g. `"CreateFileW"`.
+    pub fn export(module: impl Into<String>, symbol: impl Into<String>) -> Self {
+        Self::Export {
+            module: module.into(),
+            symbol: symbol.into(),
+        }
+    }
+
+    /// Describe an export of a loaded module, for modules exporting it by ordinal only.
+    ///
+    /// # Arguments
+    ///
+    /// * `module` - The name of the module, e.g. `"ws2_32.dll"`.
+    /// * `ordinal` - The ordinal of the export.
+    pub fn ordinal(module: impl Into<String>, ordinal: u16) -> Self {
+        Self::Ordinal {
+            module: module.into(),
+            ordinal,
+        }
+    }
+
+    /// Displace the target by `offset` bytes.
+    ///
+    /// Offsets of an already displaced target add up, and a zero offset leaves the target untouched. Offsets
+    /// overflowing once added up are kept apart instead, and rejected by [`Target::resolve`].
+    pub fn offset(self, offset: usize) -> Self {
+        if offset == 0 {
+            return self;
+        }
+
+        match self {
+            Self::Offset {
+                target,
+                offset: previous,
+            } if previous.checked_add(offset).is_some() => Self::Offset {
+                target,
+                offset: previous + offset,
+            },
+            target => Self::Offset {
+                target: Box::new(target),
+                offset,
+            },
+        }
+    }
+
+    /// The name of the module the target lives in, or `None` for [`Target::Address`].
+    pub fn module(&self) -> Option<&str> {
+        match self {
+            Self::Address(_) => None,
+            Self::Export { module, .. }
+            | Self::Ordinal { module, .. }
+            | Self::Rva { module, .. }
+            | Self::Pattern { module, .. } => Some(module),
+            Self::Offset { target, .. } => target.module(),
+        }
+    }
+
+    /// Resolve the target into the address of the function to be hooked.
+    ///
+    /// Forwarded exports, including those of API sets, resolve to their implementation in the module they are
+    /// forwarded to, see [`Target::forwarder`].
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(*mut c_void)` with the address of the target.
+    /// - `Err(minhook_detours_rs::error::Error::InvalidModule)` if the module isn't loaded.
+    /// - `Err(minhook_detours_rs::error::Error::InvalidExport)` if the module doesn't have the export.
+    /// - `Err(minhook_detours_rs::error::Error::PatternMismatch)` if a pattern doesn't match exactly once.
+    /// - `Err(minhook_detours_rs::error::Error::TargetOutOfBounds)` if the target lands outside of the code of
+    ///   its owning module, or its offset overflows the address space.
+    pub fn resolve(&self) -> Result<*mut c_void> {
+        match self {
+            Self::Address(address) => Ok(*address),
+            Self::Export { module, symbol } => {
+                let module = Module::from_name(module)?;
+
+                if let Some(forwarder) = module.forwarder(symbol) {
+                    logging::debug!("Following forwarder of {self} to {forwarder:?}");
+                }
+
+                module.export(symbol)
+            }
+            Self::Ordinal { module, ordinal } => Module::from_name(module)?.export_ordinal(*ordinal),
+            Self::Rva { module, rva } => {
+                let module = Module::from_name(module)?;
+                let address = module.base().wrapping_add(*rva);
+
+                if !module.is_executable(address) {
+                    return Err(Error::TargetOutOfBounds);
+                }
+
+                Ok(address as *mut c_void)
+            }
+            Self::Pattern { module, pattern } => match scan_module(module, pattern)?.as_slice() {
+                [address] => Ok(*address),
+                _ => Err(Error::PatternMismatch),
+            },
+            Self::Offset { target, offset } => {
+                let base = target.resolve()?;
+                let address = base
+                    .addr()
+                    .checked_add(*offset)
+                    .map(|address| base.with_addr(address))
+                    .ok_or(Error::TargetOutOfBounds)?;
+
+                // Validated against the module owning the undisplaced target, so that offsets can't escape it.
+                match Module::from_address(base) {
+                    Ok(module) if !module.is_executable(address as *const u8) => {
+                        Err(Error::TargetOutOfBounds)
+                    }
+                    Ok(_) => Ok(address),
+                    // Generated code doesn't have an owning module to validate against.
+                    Err(_) if matches!(**target, Self::Address(_)) => Ok(address),
+                    Err(error) => Err(error),
+                }
+            }
+        }
+    }
+
+    /// The forwarder of the export, as written in the export directory of its module, e.g.
+    /// `"NTDLL.RtlAllocateHeap"` for `kernel32.dll!HeapAlloc`.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Some(String))` if the target is a forwarded export.
+    /// - `Ok(None)` if the target is an export implemented by its module, or isn't an export at all.
+    /// - `Err(minhook_detours_rs::error::Error)` if the module isn't loaded.
+    pub fn forwarder(&self) -> Result<Option<String>> {
+        match self {
+            Self::Export { module, symbol } => Ok(Module::from_name(module)?
+                .forwarder(symbol)
+                .map(|forwarder| forwarder.to_string_lossy().into_owned())),
+            Self::Offset { target, .. } => target.forwarder(),
+            _ => Ok(None),
+        }
+    }
+
+    /// The undecorated name of the export, if it has a decorated MSVC name, see [`demangle`].
+    #[cfg(feature = "demangle")]
+    pub fn demangled_symbol(&self) -> Option<String> {
+        match self {
+            Self::Export { symbol, .. } => demangle(symbol),
+            Self::Offset { target, .. } => target.demangled_symbol(),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Target {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Address(address) => write!(f, "{address:p}"),
+            Self::Export { module, symbol } => write!(f, "{module}!{symbol}"),
+            Self::Ordinal { module, ordinal } => write!(f, "{module}!#{ordinal}"),
+            Self::Rva { module, rva } => write!(f, "{module}+{rva:#x}"),
+            Self::Pattern { module, pattern } => write!(f, "{module}![{pattern}]"),
+            Self::Offset { target, offset } => write!(f, "{target}+{offset:#x}"),
+        }
+    }
+}
+
+impl FromStr for Target {
+    type Err = Error;
+
+    fn from_str(spec: &str) -> Result<Self> {
+        let (module, export) = spec.split_once('!').ok_or(Error::InvalidTargetSpec)?;
+
+        // A `+` that isn't followed by a number is left to the name of the export.
+        let (export, offset) = export
+            .rsplit_once('+')
+            .and_then(|(export, offset)| Some((export, parse_number(offset)?)))
+            .unwrap_or((export, 0));
+
+        if module.is_empty() || export.is_empty() {
+            return Err(Error::InvalidTargetSpec);
+        }
+
+        let target = match export.strip_prefix('#') {
+            Some(ordinal) => {
+                let ordinal = parse_number(ordinal)
+                    .and_then(|ordinal| u16::try_from(ordinal).ok())
+                    .ok_or(Error::InvalidTargetSpec)?;
+
+                Self::ordinal(module, ordinal)
+            }
+            None => Self::export(module, export),
+        };
+
+        Ok(target.offset(offset))
+    }
+}
+
+/// Parse either a decimal, or a `0x`-prefixed hexadecimal number.
+fn parse_number(value: &str) -> Option<usize> {
+    match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Undecorate a decorated MSVC name, e.g. `"?TakeDamage@Player@@QEAAXH@Z"`, through DbgHelp.
+///
+/// # Returns
+///
+/// - `Some(String)` with the undecorated name, e.g. `"public: void __cdecl Player::TakeDamage(int)"`.
+/// - `None` if `symbol` isn't a decorated name, or couldn't be undecorated.
+#[cfg(feature = "demangle")]
+pub fn demangle(symbol: &str) -> Option<String> {
+    use std::{ffi::CString, sync::Mutex};
+    use winapi::um::dbghelp::UnDecorateSymbolName;
+
+    // DbgHelp is single-threaded, so every call into it has to be serialized.
+    static DBGHELP: Mutex<()> = Mutex::new(());
+
+    // Equivalent of `UNDNAME_COMPLETE`.
+    const UNDNAME_COMPLETE: u32 = 0;
+
+    if !symbol.starts_with('?') {
+        return None;
+    }
+
+    let symbol = CString::new(symbol).ok()?;
+    let mut undecorated = [0u8; 1024];
+
+    let length = {
+        let _lock = DBGHELP.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        unsafe {
+            UnDecorateSymbolName(
+                symbol.as_ptr(),
+                undecorated.as_mut_ptr() as _,
+                undecorated.len() as u32,
+                UNDNAME_COMPLETE,
+            )
+        }
+    };
+
+    if length == 0 {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&undecorated[..length as usize]).into_owned())
+}
@@ -0,0 +1,68 @@
+//! Hookable test targets.
+//!
+//! Responsible for functions that are safe to hook from integration tests, and examples. Every function:
+//!
+//! - is `#[inline(never)]`, so that calls go through its address instead of being inlined into the caller,
+//! - does enough work behind [`black_box`] for its prologue to fit the engine's jump, instead of compiling
+//!   down to a couple of bytes,
+//! - has a body that no other function shares, so that the linker can't fold it together with another one,
+//!   which would make hooking one hook both.
+
+use std::hint::black_box;
+
+/// Returns `42`.
+#[inline(never)]
+pub fn return_number() -> u32 {
+    black_box(42u32).wrapping_add(black_box(0))
+}
+
+/// Returns `x + y`.
+#[inline(never)]
+pub extern "C" fn add_c(x: i32, y: i32) -> i64 {
+    black_box(x) as i64 + black_box(y) as i64
+}
+
+/// Returns `x * y`.
+#[inline(never)]
+pub extern "system" fn multiply_system(x: i32, y: i32) -> i64 {
+    black_box(x) as i64 * black_box(y) as i64
+}
+
+/// Returns the sum of its arguments, which are all passed in registers on x64.
+#[inline(never)]
+pub extern "C" fn sum_four(a: u64, b: u64, c: u64, d: u64) -> u64 {
+    black_box(a)
+        .wrapping_add(black_box(b))
+        .wrapping_add(black_box(c))
+        .wrapping_add(black_box(d))
+}
+
+/// Returns the sum of its arguments, some of which are passed on the stack on every ABI.
+#[inline(never)]
+pub extern "C" fn sum_six(a: u64, b: u64, c: u64, d: u64, e: u64, f: u64) -> u64 {
+    black_box(a)
+        .wrapping_add(black_box(b))
+        .wrapping_add(black_box(c))
+        .wrapping_add(black_box(d))
+        .wrapping_add(black_box(e))
+        .wrapping_add(black_box(f))
+}
+
+/// Returns `x / y`, exercising floating point registers.
+#[inline(never)]
+pub extern "C" fn divide_f64(x: f64, y: f64) -> f64 {
+    black_box(x) / black_box(y)
+}
+
+/// Returns `x - y`, with the arguments in `ecx`, and `edx`.
+#[cfg(target_arch = "x86")]
+#[inline(never)]
+pub extern "fastcall" fn subtract_fastcall(x: i32, y: i32) -> i32 {
+    black_box(x).wrapping_sub(black_box(y))
+}
+
+/// Returns the length of `value`, exercising non-`Copy` arguments with the Rust ABI.
+#[inline(never)]
+pub fn string_length(value: String) -> usize {
+    black_box(value).len()
+}
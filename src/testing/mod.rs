@@ -0,0 +1,346 @@
+//! Testing.
+//!
+//! Responsible for a mock engine that records the operations of a [`DetourGuard`], and emulates MinHook's
+//! bookkeeping without ever patching code, so that hook orchestration logic can be unit tested. Failures can
+//! be scripted, so that error paths can be exercised deterministically.
+//!
+//! Like the rest of the crate, the mock only builds for Windows targets, as its statuses are MinHook's, whose
+//! bindings only build there. Off Windows, the crate compiles down to nothing, so that cross-platform
+//! dependents can still depend on it unconditionally.
+//!
+//! ```ignore
+//! let engine = MockEngine::new();
+//! let mut guard = DetourGuard::with_mock(&engine)?;
+//!
+//! guard.create_and_enable_hook::<fn()>(target, detour)?;
+//! assert!(engine.is_enabled(target));
+//!
+//! // The second hook fails to be created.
+//! engine.fail_on(Operation::CreateHook, 2, MH_ERROR_MEMORY_ALLOC);
+//! ```
+
+use minhook_detours_sys::{
+    MH_ERROR_ALREADY_CREATED, MH_ERROR_ALREADY_INITIALIZED, MH_ERROR_DISABLED, MH_ERROR_ENABLED,
+    MH_ERROR_NOT_CREATED, MH_ERROR_NOT_INITIALIZED, MH_OK, MH_STATUS,
+};
+use std::{
+    os::raw::c_void,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use crate::{
+    engine::Engine,
+    error::{Operation, Result},
+    guard::{DetourGuard, ThreadFreezeMethod},
+};
+
+/// An operation received by a [`MockEngine`], where a null `target` stands for every hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EngineCall {
+    Initialize,
+    Uninitialize,
+    SetThreadFreezeMethod(ThreadFreezeMethod),
+    CreateHook {
+        target: *mut c_void,
+        detour: *mut c_void,
+    },
+    RemoveHook { target: *mut c_void },
+    EnableHook { target: *mut c_void },
+    DisableHook { target: *mut c_void },
+    QueueEnableHook { target: *mut c_void },
+    QueueDisableHook { target: *mut c_void },
+    ApplyQueued,
+}
+
+/// Engine that records operations instead of patching code.
+///
+/// Clones share their state, so that the engine can be inspected while a [`DetourGuard`] drives it.
+#[derive(Debug, Clone, Default)]
+pub struct MockEngine {
+    state: Arc<Mutex<MockState>>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    initialized: bool,
+    hooks: Vec<MockHook>,
+    calls: Vec<EngineCall>,
+    counts: Vec<Operation>,
+    failures: Vec<ScriptedFailure>,
+}
+
+/// Status returned instead of running the `nth` call of `operation`.
+#[derive(Debug)]
+struct ScriptedFailure {
+    operation: Operation,
+    nth: usize,
+    status: MH_STATUS,
+}
+
+// Addresses are only compared, and never dereferenced.
+unsafe impl Send for MockState {}
+
+#[derive(Debug)]
+struct MockHook {
+    target: *mut c_void,
+    enabled: bool,
+    queued: Option<bool>,
+}
+
+impl MockEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every operation received so far, in order.
+    pub fn calls(&self) -> Vec<EngineCall> {
+        self.lock().calls.clone()
+    }
+
+    /// Make the `nth` call of `operation`, counting from 1 since the engine was constructed, return `status`
+    /// without having any effect.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The operation to fail.
+    /// * `nth` - The call of `operation` to fail.
+    /// * `status` - The status to return, e.g. `MH_ERROR_MEMORY_ALLOC`.
+    pub fn fail_on(&self, operation: Operation, nth: usize, status: MH_STATUS) {
+        self.lock().failures.push(ScriptedFailure {
+            operation,
+            nth,
+            status,
+        });
+    }
+
+    /// Make the next call of `operation` return `status` without having any effect.
+    pub fn fail_next(&self, operation: Operation, status: MH_STATUS) {
+        let nth = self.lock().count(operation) + 1;
+        self.fail_on(operation, nth, status);
+    }
+
+    /// Forget the operations received so far.
+    ///
+    /// Scripted failures keep counting from the engine's construction.
+    pub fn clear_calls(&self) {
+        self.lock().calls.clear();
+    }
+
+    /// Whether a hook is registered for `target`.
+    pub fn is_created(&self, target: *mut c_void) -> bool {
+        self.lock().hooks.iter().any(|hook| hook.target == target)
+    }
+
+    /// Whether the hook registered for `target` is enabled.
+    pub fn is_enabled(&self, target: *mut c_void) -> bool {
+        self.lock()
+            .hooks
+            .iter()
+            .any(|hook| hook.target == target && hook.enabled)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MockState> {
+        // A failed assertion while the state is locked shouldn't hide the state from the next test.
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Record `call`, and run `operation` if the engine is initialized, unless a failure was scripted for it.
+    fn record(&self, call: EngineCall, operation: impl FnOnce(&mut MockState) -> MH_STATUS) -> MH_STATUS {
+        let mut state = self.lock();
+
+        if let Some(status) = state.push(call) {
+            return status;
+        }
+
+        if !state.initialized {
+            return MH_ERROR_NOT_INITIALIZED;
+        }
+
+        operation(&mut state)
+    }
+
+    pub(crate) fn initialize(&self) -> MH_STATUS {
+        let mut state = self.lock();
+
+        if let Some(status) = state.push(EngineCall::Initialize) {
+            return status;
+        }
+
+        if state.initialized {
+            return MH_ERROR_ALREADY_INITIALIZED;
+        }
+
+        state.initialized = true;
+        MH_OK
+    }
+
+    pub(crate) fn uninitialize(&self) -> MH_STATUS {
+        self.record(EngineCall::Uninitialize, |state| {
+            state.hooks.clear();
+            state.initialized = false;
+            MH_OK
+        })
+    }
+
+    pub(crate) fn set_thread_freeze_method(&self, method: ThreadFreezeMethod) -> MH_STATUS {
+        self.record(EngineCall::SetThreadFreezeMethod(method), |_| MH_OK)
+    }
+
+    /// # Safety
+    ///
+    /// `original` must be valid for writes.
+    pub(crate) unsafe fn create_hook(
+        &self,
+        target: *mut c_void,
+        detour: *mut c_void,
+        original: *mut *mut c_void,
+    ) -> MH_STATUS {
+        self.record(EngineCall::CreateHook { target, detour }, |state| {
+            if state.hooks.iter().any(|hook| hook.target == target) {
+                return MH_ERROR_ALREADY_CREATED;
+            }
+
+            state.hooks.push(MockHook {
+                target,
+                enabled: false,
+                queued: None,
+            });
+
+            // Nothing is patched, so the target itself behaves as the original.
+            unsafe { original.write(target) };
+            MH_OK
+        })
+    }
+
+    pub(crate) fn remove_hook(&self, target: *mut c_void) -> MH_STATUS {
+        self.record(EngineCall::RemoveHook { target }, |state| {
+            let Some(index) = state.hooks.iter().position(|hook| hook.target == target) else {
+                return MH_ERROR_NOT_CREATED;
+            };
+
+            state.hooks.remove(index);
+            MH_OK
+        })
+    }
+
+    pub(crate) fn enable_hook(&self, target: *mut c_void) -> MH_STATUS {
+        self.record(EngineCall::EnableHook { target }, |state| {
+            state.set_enabled(target, true)
+        })
+    }
+
+    pub(crate) fn disable_hook(&self, target: *mut c_void) -> MH_STATUS {
+        self.record(EngineCall::DisableHook { target }, |state| {
+            state.set_enabled(target, false)
+        })
+    }
+
+    pub(crate) fn queue_enable_hook(&self, target: *mut c_void) -> MH_STATUS {
+        self.record(EngineCall::QueueEnableHook { target }, |state| {
+            state.queue(target, true)
+        })
+    }
+
+    pub(crate) fn queue_disable_hook(&self, target: *mut c_void) -> MH_STATUS {
+        self.record(EngineCall::QueueDisableHook { target }, |state| {
+            state.queue(target, false)
+        })
+    }
+
+    pub(crate) fn apply_queued(&self) -> MH_STATUS {
+        self.record(EngineCall::ApplyQueued, |state| {
+            for hook in &mut state.hooks {
+                if let Some(enabled) = hook.queued.take() {
+                    hook.enabled = enabled;
+                }
+            }
+            MH_OK
+        })
+    }
+}
+
+impl EngineCall {
+    /// The operation the call stands for.
+    pub fn operation(&self) -> Operation {
+        match self {
+            Self::Initialize => Operation::Initialize,
+            Self::Uninitialize => Operation::Uninitialize,
+            Self::SetThreadFreezeMethod(_) => Operation::SetThreadFreezeMethod,
+            Self::CreateHook { .. } => Operation::CreateHook,
+            Self::RemoveHook { .. } => Operation::RemoveHook,
+            Self::EnableHook { .. } => Operation::EnableHook,
+            Self::DisableHook { .. } => Operation::DisableHook,
+            Self::QueueEnableHook { .. } => Operation::QueueEnableHook,
+            Self::QueueDisableHook { .. } => Operation::QueueDisableHook,
+            Self::ApplyQueued => Operation::ApplyQueued,
+        }
+    }
+}
+
+impl MockState {
+    /// Record `call`, returning the status scripted for it, if any.
+    fn push(&mut self, call: EngineCall) -> Option<MH_STATUS> {
+        let operation = call.operation();
+        self.calls.push(call);
+        self.counts.push(operation);
+
+        let nth = self.count(operation);
+        self.failures
+            .iter()
+            .find(|failure| failure.operation == operation && failure.nth == nth)
+            .map(|failure| failure.status)
+    }
+
+    /// The number of calls of `operation` received since the engine was constructed.
+    fn count(&self, operation: Operation) -> usize {
+        self.counts.iter().filter(|&&counted| counted == operation).count()
+    }
+
+    fn set_enabled(&mut self, target: *mut c_void, enabled: bool) -> MH_STATUS {
+        // Like MinHook, every hook is switched at once when `target` is null, whatever its state.
+        if target.is_null() {
+            self.hooks.iter_mut().for_each(|hook| hook.enabled = enabled);
+            return MH_OK;
+        }
+
+        let Some(hook) = self.hooks.iter_mut().find(|hook| hook.target == target) else {
+            return MH_ERROR_NOT_CREATED;
+        };
+
+        match (hook.enabled, enabled) {
+            (true, true) => MH_ERROR_ENABLED,
+            (false, false) => MH_ERROR_DISABLED,
+            _ => {
+                hook.enabled = enabled;
+                MH_OK
+            }
+        }
+    }
+
+    fn queue(&mut self, target: *mut c_void, enabled: bool) -> MH_STATUS {
+        if target.is_null() {
+            self.hooks
+                .iter_mut()
+                .for_each(|hook| hook.queued = Some(enabled));
+            return MH_OK;
+        }
+
+        let Some(hook) = self.hooks.iter_mut().find(|hook| hook.target == target) else {
+            return MH_ERROR_NOT_CREATED;
+        };
+
+        hook.queued = Some(enabled);
+        MH_OK
+    }
+}
+
+impl<'a> DetourGuard<'a> {
+    /// Construct a [`DetourGuard`] driving `engine` instead of MinHook.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine` - The mock engine, which can be inspected while the [`DetourGuard`] drives it.
+    pub fn with_mock(engine: &MockEngine) -> Result<Self> {
+        Self::with_engine(Engine::Mock(engine.clone()))
+    }
+}
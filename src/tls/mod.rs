@@ -0,0 +1,66 @@
+//! TLS callback installation.
+//!
+//! Responsible for running hook installation from a TLS callback of the module linking the crate, which the
+//! loader calls before the entry point, and therefore before the CRT, and static initializers of that
+//! module run. Registered through [`crate::tls_callback`].
+//!
+//! The loader lock is held meanwhile, so installation must stay away from loading modules, or waiting on
+//! other threads.
+
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+use crate::{error::Result, logging};
+
+#[doc(hidden)]
+pub const DLL_PROCESS_ATTACH: u32 = 1;
+
+/// Runs `install`, reporting its failure, as neither errors, nor panics may escape a TLS callback.
+#[doc(hidden)]
+pub fn run(name: &str, install: fn() -> Result<()>) {
+    match catch_unwind(AssertUnwindSafe(install)) {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => logging::warn!("TLS callback {name} failed: {e}"),
+        Err(_) => logging::warn!("TLS callback {name} panicked"),
+    }
+}
+
+/// Register a `fn() -> Result<()>` to run from a TLS callback once the module linking it is attached to the
+/// process, before its entry point, e.g. for getting hooks in place before static initializers run.
+///
+/// Guards created there outlive the callback through [`crate::guard::DetourGuard::into_raw`].
+///
+/// ```ignore
+/// fn install() -> Result<()> {
+///     let mut guard = DetourGuard::new()?;
+///     guard.create_and_enable_hook::<FunctionType>(target, detour as _)?;
+///     guard.into_raw();
+///     Ok(())
+/// }
+///
+/// tls_callback!(install);
+/// ```
+#[macro_export]
+macro_rules! tls_callback {
+    ($install:path) => {
+        const _: () = {
+            unsafe extern "system" fn callback(
+                _module: *mut ::std::os::raw::c_void,
+                reason: u32,
+                _reserved: *mut ::std::os::raw::c_void,
+            ) {
+                if reason == $crate::tls::DLL_PROCESS_ATTACH {
+                    $crate::tls::run(stringify!($install), $install);
+                }
+            }
+
+            // The loader calls every pointer laid out between `.CRT$XLA`, and `.CRT$XLZ`.
+            #[unsafe(link_section = ".CRT$XLB")]
+            #[used]
+            static CALLBACK: unsafe extern "system" fn(
+                *mut ::std::os::raw::c_void,
+                u32,
+                *mut ::std::os::raw::c_void,
+            ) = callback;
+        };
+    };
+}
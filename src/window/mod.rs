@@ -0,0 +1,144 @@
+//! Window procedure subclassing.
+//!
+//! Responsible for replacing the window procedure of a window, e.g. for handling the input of an overlay.
+//! Window procedures aren't patched through the engine, as they're already reached through a pointer that
+//! `SetWindowLongPtrW` can swap.
+
+use std::{mem::transmute, os::raw::c_void};
+use winapi::{
+    shared::{
+        minwindef::{LPARAM, LRESULT, UINT, WPARAM},
+        windef::HWND,
+    },
+    um::{
+        errhandlingapi::{GetLastError, SetLastError},
+        winuser::{CallWindowProcW, GWLP_WNDPROC, GetWindowLongPtrW, SetWindowLongPtrW, WNDPROC},
+    },
+};
+
+use crate::{
+    error::{Error, Result},
+    logging,
+};
+
+/// The signature of a window procedure.
+pub type WindowProcedure = unsafe extern "system" fn(HWND, UINT, WPARAM, LPARAM) -> LRESULT;
+
+/// The window procedure a [`WndProcHook`] replaced, the counterpart of the original function of a hook.
+///
+/// Procedures are called through `CallWindowProcW`, so that procedures expecting ANSI messages, which
+/// `GetWindowLongPtrW` only hands out as an opaque handle, are handled as well. It stays valid once the hook
+/// is restored, as it belongs to the window, or its class.
+#[derive(Debug, Clone, Copy)]
+pub struct OriginalWndProc(WNDPROC);
+
+// Window procedures are plain values, valid from any thread of the process.
+unsafe impl Send for OriginalWndProc {}
+unsafe impl Sync for OriginalWndProc {}
+
+impl OriginalWndProc {
+    /// Forwards a message to the window procedure.
+    ///
+    /// # Safety
+    ///
+    /// The arguments must be the ones the detour was called with, or valid for `message` otherwise.
+    pub unsafe fn call(&self, window: HWND, message: UINT, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        unsafe { CallWindowProcW(self.0, window, message, wparam, lparam) }
+    }
+
+    /// The address, or handle of the window procedure, null if there was none.
+    pub fn as_ptr(&self) -> *mut c_void {
+        self.0.map_or(std::ptr::null_mut(), |original| original as *mut c_void)
+    }
+}
+
+/// A window procedure replaced by a detour, restored once dropped.
+///
+/// Can be kept alongside the hooks through [`crate::guard::DetourGuard::subclass_window`].
+#[derive(Debug)]
+pub struct WndProcHook {
+    window: HWND,
+    detour: WindowProcedure,
+    original: WNDPROC,
+}
+
+// Window handles, and procedures are plain values, valid from any thread of the process.
+unsafe impl Send for WndProcHook {}
+unsafe impl Sync for WndProcHook {}
+
+impl WndProcHook {
+    /// Replaces the window procedure of `window` with `detour`.
+    ///
+    /// # Arguments
+    ///
+    /// * `window` - The window to subclass.
+    /// * `detour` - The window procedure to install, which should forward the messages it doesn't handle
+    ///   through [`WndProcHook::call_original`].
+    ///
+    /// # Safety
+    ///
+    /// `window` must be a valid window of the current process.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(WndProcHook)` if the window procedure was succesfully replaced.
+    /// - `Err(minhook_detours_rs::error::Error::WindowProcedure)` otherwise, with the last Win32 error.
+    pub unsafe fn new(window: HWND, detour: WindowProcedure) -> Result<Self> {
+        // The previous procedure can't be null, but zero is also how failure is reported.
+        unsafe { SetLastError(0) };
+        let previous = unsafe { SetWindowLongPtrW(window, GWLP_WNDPROC, detour as isize) };
+
+        let error = unsafe { GetLastError() };
+        if previous == 0 && error != 0 {
+            return Err(Error::WindowProcedure(error));
+        }
+
+        // We succesfully subclassed the window!
+        logging::debug!("Replaced the window procedure of {window:p}");
+        Ok(Self {
+            window,
+            detour,
+            original: unsafe { transmute::<isize, WNDPROC>(previous) },
+        })
+    }
+
+    /// The subclassed window.
+    pub fn target(&self) -> HWND {
+        self.window
+    }
+
+    /// The window procedure that was replaced.
+    pub fn original(&self) -> OriginalWndProc {
+        OriginalWndProc(self.original)
+    }
+
+    /// Forwards a message to the window procedure that was replaced, through `CallWindowProcW`, so that
+    /// procedures expecting ANSI messages are handled as well.
+    ///
+    /// # Safety
+    ///
+    /// The arguments must be the ones the detour was called with, or valid for `message` otherwise.
+    pub unsafe fn call_original(
+        &self,
+        window: HWND,
+        message: UINT,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        unsafe { self.original().call(window, message, wparam, lparam) }
+    }
+}
+
+impl Drop for WndProcHook {
+    fn drop(&mut self) {
+        // Someone subclassed the window after us, so restoring would unlink them as well.
+        let current = unsafe { GetWindowLongPtrW(self.window, GWLP_WNDPROC) };
+        if current != self.detour as isize {
+            logging::warn!("WndProcHook drop skipped, as the window was subclassed again");
+            return;
+        }
+
+        let original = self.original.map_or(0, |original| original as isize);
+        unsafe { SetWindowLongPtrW(self.window, GWLP_WNDPROC, original) };
+    }
+}
@@ -0,0 +1,83 @@
+//! WOW64 helpers.
+//!
+//! Responsible for reasoning about the bitness of modules, and of the current process, so that 32-bit images
+//! can be inspected from 64-bit tooling, and hooking across bitness is reported as such.
+//!
+//! The engine only patches code of the current process' bitness. Modules of the other bitness, e.g. mapped
+//! through `LoadLibraryExW` with `LOAD_LIBRARY_AS_IMAGE_RESOURCE`, are rejected with
+//! [`Error::BitnessMismatch`] by every API expecting hookable code, but can still be inspected here.
+
+use std::fmt::{self, Display, Formatter};
+use winapi::{
+    shared::minwindef::{FALSE, HMODULE},
+    um::{processthreadsapi::GetCurrentProcess, wow64apiset::IsWow64Process},
+};
+
+#[cfg(doc)]
+use crate::error::Error;
+use crate::{error::Result, pe::Module};
+
+/// The pointer width of an image, or of a process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bitness {
+    Bits32,
+    Bits64,
+}
+
+impl Bitness {
+    /// The bitness of the current process.
+    pub const fn native() -> Self {
+        if cfg!(target_pointer_width = "64") {
+            Self::Bits64
+        } else {
+            Self::Bits32
+        }
+    }
+}
+
+impl Display for Bitness {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bits32 => f.write_str("32-bit"),
+            Self::Bits64 => f.write_str("64-bit"),
+        }
+    }
+}
+
+/// Whether the current process is a 32-bit process running on 64-bit Windows.
+pub fn is_wow64() -> bool {
+    let mut wow64 = FALSE;
+
+    let succeeded = unsafe { IsWow64Process(GetCurrentProcess(), &mut wow64) };
+
+    succeeded != FALSE && wow64 != FALSE
+}
+
+/// The bitness of a module mapped as an image, whichever its bitness.
+///
+/// # Safety
+///
+/// `module` must be the base address of an image mapped in the current process.
+pub unsafe fn module_bitness(module: HMODULE) -> Result<Bitness> {
+    Ok(unsafe { Module::from_handle_any(module)? }.bitness())
+}
+
+/// Resolve the RVA of an export of a module mapped as an image, whichever its bitness, by walking its export
+/// directory.
+///
+/// # Arguments
+///
+/// * `module` - The base address of the image.
+/// * `symbol` - The name of the export.
+///
+/// # Safety
+///
+/// `module` must be the base address of an image mapped in the current process.
+///
+/// # Returns
+///
+/// - `Ok(u32)` if the export was found.
+/// - `Err(minhook_detours_rs::error::Error::InvalidExport)` otherwise.
+pub unsafe fn export_rva(module: HMODULE, symbol: &str) -> Result<u32> {
+    unsafe { Module::from_handle_any(module)? }.export_rva(symbol)
+}
@@ -0,0 +1,45 @@
+#![cfg(feature = "capi")]
+
+use minhook_detours_rs::capi::{
+    mhd_create_hook, mhd_disable, mhd_enable, mhd_init, mhd_uninit,
+};
+use minhook_detours_sys::{MH_ERROR_NOT_INITIALIZED, MH_OK};
+use serial_test::serial;
+use std::{os::raw::c_int, ptr::null_mut};
+
+#[test]
+#[serial]
+fn drive_hook_through_capi() {
+    // The type of the hooked function, and of the detour.
+    type FunctionType = extern "C" fn(i32, i32) -> i64;
+
+    extern "C" fn add_two(lhs: i32, rhs: i32) -> i64 {
+        (lhs + rhs) as i64
+    }
+
+    extern "C" fn add_two_hook(lhs: i32, rhs: i32) -> i64 {
+        (lhs - rhs) as i64
+    }
+
+    // Operations require the engine to be initialized first.
+    assert_eq!(mhd_enable(add_two as _), MH_ERROR_NOT_INITIALIZED as c_int);
+
+    assert_eq!(mhd_init(), MH_OK as c_int);
+
+    let mut original = null_mut();
+    assert_eq!(
+        unsafe { mhd_create_hook(add_two as _, add_two_hook as _, &mut original) },
+        MH_OK as c_int
+    );
+    assert_eq!(mhd_enable(add_two as _), MH_OK as c_int);
+
+    // The hook applies, while the original is still reachable.
+    let original: FunctionType = unsafe { std::mem::transmute(original) };
+    assert_eq!(add_two(2, 2), 0);
+    assert_eq!(original(2, 2), 4);
+
+    assert_eq!(mhd_disable(add_two as _), MH_OK as c_int);
+    assert_eq!(add_two(2, 2), 4);
+
+    assert_eq!(mhd_uninit(), MH_OK as c_int);
+}
@@ -0,0 +1,529 @@
+use minhook_detours_rs::{
+    detour::{
+        CallOriginalOnDrop, HookAction, bypass_hooks_for_current_thread, current_hook_context,
+    },
+    error::Result,
+    guard::DetourGuard,
+    filter_detour, hook_module, hook_struct, intercept_detour, non_reentrant_detour,
+    pipeline_detour, static_detour, trace_detour,
+};
+use serial_test::serial;
+
+fn add_two(x: i32, y: i32) -> i64 {
+    (x + y) as i64
+}
+
+static_detour! {
+    static ADD_TWO: fn(x: i32, y: i32) -> i64 =
+        |x, y| ADD_TWO.call_original(|original| original(x, y)) * 2;
+}
+
+#[test]
+#[serial]
+fn static_detour() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    let handle = ADD_TWO.create(&mut guard, add_two as _)?;
+    guard.enable_hook(handle.target())?;
+
+    // The shim forwards to the detour, which calls the original function.
+    assert_eq!(add_two(2, 2), 8);
+    assert_eq!(add_two(1, 2), 6);
+
+    #[cfg(feature = "stats")]
+    {
+        assert_eq!(handle.call_count(), Some(2));
+
+        let (info, stats) = guard.stats().next().unwrap();
+        assert_eq!(info.target(), add_two as _);
+        assert_eq!(stats.call_count(), 2);
+    }
+
+    #[cfg(feature = "timing")]
+    {
+        let stats = ADD_TWO.stats();
+
+        // The detour's duration includes the original's.
+        assert_eq!(stats.detour_timing().count, 2);
+        assert_eq!(stats.original_timing().count, 2);
+        assert!(stats.detour_timing().total >= stats.original_timing().total);
+        assert!(stats.detour_timing().min <= stats.detour_timing().mean());
+    }
+
+    Ok(())
+}
+
+fn multiply(x: u32, y: u32) -> u32 {
+    x * y
+}
+
+trace_detour! {
+    static MULTIPLY: fn(x: u32, y: u32) -> u32;
+}
+
+#[test]
+#[serial]
+fn trace_detour() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    let handle = MULTIPLY.create(&mut guard, multiply as _)?;
+    guard.enable_hook(handle.target())?;
+
+    // Tracing doesn't change the behavior of the function.
+    assert_eq!(multiply(6, 7), 42);
+
+    Ok(())
+}
+
+hook_struct! {
+    #[repr(C)]
+    struct Calculator {
+        add: fn(u32, u32) -> u32,
+        subtract: fn(u32, u32) -> u32,
+    }
+}
+
+#[inline(never)]
+fn add(x: u32, y: u32) -> u32 {
+    x + y
+}
+
+#[inline(never)]
+fn subtract(x: u32, y: u32) -> u32 {
+    x - y
+}
+
+fn add_hook(x: u32, y: u32) -> u32 {
+    x * y
+}
+
+fn subtract_hook(x: u32, y: u32) -> u32 {
+    x / y
+}
+
+#[test]
+#[serial]
+fn hook_struct() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    let targets = Calculator { add, subtract };
+    let originals = targets.create_hooks(
+        &mut guard,
+        &Calculator {
+            add: add_hook,
+            subtract: subtract_hook,
+        },
+    )?;
+    targets.enable_hooks(&mut guard)?;
+
+    assert_eq!(add(6, 3), 18);
+    assert_eq!(subtract(6, 3), 2);
+
+    // The originals still reach the real functions.
+    assert_eq!((originals.add)(6, 3), 9);
+    assert_eq!((originals.subtract)(6, 3), 3);
+
+    targets.remove_hooks(&mut guard)?;
+    assert_eq!(add(6, 3), 9);
+
+    Ok(())
+}
+
+#[inline(never)]
+fn negate(x: i32) -> i32 {
+    -x
+}
+
+#[inline(never)]
+fn double(x: i32) -> i32 {
+    x * 2
+}
+
+static_detour! {
+    static NEGATE: fn(x: i32) -> i32 = |x| NEGATE.original()(x) + 1;
+}
+
+static_detour! {
+    static DOUBLE: fn(x: i32) -> i32 = |x| DOUBLE.original()(x) + 1;
+}
+
+hook_module! {
+    name: Arithmetic,
+    hooks: [
+        NEGATE => negate as _,
+        DOUBLE => double as _,
+    ],
+}
+
+#[test]
+#[serial]
+fn hook_module() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    let arithmetic = Arithmetic::install(&mut guard)?;
+    assert_eq!(arithmetic.handles().len(), 2);
+
+    arithmetic.enable(&mut guard)?;
+    assert_eq!(negate(2), -1);
+    assert_eq!(double(2), 5);
+
+    arithmetic.disable(&mut guard)?;
+    assert_eq!(negate(2), -2);
+
+    arithmetic.uninstall(&mut guard)?;
+    assert_eq!(guard.hooks().count(), 0);
+
+    Ok(())
+}
+
+static_detour! {
+    static PANICKING: fn(x: u32) -> u32 = |_| panic!("detour failed");
+}
+
+#[test]
+fn unwind_on_panic() {
+    let shim: fn(u32) -> u32 = unsafe { std::mem::transmute(PANICKING.detour()) };
+
+    // Without `set_abort_on_panic`, the panic reaches the caller untouched.
+    let payload = std::panic::catch_unwind(|| shim(1)).unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"detour failed"));
+}
+
+/// Set for the test binary running [`abort_on_panic`] again, as a child process that is expected to abort.
+const ABORT_CHILD: &str = "MINHOOK_DETOURS_ABORT_CHILD";
+
+#[test]
+fn abort_on_panic() {
+    if std::env::var_os(ABORT_CHILD).is_some() {
+        minhook_detours_rs::detour::set_abort_on_panic(|panic| {
+            let (detour, message) = (panic.detour, panic.message);
+            eprintln!("aborting: {detour} panicked with {message:?}");
+        });
+
+        let shim: fn(u32) -> u32 = unsafe { std::mem::transmute(PANICKING.detour()) };
+        let _ = std::panic::catch_unwind(|| shim(1));
+
+        // Reaching this point means the panic unwound, rather than aborting.
+        std::process::exit(0);
+    }
+
+    // Aborting would take the whole test binary down, so the panic is raised in a child process.
+    let output = std::process::Command::new(std::env::current_exe().unwrap())
+        .args(["abort_on_panic", "--exact", "--nocapture"])
+        .env(ABORT_CHILD, "1")
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("aborting: PANICKING panicked with Some(\"detour failed\")")
+    );
+}
+
+fn increment(x: u32) -> u32 {
+    x + 1
+}
+
+non_reentrant_detour! {
+    // Calls the hooked function again, which would recurse forever through `static_detour!`.
+    static INCREMENT: fn(x: u32) -> u32 = |x| {
+        assert!(INCREMENT.is_entered());
+        increment(x) * 10
+    };
+}
+
+#[test]
+#[serial]
+fn non_reentrant_detour() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    let handle = INCREMENT.create(&mut guard, increment as _)?;
+    guard.enable_hook(handle.target())?;
+
+    // The inner call went to the original function.
+    assert_eq!(increment(1), 20);
+    assert!(!INCREMENT.is_entered());
+
+    Ok(())
+}
+
+fn halve(x: u32) -> u32 {
+    x / 2
+}
+
+static_detour! {
+    static HALVE: fn(x: u32) -> u32 = |x| HALVE.call_original(|original| original(x)) + 1;
+}
+
+#[test]
+#[serial]
+fn bypass_current_thread() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    let handle = HALVE.create(&mut guard, halve as _)?;
+    guard.enable_hook(handle.target())?;
+    assert_eq!(halve(10), 6);
+
+    // Calls of the current thread reach the original function, even nested ones.
+    assert_eq!(bypass_hooks_for_current_thread(|| halve(10)), 5);
+    let nested = bypass_hooks_for_current_thread(|| bypass_hooks_for_current_thread(|| halve(10)));
+    assert_eq!(nested, 5);
+
+    // Other threads are unaffected meanwhile.
+    let other = bypass_hooks_for_current_thread(|| std::thread::spawn(|| halve(10)).join());
+    assert_eq!(other.unwrap(), 6);
+    assert_eq!(halve(10), 6);
+
+    Ok(())
+}
+
+fn third(x: u32) -> u32 {
+    x / 3
+}
+
+static_detour! {
+    static THIRD: fn(x: u32) -> u32 = |x| THIRD.call_original(|original| original(x)) + 1;
+}
+
+#[test]
+#[serial]
+fn with_hook_disabled_on_current_thread() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    let handle = THIRD.create(&mut guard, third as _)?;
+    guard.enable_hook(handle.target())?;
+    assert_eq!(third(9), 4);
+
+    // Only the current thread reaches the original function, the hook stays enabled for the others.
+    let (current, other) = guard.with_hook_disabled(handle.target(), || {
+        (third(9), std::thread::spawn(|| third(9)).join().unwrap())
+    })?;
+    assert_eq!((current, other), (3, 4));
+    assert!(guard.hook_info(handle.target()).unwrap().is_enabled());
+    assert_eq!(third(9), 4);
+
+    Ok(())
+}
+
+#[test]
+fn call_original_on_drop() {
+    use std::cell::Cell;
+
+    let calls = Cell::new(0);
+    let original = || {
+        calls.set(calls.get() + 1);
+        42
+    };
+
+    // Called explicitly, the original isn't called again once dropped.
+    assert_eq!(CallOriginalOnDrop::new(original).call(), 42);
+    assert_eq!(calls.get(), 1);
+
+    // Early returns still forward.
+    let detour = |early: bool| {
+        let original = CallOriginalOnDrop::new(original);
+        if early {
+            return 0;
+        }
+        original.call()
+    };
+    assert_eq!(detour(true), 0);
+    assert_eq!(calls.get(), 2);
+
+    // And so do panics.
+    let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let _original = CallOriginalOnDrop::new(original);
+        panic!("detour failed");
+    }));
+    assert!(panicked.is_err());
+    assert_eq!(calls.get(), 3);
+
+    CallOriginalOnDrop::new(original).cancel();
+    assert_eq!(calls.get(), 3);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn sum(a: u32, b: u32, c: u32, d: u32, e: u32, f: u32, g: u32, h: u32) -> u32 {
+    a + b + c + d + e + f + g + h
+}
+
+static_detour! {
+    static SUM: fn(a: u32, b: u32, c: u32, d: u32, e: u32, f: u32, g: u32, h: u32) -> u32 =
+        |_, _, _, _, _, _, _, _| forward_to_original!() * 10;
+}
+
+#[test]
+#[serial]
+fn forward_to_original() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    let handle = SUM.create(&mut guard, sum as _)?;
+    guard.enable_hook(handle.target())?;
+
+    // Every argument made it to the original function, in order.
+    assert_eq!(sum(1, 2, 3, 4, 5, 6, 7, 8), 360);
+
+    Ok(())
+}
+
+fn write(file: u32, buffer: u32, length: u32) -> u32 {
+    file + buffer + length
+}
+
+intercept_detour! {
+    static WRITE: fn(file: u32, buffer: u32, length: u32) -> u32 =
+        |args| args.set::<2>(args.get::<2>().min(16));
+}
+
+#[test]
+#[serial]
+fn intercept_detour() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    let handle = WRITE.create(&mut guard, write as _)?;
+    guard.enable_hook(handle.target())?;
+
+    // Only the third argument was changed on its way to the original function.
+    assert_eq!(write(1, 2, 100), 19);
+    assert_eq!(write(1, 2, 3), 6);
+
+    Ok(())
+}
+
+fn open(id: u32) -> i32 {
+    id as i32
+}
+
+filter_detour! {
+    static OPEN: fn(id: u32) -> i32 = |args| {
+        if args.get::<0>() == 13 {
+            return HookAction::Return(-1);
+        }
+
+        // Forwarded as modified.
+        args.set::<0>(args.get::<0>() * 2);
+        HookAction::CallOriginal
+    };
+}
+
+#[test]
+#[serial]
+fn filter_detour() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    let handle = OPEN.create(&mut guard, open as _)?;
+    guard.enable_hook(handle.target())?;
+
+    assert_eq!(open(13), -1);
+    assert_eq!(open(2), 4);
+
+    Ok(())
+}
+
+static SENT: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+fn send(length: u32) -> u32 {
+    SENT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    length
+}
+
+pipeline_detour! {
+    static SEND: fn(length: u32) -> u32;
+}
+
+#[test]
+#[serial]
+fn pipeline_detour() -> Result<()> {
+    use std::sync::{Arc, Mutex};
+
+    let mut guard = DetourGuard::new()?;
+
+    let handle = SEND.create(&mut guard, send as _)?;
+    guard.enable_hook(handle.target())?;
+
+    // Without subscribers, calls go through untouched.
+    assert_eq!(send(5), 5);
+
+    let order = Arc::new(Mutex::new(Vec::new()));
+
+    let observed = order.clone();
+    let last = SEND.inspect(10, move |args| observed.lock().unwrap().push(args.get::<0>()));
+    let observed = order.clone();
+    let first = SEND.subscribe(-10, move |args| {
+        observed.lock().unwrap().push(args.get::<0>());
+        args.set::<0>(args.get::<0>() + 1);
+    });
+    assert_eq!(SEND.subscribers(), 2);
+
+    // Subscribers run by priority, and the original is called once, with the modified arguments.
+    assert_eq!(send(5), 6);
+    assert_eq!(*order.lock().unwrap(), [5, 6]);
+    assert_eq!(SENT.load(std::sync::atomic::Ordering::Relaxed), 2);
+
+    assert!(SEND.unsubscribe(first));
+    assert!(!SEND.unsubscribe(first));
+    assert!(SEND.unsubscribe(last));
+    assert_eq!(send(5), 5);
+
+    // Subscribers may unsubscribe while being run, the list outliving the change until the call returns.
+    let subscription = Arc::new(Mutex::new(None));
+    let own = subscription.clone();
+    let once = SEND.inspect(0, move |_| {
+        if let Some(once) = own.lock().unwrap().take() {
+            SEND.unsubscribe(once);
+        }
+    });
+    *subscription.lock().unwrap() = Some(once);
+
+    assert_eq!(send(5), 5);
+    assert_eq!(SEND.subscribers(), 0);
+
+    Ok(())
+}
+
+fn scale(x: u32) -> u32 {
+    x
+}
+
+static_detour! {
+    static SCALE: fn(x: u32) -> u32 = |x| {
+        let factor = current_hook_context::<u32>().map_or(1, |factor| *factor);
+        SCALE.call_original(|original| original(x)) * factor
+    };
+}
+
+#[test]
+#[serial]
+fn hook_context() -> Result<()> {
+    use std::sync::Arc;
+
+    let mut guard = DetourGuard::new()?;
+
+    let handle = SCALE.create_with_context(&mut guard, scale as _, Arc::new(3u32))?;
+    guard.enable_hook(handle.target())?;
+
+    assert_eq!(scale(2), 6);
+    assert_eq!(SCALE.context::<u32>().as_deref(), Some(&3));
+    assert!(SCALE.context::<i64>().is_none());
+
+    // Outside of the detour, there's no current hook.
+    assert!(current_hook_context::<u32>().is_none());
+
+    SCALE.set_context(Arc::new(5u32));
+    assert_eq!(scale(2), 10);
+
+    SCALE.clear_context();
+    assert_eq!(scale(2), 2);
+
+    // The user data belongs to the hook, so a hook created later on doesn't inherit it.
+    SCALE.set_context(Arc::new(7u32));
+    guard.remove_hook(handle.target())?;
+    assert!(SCALE.context::<u32>().is_none());
+
+    let handle = SCALE.create(&mut guard, scale as _)?;
+    guard.enable_hook(handle.target())?;
+    assert_eq!(scale(2), 2);
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use minhook_detours_rs::error::{Error, ErrorContext, Result, ResultExt};
+use minhook_detours_sys::{MH_ERROR_ENABLED, MH_STATUS};
+
+#[test]
+fn raw_status_roundtrip() {
+    // MinHook-native errors keep their status.
+    assert_eq!(Error::from(MH_ERROR_ENABLED).raw_status(), Some(MH_ERROR_ENABLED));
+
+    // Statuses unknown to the crate are preserved rather than lost.
+    let unknown: MH_STATUS = 0x1337;
+    assert!(matches!(Error::from(unknown), Error::Unknown(status) if status == unknown));
+    assert_eq!(Error::from(unknown).raw_status(), Some(unknown));
+
+    // Rust-level errors never went through the C API.
+    assert_eq!(Error::InvalidTarget.raw_status(), None);
+}
+
+#[test]
+fn attach_context() {
+    let result: Result<()> = Err(Error::InvalidTarget);
+    let error = result
+        .for_target(0x1000 as *const _)
+        .for_symbol("user32.dll!MessageBoxW")
+        .unwrap_err();
+
+    // Context accumulates in a single layer, around the original error.
+    assert!(matches!(error.root(), Error::InvalidTarget));
+    let mut expected = ErrorContext::default();
+    expected.target = Some(0x1000);
+    expected.symbol = Some("user32.dll!MessageBoxW".into());
+    assert_eq!(error.context(), Some(&expected));
+    #[cfg(not(feature = "minimal"))]
+    assert_eq!(
+        error.to_string(),
+        "Operation failed for user32.dll!MessageBoxW (0x1000): The specified pointer is known to be invalid"
+    );
+}
+
+#[test]
+fn map_to_hresult() {
+    // E_OUTOFMEMORY, and E_POINTER respectively.
+    assert_eq!(Error::FailedAllocatingMemory.to_hresult(), 0x8007000E_u32 as i32);
+    assert_eq!(
+        Err::<(), _>(Error::InvalidTarget)
+            .for_symbol("user32.dll!MessageBoxW")
+            .unwrap_err()
+            .to_hresult(),
+        0x80004003_u32 as i32
+    );
+
+    // HRESULT_FROM_WIN32(ERROR_MOD_NOT_FOUND).
+    assert_eq!(Error::InvalidModule.to_hresult(), 0x8007007E_u32 as i32);
+}
+
+#[test]
+fn stable_codes() {
+    // MinHook-native errors reuse their status, Rust-level ones sit past them.
+    assert_eq!(Error::from(MH_ERROR_ENABLED).code(), MH_ERROR_ENABLED);
+    assert_eq!(Error::InvalidTarget.code(), 0x100);
+
+    // Context doesn't change the kind of the error.
+    let error = Err::<(), _>(Error::Poisoned).for_target(0x1000 as *const _).unwrap_err();
+    assert_eq!(error.code(), 0x113);
+}
+
+#[cfg(feature = "minimal")]
+#[test]
+fn minimal_messages() {
+    assert_eq!(Error::InvalidTarget.to_string(), "error 0x100");
+    assert_eq!(format!("{:?}", Error::InvalidTarget), "error 0x100");
+}
@@ -0,0 +1,41 @@
+#![cfg(feature = "etw")]
+
+use std::ptr::null_mut;
+
+use minhook_detours_rs::{
+    error::Error,
+    etw::EtwObserver,
+    guard::{HookEvent, HookObserver},
+};
+
+#[test]
+fn register_provider() {
+    let observer = EtwObserver::new();
+    assert!(observer.is_registered());
+
+    // Registering again is a no-op.
+    assert!(EtwObserver::new().is_registered());
+}
+
+#[test]
+fn emit_every_event() {
+    let mut observer = EtwObserver::new();
+    let error = Error::NotInitialized;
+
+    // Without a session listening, every event is dropped by ETW, but still has to go through.
+    for event in [
+        HookEvent::EngineInitialized,
+        HookEvent::Created {
+            target: null_mut(),
+            detour: null_mut(),
+        },
+        HookEvent::Enabled { target: null_mut() },
+        HookEvent::Disabled { target: null_mut() },
+        HookEvent::Removed { target: null_mut() },
+        HookEvent::Failed { error: &error },
+        HookEvent::DroppedWithLiveHooks { count: 1 },
+        HookEvent::EngineUninitialized,
+    ] {
+        observer.on_event(&event);
+    }
+}
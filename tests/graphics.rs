@@ -0,0 +1,95 @@
+#![cfg(feature = "graphics")]
+
+use std::{
+    os::raw::{c_int, c_void},
+    ptr::null_mut,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use minhook_detours_rs::{
+    error::{Error, Result},
+    graphics::{d3d9_targets, dxgi_targets},
+    guard::DetourGuard,
+    target::Target,
+};
+use serial_test::serial;
+use winapi::{
+    shared::{
+        minwindef::HINSTANCE,
+        windef::{HMENU, HWND},
+        winerror::{E_FAIL, ERROR_ACCESS_DENIED, HRESULT_FROM_WIN32},
+    },
+    um::errhandlingapi::SetLastError,
+};
+
+#[test]
+#[serial]
+fn resolve_targets() -> Result<()> {
+    // Headless machines may lack a display adapter altogether.
+    match dxgi_targets() {
+        Ok(targets) => {
+            assert!(!targets.present.is_null());
+            assert!(!targets.resize_buffers.is_null());
+        }
+        Err(Error::GraphicsDevice(_)) => {}
+        Err(e) => return Err(e),
+    }
+
+    match d3d9_targets() {
+        Ok(targets) => {
+            assert!(!targets.end_scene.is_null());
+            assert_ne!(targets.end_scene, targets.present);
+        }
+        Err(Error::GraphicsDevice(_)) => {}
+        Err(e) => return Err(e),
+    }
+
+    Ok(())
+}
+
+/// The error the failing `CreateWindowExW` leaves behind.
+static LAST_ERROR: AtomicU32 = AtomicU32::new(0);
+
+#[allow(clippy::too_many_arguments)]
+unsafe extern "system" fn create_window_hook(
+    _: u32,
+    _: *const u16,
+    _: *const u16,
+    _: u32,
+    _: c_int,
+    _: c_int,
+    _: c_int,
+    _: c_int,
+    _: HWND,
+    _: HMENU,
+    _: HINSTANCE,
+    _: *mut c_void,
+) -> HWND {
+    unsafe { SetLastError(LAST_ERROR.load(Ordering::SeqCst)) };
+    null_mut()
+}
+
+#[test]
+#[serial]
+fn report_window_failures() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    // Make creating the hidden window fail.
+    let target = Target::export("user32.dll", "CreateWindowExW").resolve()?;
+    let _ = guard.create_and_enable_hook::<*mut c_void>(target, create_window_hook as _)?;
+
+    LAST_ERROR.store(ERROR_ACCESS_DENIED, Ordering::SeqCst);
+    assert!(matches!(
+        dxgi_targets(),
+        Err(Error::GraphicsDevice(hresult)) if hresult == HRESULT_FROM_WIN32(ERROR_ACCESS_DENIED)
+    ));
+
+    // Failing without an error code still isn't a success.
+    LAST_ERROR.store(0, Ordering::SeqCst);
+    assert!(matches!(
+        dxgi_targets(),
+        Err(Error::GraphicsDevice(hresult)) if hresult == E_FAIL
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use minhook_detours_rs::heap::PrivateHeap;
+use std::alloc::{GlobalAlloc, Layout};
+
+// Everything the test harness, and the crate allocate goes through the private heap.
+#[global_allocator]
+static ALLOCATOR: PrivateHeap = PrivateHeap;
+
+#[test]
+fn allocate_privately() {
+    let numbers = (0..1024).collect::<Vec<u32>>();
+    assert_eq!(numbers.iter().sum::<u32>(), 523776);
+
+    assert!(PrivateHeap::is_private(PrivateHeap::handle()));
+    assert!(!PrivateHeap::is_private(std::ptr::null_mut()));
+}
+
+#[test]
+fn over_aligned() {
+    let layout = Layout::from_size_align(100, 256).unwrap();
+
+    unsafe {
+        let block = ALLOCATOR.alloc_zeroed(layout);
+        assert_eq!(block as usize % 256, 0);
+        assert_eq!(*block.add(99), 0);
+        block.write_bytes(0x42, 100);
+
+        // Growing keeps both the contents, and the alignment.
+        let grown = ALLOCATOR.realloc(block, layout, 4096);
+        assert_eq!(grown as usize % 256, 0);
+        assert_eq!(*grown.add(99), 0x42);
+
+        ALLOCATOR.dealloc(grown, Layout::from_size_align(4096, 256).unwrap());
+    }
+}
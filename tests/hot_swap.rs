@@ -0,0 +1,100 @@
+#![cfg(feature = "hot-swap")]
+
+use std::{fs, os::raw::c_void, ptr::null_mut};
+
+use minhook_detours_rs::{error::Result, guard::DetourGuard, hot_swap::HotSwap};
+use serial_test::serial;
+use winapi::{
+    shared::minwindef::{DWORD, LPDWORD},
+    um::winnt::LPCWSTR,
+};
+
+// The system `version.dll` stands in for a DLL of detours, with `GetFileVersionInfoSizeW` as the detour,
+// which returns 0 for files that don't exist.
+const DLL: &str = r"C:\Windows\System32\version.dll";
+const SYMBOL: &str = "GetFileVersionInfoSizeW";
+
+type FunctionType = unsafe extern "system" fn(LPCWSTR, LPDWORD) -> DWORD;
+
+#[inline(never)]
+unsafe extern "system" fn first_target(_: LPCWSTR, _: LPDWORD) -> DWORD {
+    std::hint::black_box(7)
+}
+
+#[inline(never)]
+unsafe extern "system" fn second_target(_: LPCWSTR, _: LPDWORD) -> DWORD {
+    std::hint::black_box(8)
+}
+
+fn call(function: FunctionType) -> DWORD {
+    let path = "not-a-file.dll\0".encode_utf16().collect::<Vec<_>>();
+    unsafe { std::hint::black_box(function)(path.as_ptr(), null_mut()) }
+}
+
+#[test]
+#[serial]
+fn reload_keeps_hooks() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+    let mut swap = HotSwap::load(DLL)?;
+
+    let target = first_target as *mut c_void;
+    let _ = swap.hook::<FunctionType>(&mut guard, target, SYMBOL)?;
+    guard.enable_hook(target)?;
+    assert_eq!(call(first_target), 0);
+
+    let previous = guard.hook_info(target).unwrap().detour();
+    swap.reload(&mut guard)?;
+
+    // The hook now runs on the copy of the second generation.
+    assert_eq!(swap.generation(), 1);
+    assert_ne!(guard.hook_info(target).unwrap().detour(), previous);
+    assert_eq!(call(first_target), 0);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn failed_reload_rolls_back() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+    let mut swap = HotSwap::load(DLL)?;
+
+    let first = first_target as *mut c_void;
+    let second = second_target as *mut c_void;
+    let _ = swap.hook::<FunctionType>(&mut guard, first, SYMBOL)?;
+    let _ = swap.hook::<FunctionType>(&mut guard, second, SYMBOL)?;
+    guard.enable_hook(first)?;
+
+    // The first hook is re-pointed before the second one fails to be.
+    guard.remove_hook(second)?;
+
+    let previous = guard.hook_info(first).unwrap().detour();
+    assert!(swap.reload(&mut guard).is_err());
+
+    assert_eq!(swap.generation(), 0);
+    assert_eq!(guard.hook_info(first).unwrap().detour(), previous);
+    assert_eq!(call(first_target), 0);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn load_removes_stale_copies() -> Result<()> {
+    let directory = std::env::temp_dir().join("minhook-detours-hot-swap");
+    fs::create_dir_all(&directory).unwrap();
+
+    // A copy left behind by a process that's gone, and a file that merely shares the prefix.
+    let stale = directory.join("version-0-0.dll");
+    let unrelated = directory.join("version-info.dll");
+    fs::write(&stale, []).unwrap();
+    fs::write(&unrelated, []).unwrap();
+
+    let _swap = HotSwap::load(DLL)?;
+
+    assert!(!stale.exists());
+    assert!(unrelated.exists());
+
+    let _ = fs::remove_file(&unrelated);
+    Ok(())
+}
@@ -0,0 +1,61 @@
+#![cfg(target_env = "msvc")]
+
+use std::{os::raw::c_void, ptr::null_mut};
+
+use minhook_detours_rs::{error::Result, import::DelayImportHook};
+use winapi::{
+    shared::minwindef::{DWORD, LPDWORD},
+    um::{winnt::LPCWSTR, winver::GetFileVersionInfoSizeW},
+};
+
+/// The test binary delay-loads `version.dll`, see `build.rs`.
+const DLL: &str = "version.dll";
+const SYMBOL: &str = "GetFileVersionInfoSizeW";
+
+unsafe extern "system" fn get_file_version_info_size_hook(_: LPCWSTR, _: LPDWORD) -> DWORD {
+    42
+}
+
+fn executable() -> String {
+    let path = std::env::current_exe().unwrap();
+    path.file_name().unwrap().to_string_lossy().into_owned()
+}
+
+fn get_file_version_info_size() -> DWORD {
+    let path = "not-a-file.dll\0".encode_utf16().collect::<Vec<_>>();
+    unsafe { GetFileVersionInfoSizeW(path.as_ptr(), null_mut()) }
+}
+
+#[test]
+fn hook_delay_import() -> Result<()> {
+    let module = executable();
+    type FunctionType = unsafe extern "system" fn(LPCWSTR, LPDWORD) -> DWORD;
+
+    // The import wasn't called yet, so the slot still points at the delay-load thunk.
+    {
+        let hook = unsafe {
+            DelayImportHook::new(&module, DLL, SYMBOL, get_file_version_info_size_hook as *mut c_void)?
+        };
+        assert_eq!(get_file_version_info_size(), 42);
+
+        // The original was resolved right away, without waiting for the first call.
+        let original: FunctionType = unsafe { std::mem::transmute(hook.original()) };
+        let path = "not-a-file.dll\0".encode_utf16().collect::<Vec<_>>();
+        assert_eq!(unsafe { original(path.as_ptr(), null_mut()) }, 0);
+    }
+
+    // Dropping the hook restored the thunk, which the first call snaps through the delay-load helper.
+    assert_eq!(get_file_version_info_size(), 0);
+
+    // Snapped slots are swapped as they are.
+    {
+        let _hook = unsafe {
+            DelayImportHook::new(&module, DLL, SYMBOL, get_file_version_info_size_hook as *mut c_void)?
+        };
+        assert_eq!(get_file_version_info_size(), 42);
+    }
+
+    assert_eq!(get_file_version_info_size(), 0);
+
+    Ok(())
+}
@@ -1,5 +1,12 @@
-use minhook_detours_rs::{error::Result, guard::DetourGuard};
+use minhook_detours_rs::{
+    error::{Error, Operation, Result},
+    guard::{DetourGuard, HookConfig, HookEvent, HookHandle, ThreadFreezeMethod},
+    target::Target,
+};
+use minhook_detours_sys::{MH_Initialize, MH_OK, MH_THREAD_FREEZE_METHOD, MH_Uninitialize};
 use serial_test::serial;
+use std::sync::{Arc, Mutex};
+use winapi::um::libloaderapi::{FreeLibrary, LoadLibraryW};
 
 // The `#[serial]` attribute is used to make sure the tests don't run in parallel, which could lead to
 // the creation of multiple [`DetourGuard`]-s at the same time, which is unsupported behavior.
@@ -184,3 +191,395 @@ fn standard_original_usage() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+#[serial]
+fn apply_config() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    // The type of the hooked functions, and of the detours.
+    type FunctionType = fn() -> u32;
+
+    fn return_one() -> u32 {
+        1
+    }
+
+    fn return_one_hook() -> u32 {
+        10
+    }
+
+    fn return_two() -> u32 {
+        2
+    }
+
+    fn return_two_hook() -> u32 {
+        20
+    }
+
+    let _ = guard.create_hook::<FunctionType>(return_one as _, return_one_hook as _)?;
+    let _ = guard.create_and_enable_hook::<FunctionType>(return_two as _, return_two_hook as _)?;
+    guard.set_hook_name(return_one as _, "one")?;
+    guard.set_hook_name(return_two as _, "two")?;
+
+    // Enable `one`, and drop `two` from the configuration entirely.
+    guard.apply_config(&HookConfig::from_iter([("one", true)]))?;
+
+    assert_eq!(return_one(), 10);
+    assert_eq!(return_two(), 2);
+    assert!(guard.hook_info(return_two as _).is_none());
+
+    // Unknown hooks are rejected without applying anything.
+    assert!(matches!(
+        guard.apply_config(&HookConfig::from_iter([("one", false), ("three", true)])),
+        Err(Error::UnknownHook(_))
+    ));
+    assert_eq!(return_one(), 10);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn raw_handle_roundtrip() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    // The type of the hooked function, and of the detour.
+    type FunctionType = fn() -> u32;
+
+    fn return_number() -> u32 {
+        42
+    }
+
+    fn return_number_hook() -> u32 {
+        1337
+    }
+
+    let original =
+        guard.create_and_enable_hook::<FunctionType>(return_number as _, return_number_hook as _)?;
+
+    let handle = guard.handle(return_number as _).unwrap();
+    let (target, slot) = handle.as_raw();
+
+    // The raw state points at the same storage the typed original lives in.
+    assert_eq!(target, return_number as _);
+    assert_eq!(slot as usize, original as *const FunctionType as usize);
+    assert_eq!(unsafe { HookHandle::from_raw(target, slot) }, handle);
+
+    let original: FunctionType = unsafe { std::mem::transmute(handle.original()) };
+    assert_eq!(original(), 42);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn error_context() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    // The type of the hooked function, and of the detour.
+    type FunctionType = fn() -> u32;
+
+    fn return_number() -> u32 {
+        42
+    }
+
+    fn return_number_hook() -> u32 {
+        1337
+    }
+
+    let _ =
+        guard.create_and_enable_hook::<FunctionType>(return_number as _, return_number_hook as _)?;
+
+    // Enabling twice fails, and the error says where.
+    let error = guard.enable_hook(return_number as _).unwrap_err();
+
+    assert!(matches!(error.root(), Error::Enabled));
+
+    let context = error.context().unwrap();
+    assert_eq!(context.operation, Some(Operation::EnableHook));
+    assert_eq!(context.target, Some(return_number as usize));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn observe_lifecycle() -> Result<()> {
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let recorded = events.clone();
+    let mut guard = DetourGuard::with_observer(move |event: &HookEvent| {
+        let name = match event {
+            HookEvent::EngineInitialized => "initialized",
+            HookEvent::EngineUninitialized => "uninitialized",
+            HookEvent::Created { .. } => "created",
+            HookEvent::Enabled { .. } => "enabled",
+            HookEvent::Disabled { .. } => "disabled",
+            HookEvent::Removed { .. } => "removed",
+            HookEvent::Failed { .. } => "failed",
+            HookEvent::DroppedWithLiveHooks { .. } => "dropped",
+        };
+        recorded.lock().unwrap().push(name);
+    })?;
+
+    // The type of the hooked function, and of the detour.
+    type FunctionType = fn() -> u32;
+
+    fn return_number() -> u32 {
+        42
+    }
+
+    fn return_number_hook() -> u32 {
+        1337
+    }
+
+    let _ =
+        guard.create_and_enable_hook::<FunctionType>(return_number as _, return_number_hook as _)?;
+
+    // Enabling twice fails, which is reported as well.
+    assert!(guard.enable_hook(return_number as _).is_err());
+
+    guard.disable_hook(return_number as _)?;
+    drop(guard);
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            "initialized",
+            "created",
+            "enabled",
+            "failed",
+            "disabled",
+            "dropped",
+            "removed",
+            "uninitialized"
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn thread_freeze_method_conversion() {
+    for method in [
+        ThreadFreezeMethod::Original,
+        ThreadFreezeMethod::Fast,
+        ThreadFreezeMethod::None,
+    ] {
+        let raw: MH_THREAD_FREEZE_METHOD = method.into();
+        assert_eq!(ThreadFreezeMethod::from(raw), method);
+    }
+}
+
+#[test]
+#[serial]
+fn attach_to_engine() -> Result<()> {
+    // Another component owns the engine.
+    assert_eq!(unsafe { MH_Initialize() }, MH_OK);
+
+    // The type of the hooked function, and of the detour.
+    type FunctionType = fn() -> u32;
+
+    fn return_number() -> u32 {
+        42
+    }
+
+    fn return_number_hook() -> u32 {
+        1337
+    }
+
+    {
+        let mut guard = DetourGuard::new_or_attach()?;
+        assert!(!guard.owns_engine());
+
+        let _ = guard
+            .create_and_enable_hook::<FunctionType>(return_number as _, return_number_hook as _)?;
+        assert_eq!(return_number(), 1337);
+    }
+
+    // The attached guard only removed its own hook.
+    assert_eq!(return_number(), 42);
+    assert_eq!(unsafe { MH_Uninitialize() }, MH_OK);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn rebind_detour() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    // The type of the hooked function, and of the detours.
+    type FunctionType = fn() -> u32;
+
+    fn return_number() -> u32 {
+        42
+    }
+
+    fn verbose_hook() -> u32 {
+        1
+    }
+
+    fn fast_hook() -> u32 {
+        2
+    }
+
+    let _ = guard.create_rebindable_hook::<FunctionType>(return_number as _, verbose_hook as _)?;
+    guard.enable_hook(return_number as _)?;
+    assert_eq!(return_number(), 1);
+
+    // Swapping the detour doesn't go through the engine.
+    guard.rebind(return_number as _, fast_hook as _)?;
+    assert_eq!(return_number(), 2);
+    assert_eq!(
+        guard.hook_info(return_number as _).unwrap().detour(),
+        fast_hook as _
+    );
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn rehook_module() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    extern "system" fn fake_process_id() -> u32 {
+        1234
+    }
+
+    let target = Target::export("kernel32.dll", "GetCurrentProcessId");
+    let (_, original) =
+        guard.create_hook_at::<extern "system" fn() -> u32>(&target, fake_process_id as _)?;
+    let real = original();
+    guard.enable_hook(target.resolve()?)?;
+    assert_eq!(std::process::id(), 1234);
+
+    // The module is matched case-insensitively, and the hook stays enabled.
+    assert_eq!(guard.rehook_module("KERNEL32.DLL")?, 1);
+    assert_eq!(std::process::id(), 1234);
+    assert_eq!(original(), real);
+
+    // Hooks of other modules are left alone.
+    assert_eq!(guard.rehook_module("user32.dll")?, 0);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn remove_on_unload() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    extern "system" fn fake_time() -> u32 {
+        0
+    }
+
+    // A module the tests don't load otherwise, so that freeing it unloads it.
+    let name = "winmm.dll\0".encode_utf16().collect::<Vec<_>>();
+    let module = unsafe { LoadLibraryW(name.as_ptr()) };
+    assert!(!module.is_null());
+
+    let spec = Target::export("winmm.dll", "timeGetTime");
+    let (target, _) = guard.create_hook_at::<extern "system" fn() -> u32>(&spec, fake_time as _)?;
+    guard.enable_hook(target)?;
+
+    unsafe { FreeLibrary(module) };
+
+    // Enabling every hook doesn't touch the unloaded module, as its hook is gone.
+    guard.enable_all_hooks()?;
+    assert!(guard.hook_info(target).is_none());
+
+    // Loading the module again brings the hook back, enabled.
+    let module = unsafe { LoadLibraryW(name.as_ptr()) };
+    assert_eq!(guard.rehook_module("winmm.dll")?, 1);
+
+    let target = spec.resolve()?;
+    assert!(guard.hook_info(target).unwrap().is_enabled());
+
+    drop(guard);
+    unsafe { FreeLibrary(module) };
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn switch_hook() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    type FunctionType = fn() -> u32;
+
+    fn return_number() -> u32 {
+        42
+    }
+
+    fn hook() -> u32 {
+        1
+    }
+
+    let _ = guard.create_rebindable_hook::<FunctionType>(return_number as _, hook as _)?;
+    guard.enable_hook(return_number as _)?;
+
+    let switch = guard.switch(return_number as _)?;
+    assert!(switch.is_on());
+
+    // Switches are toggled from any thread, while the hook stays enabled.
+    std::thread::scope(|scope| {
+        scope.spawn(|| switch.off()).join().unwrap();
+    });
+    assert!(!switch.is_on());
+    assert_eq!(return_number(), 42);
+    assert!(guard.hook_info(return_number as _).unwrap().is_enabled());
+
+    switch.on();
+    assert_eq!(return_number(), 1);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn hold_loader_lock() -> Result<()> {
+    type LdrLockLoaderLock = unsafe extern "system" fn(u32, *mut u32, *mut usize) -> i32;
+    type LdrUnlockLoaderLock = unsafe extern "system" fn(u32, usize) -> i32;
+
+    let lock = Target::export("ntdll.dll", "LdrLockLoaderLock").resolve()?;
+    let lock: LdrLockLoaderLock = unsafe { std::mem::transmute(lock) };
+    let unlock = Target::export("ntdll.dll", "LdrUnlockLoaderLock").resolve()?;
+    let unlock: LdrUnlockLoaderLock = unsafe { std::mem::transmute(unlock) };
+
+    let mut guard = DetourGuard::new()?;
+
+    // The type of the hooked function, and of the detour.
+    type FunctionType = fn() -> u32;
+
+    fn return_number() -> u32 {
+        42
+    }
+
+    fn return_number_hook() -> u32 {
+        1337
+    }
+
+    let _ = guard.create_hook::<FunctionType>(return_number as _, return_number_hook as _)?;
+    assert!(!DetourGuard::is_loader_lock_held());
+
+    let mut cookie = 0;
+    unsafe { lock(0, std::ptr::null_mut(), &mut cookie) };
+    assert!(DetourGuard::is_loader_lock_held());
+
+    // No thread could run meanwhile, so the hook is enabled in place.
+    let enabled = Arc::new(Mutex::new(None));
+    let result = enabled.clone();
+    guard.enable_hook_async(return_number as _, move |enabled| {
+        *result.lock().unwrap() = Some(enabled.is_ok());
+    })?;
+    assert_eq!(*enabled.lock().unwrap(), Some(true));
+
+    unsafe { unlock(0, cookie) };
+    assert!(!DetourGuard::is_loader_lock_held());
+    assert_eq!(return_number(), 1337);
+
+    Ok(())
+}
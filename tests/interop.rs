@@ -0,0 +1,35 @@
+#![cfg(feature = "windows-sys")]
+
+use minhook_detours_rs::{
+    error::Result,
+    interop::{FARPROC, farproc_to_ptr, owning_module, pcwstr_to_string},
+    target::Target,
+};
+use winapi::um::libloaderapi::{GetModuleHandleW, GetProcAddress};
+
+#[test]
+fn convert_windows_sys_types() -> Result<()> {
+    let module_name = "kernel32.dll\0".encode_utf16().collect::<Vec<_>>();
+    assert_eq!(
+        unsafe { pcwstr_to_string(module_name.as_ptr()) },
+        "kernel32.dll"
+    );
+
+    let (module, proc) = unsafe {
+        let module = GetModuleHandleW(module_name.as_ptr());
+        let proc: FARPROC =
+            std::mem::transmute(GetProcAddress(module, c"GetCurrentProcessId".as_ptr()));
+        (module, proc)
+    };
+
+    // `None` maps to null, and everything else to the function's address.
+    assert!(farproc_to_ptr(None).is_null());
+    assert_eq!(
+        Target::from(proc),
+        Target::Address(farproc_to_ptr(proc))
+    );
+
+    assert_eq!(owning_module(farproc_to_ptr(proc))?, module as _);
+
+    Ok(())
+}
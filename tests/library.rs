@@ -0,0 +1,40 @@
+#![cfg(feature = "libloading")]
+
+use libloading::Library;
+use minhook_detours_rs::{
+    error::{Error, Result},
+    guard::DetourGuard,
+    static_detour,
+};
+use serial_test::serial;
+use std::os::raw::c_void;
+
+static_detour! {
+    static TIME_GET_TIME: unsafe extern "system" fn() -> u32 = || 42;
+}
+
+#[test]
+#[serial]
+fn hook_library_symbol() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+    let library = unsafe { Library::new("winmm.dll") }.unwrap();
+
+    let handle = TIME_GET_TIME.create_in_library(&mut guard, &library, "timeGetTime")?;
+    guard.enable_hook(handle.target())?;
+
+    // Calls through the library's own symbols are detoured too.
+    let time_get_time =
+        unsafe { library.get::<unsafe extern "system" fn() -> u32>(b"timeGetTime") }.unwrap();
+    assert_eq!(unsafe { time_get_time() }, 42);
+
+    guard.remove_hook(handle.target())?;
+
+    // The symbol is named in the error.
+    let error = guard
+        .create_hook_in_library::<*mut c_void>(&library, "timeGetTimeEx", 0x1000 as _)
+        .unwrap_err();
+    assert!(matches!(error.root(), Error::InvalidExport));
+    assert_eq!(error.context().unwrap().symbol.as_deref(), Some("timeGetTimeEx"));
+
+    Ok(())
+}
@@ -0,0 +1,184 @@
+#![cfg(feature = "manifest")]
+
+use minhook_detours_rs::{
+    error::{Error, Result},
+    manifest::{HookManifest, Location},
+    target::Target,
+};
+#[cfg(feature = "testing")]
+use minhook_detours_rs::{guard::DetourGuard, manifest::DetourRegistry, testing::MockEngine};
+#[cfg(feature = "testing")]
+use std::{
+    os::raw::c_void,
+    ptr::null_mut,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+#[cfg(feature = "testing")]
+use winapi::um::libloaderapi::GetModuleHandleW;
+
+#[test]
+fn parse_manifest() -> Result<()> {
+    let manifest: HookManifest = r#"
+        [[hook]]
+        name = "create_file"
+        module = "kernel32.dll"
+        symbol = "CreateFileW"
+        offset = 0x40
+        detour = "create_file_hook"
+        group = "io"
+
+        [[hook]]
+        name = "tick"
+        module = "game.exe"
+        rva = 0x1234
+        detour = "tick_hook"
+        enabled = false
+    "#
+    .parse()?;
+
+    let [create_file, tick] = &manifest.hooks[..] else {
+        panic!("Expected two hooks, got {}", manifest.hooks.len());
+    };
+
+    // Entries are enabled by default.
+    assert!(create_file.enabled);
+    assert_eq!(create_file.group.as_deref(), Some("io"));
+    assert_eq!(
+        create_file.target()?,
+        Target::export("kernel32.dll", "CreateFileW").offset(0x40)
+    );
+
+    assert!(!tick.enabled);
+    assert_eq!(tick.location, Location::Rva(0x1234));
+
+    // Entries without a location are rejected.
+    assert!(matches!(
+        r#"
+            [[hook]]
+            name = "broken"
+            module = "kernel32.dll"
+            detour = "broken_hook"
+        "#
+        .parse::<HookManifest>(),
+        Err(Error::InvalidManifest(_))
+    ));
+
+    Ok(())
+}
+
+#[cfg(feature = "testing")]
+const DETOUR: *mut c_void = 0x2000 as _;
+
+#[cfg(feature = "testing")]
+static PROCESS_ORIGINAL: AtomicPtr<c_void> = AtomicPtr::new(null_mut());
+#[cfg(feature = "testing")]
+static FILE_ORIGINAL: AtomicPtr<c_void> = AtomicPtr::new(null_mut());
+
+#[cfg(feature = "testing")]
+#[test]
+fn load_manifest() -> Result<()> {
+    // Build an RVA, and a pattern out of exports implemented by `kernelbase.dll` itself.
+    let create_file_w = Target::export("kernelbase.dll", "CreateFileW").resolve()?;
+    let create_file_a = Target::export("kernelbase.dll", "CreateFileA").resolve()?;
+
+    let base = unsafe {
+        GetModuleHandleW(
+            "kernelbase.dll\0"
+                .encode_utf16()
+                .collect::<Vec<_>>()
+                .as_ptr(),
+        )
+    };
+    let rva = create_file_w.addr() - base.addr();
+
+    let bytes = unsafe { std::slice::from_raw_parts(create_file_a as *const u8, 32) };
+    let pattern = bytes
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let manifest: HookManifest = format!(
+        r#"
+            [[hook]]
+            name = "process_id"
+            module = "kernel32.dll"
+            symbol = "GetCurrentProcessId"
+            detour = "process_hook"
+
+            [[hook]]
+            name = "create_file_w"
+            module = "kernelbase.dll"
+            rva = {rva:#x}
+            detour = "file_hook"
+            group = "io"
+            enabled = false
+
+            [[hook]]
+            name = "create_file_a"
+            module = "kernelbase.dll"
+            pattern = "{pattern}"
+            detour = "file_hook"
+            group = "io"
+        "#
+    )
+    .parse()?;
+
+    let mut detours = DetourRegistry::new();
+    detours
+        .register("process_hook", DETOUR, &PROCESS_ORIGINAL)
+        .register("file_hook", DETOUR, &FILE_ORIGINAL);
+
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let targets = guard.load_manifest(&manifest, &detours)?;
+    assert_eq!(
+        targets,
+        [
+            Target::export("kernel32.dll", "GetCurrentProcessId").resolve()?,
+            create_file_w,
+            create_file_a,
+        ]
+    );
+
+    let process_id = guard.hook_info(targets[0]).unwrap();
+    assert!(process_id.is_enabled());
+    assert_eq!(process_id.name(), Some("process_id"));
+    assert_eq!(process_id.group(), None);
+
+    let create_file_w = guard.hook_info(targets[1]).unwrap();
+    assert!(!create_file_w.is_enabled());
+    assert_eq!(create_file_w.group(), Some("io"));
+
+    let create_file_a = guard.hook_info(targets[2]).unwrap();
+    assert!(create_file_a.is_enabled());
+    assert_eq!(create_file_a.name(), Some("create_file_a"));
+    assert_eq!(create_file_a.group(), Some("io"));
+
+    // The original of every detour is stored where it was registered, the mock behaving as its own original.
+    assert_eq!(PROCESS_ORIGINAL.load(Ordering::Acquire), targets[0]);
+    assert_eq!(FILE_ORIGINAL.load(Ordering::Acquire), targets[2]);
+
+    // Entries referring to a detour that isn't registered are rejected, before anything is hooked.
+    let unknown: HookManifest = r#"
+        [[hook]]
+        name = "thread_id"
+        module = "kernel32.dll"
+        symbol = "GetCurrentThreadId"
+        detour = "thread_hook"
+    "#
+    .parse()?;
+
+    assert!(matches!(
+        guard.load_manifest(&unknown, &detours),
+        Err(Error::UnknownDetour(detour)) if detour == "thread_hook"
+    ));
+    assert!(
+        guard
+            .hook_info(Target::export("kernel32.dll", "GetCurrentThreadId").resolve()?)
+            .is_none()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use minhook_detours_rs::{
+    error::{Error, Result},
+    mem::{self, Patch, ProtectGuard},
+};
+use std::{
+    mem::{size_of, zeroed},
+    os::raw::c_void,
+    ptr::null_mut,
+};
+use winapi::um::{
+    memoryapi::{VirtualAlloc, VirtualFree, VirtualProtect, VirtualQuery},
+    winnt::{
+        MEM_COMMIT, MEM_RELEASE, MEM_RESERVE, MEMORY_BASIC_INFORMATION, PAGE_READONLY,
+        PAGE_READWRITE,
+    },
+};
+
+/// Allocate a read-only page, starting with `bytes`.
+fn read_only_page(bytes: &[u8]) -> *mut c_void {
+    unsafe {
+        let page = VirtualAlloc(null_mut(), 0x1000, MEM_COMMIT | MEM_RESERVE, PAGE_READWRITE);
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), page as *mut u8, bytes.len());
+
+        let mut protection = 0;
+        VirtualProtect(page, 0x1000, PAGE_READONLY, &mut protection);
+        page
+    }
+}
+
+fn protection_of(address: *mut c_void) -> u32 {
+    let mut region: MEMORY_BASIC_INFORMATION = unsafe { zeroed() };
+    unsafe { VirtualQuery(address, &mut region, size_of::<MEMORY_BASIC_INFORMATION>()) };
+    region.Protect
+}
+
+#[test]
+fn protect_guard() -> Result<()> {
+    let page = read_only_page(&[0xAA]);
+
+    {
+        let writable = unsafe { ProtectGuard::make_writable(page, 1)? };
+        assert_eq!(writable.previous(), PAGE_READONLY);
+        assert_eq!(protection_of(page), PAGE_READWRITE);
+
+        unsafe { *(page as *mut u8) = 0xBB };
+    }
+
+    // The protection is restored once dropped.
+    assert_eq!(protection_of(page), PAGE_READONLY);
+    assert_eq!(unsafe { mem::read_bytes(page, 1)? }, [0xBB]);
+
+    unsafe { VirtualFree(page, 0, MEM_RELEASE) };
+    Ok(())
+}
+
+#[test]
+fn protect_guard_regions() -> Result<()> {
+    // A read-only page, followed by a read-write one.
+    let pages =
+        unsafe { VirtualAlloc(null_mut(), 0x2000, MEM_COMMIT | MEM_RESERVE, PAGE_READWRITE) };
+    let mut protection = 0;
+    unsafe { VirtualProtect(pages, 0x1000, PAGE_READONLY, &mut protection) };
+    let second = pages.wrapping_add(0x1000);
+
+    {
+        let writable = unsafe { ProtectGuard::make_writable(pages.wrapping_add(0xFFE), 4)? };
+        assert_eq!(writable.regions().len(), 2);
+        assert_eq!(protection_of(pages), PAGE_READWRITE);
+    }
+
+    // Each region gets its own protection back.
+    assert_eq!(protection_of(pages), PAGE_READONLY);
+    assert_eq!(protection_of(second), PAGE_READWRITE);
+
+    unsafe { VirtualFree(pages, 0, MEM_RELEASE) };
+
+    // Ranges wrapping around the address space are refused, rather than overflowing.
+    assert!(!mem::is_accessible(usize::MAX as *const c_void, 2));
+
+    Ok(())
+}
+
+#[test]
+fn read_and_write() -> Result<()> {
+    let page = read_only_page(&[1, 0, 0, 0]);
+
+    assert_eq!(unsafe { mem::read::<u32>(page)? }, 1);
+
+    unsafe { mem::write(page, 0xDEADBEEFu32)? };
+    assert_eq!(unsafe { mem::read::<u32>(page)? }, 0xDEADBEEF);
+
+    unsafe { mem::write_bytes(page.wrapping_add(1), &[0x11, 0x22])? };
+    assert_eq!(
+        unsafe { mem::read_bytes(page, 4)? },
+        [0xEF, 0x11, 0x22, 0xDE]
+    );
+    assert_eq!(protection_of(page), PAGE_READONLY);
+
+    unsafe { VirtualFree(page, 0, MEM_RELEASE) };
+
+    // Memory that isn't there anymore is refused, rather than faulting.
+    assert!(!mem::is_accessible(page, 1));
+    assert!(matches!(
+        unsafe { mem::read_bytes(page, 1) },
+        Err(Error::InvalidTarget)
+    ));
+    assert!(matches!(
+        unsafe { mem::write(page, 0u32) },
+        Err(Error::InvalidTarget)
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn encode_patches() -> Result<()> {
+    // The patches are only inspected, never executed.
+    let page = read_only_page(&[0xCC; 16]);
+    let near = page.wrapping_add(0x100);
+
+    let nop = unsafe { Patch::nop(page, 3)? };
+    assert_eq!(
+        unsafe { mem::read_bytes(page, 4)? },
+        [0x90, 0x90, 0x90, 0xCC]
+    );
+    drop(nop);
+
+    let ret = unsafe { Patch::ret(page)? };
+    assert_eq!(ret.bytes(), [0xC3]);
+    assert_eq!(ret.original(), [0xCC]);
+    drop(ret);
+
+    // Near destinations are reached relatively, far ones absolutely.
+    let jmp = unsafe { Patch::jmp(page, near)? };
+    assert_eq!(jmp.bytes(), [0xE9, 0xFB, 0x00, 0x00, 0x00]);
+    drop(jmp);
+
+    let far = page.wrapping_add(0x1_0000_0000) as *const c_void;
+    let jmp = unsafe { Patch::jmp(page, far)? };
+    assert_eq!(jmp.bytes()[..6], [0xFF, 0x25, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(jmp.bytes()[6..], (far as u64).to_le_bytes());
+    drop(jmp);
+
+    assert_eq!(unsafe { mem::read_bytes(page, 16)? }, [0xCC; 16]);
+
+    unsafe { VirtualFree(page, 0, MEM_RELEASE) };
+    Ok(())
+}
+
+#[test]
+fn alloc_near() -> Result<()> {
+    let address = alloc_near as *const c_void;
+
+    let allocation = mem::alloc_near(address, 0x100)?;
+    assert!((allocation as isize).abs_diff(address as isize) < 0x8000_0000);
+
+    unsafe { VirtualFree(allocation, 0, MEM_RELEASE) };
+    Ok(())
+}
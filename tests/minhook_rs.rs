@@ -0,0 +1,38 @@
+#![cfg(feature = "minhook-rs")]
+
+use minhook_detours_rs::{compat::minhook_rs::MinHook, error::Result};
+use serial_test::serial;
+
+#[test]
+#[serial]
+fn minhook_shim() -> Result<()> {
+    // The type of the hooked function, and of the detour.
+    type FunctionType = fn(i32, i32) -> i64;
+
+    fn add_two(x: i32, y: i32) -> i64 {
+        (x + y) as i64
+    }
+
+    fn add_two_hook(x: i32, y: i32) -> i64 {
+        (x - y) as i64
+    }
+
+    let original = unsafe { MinHook::create_hook(add_two as _, add_two_hook as _)? };
+    let original: FunctionType = unsafe { std::mem::transmute(original) };
+
+    unsafe { MinHook::enable_hook(add_two as _)? };
+    assert_eq!(add_two(2, 2), 0);
+    assert_eq!(original(2, 2), 4);
+
+    // Queued operations take effect together.
+    unsafe { MinHook::queue_disable_hook(add_two as _)? };
+    assert_eq!(add_two(2, 2), 0);
+    unsafe { MinHook::apply_queued()? };
+    assert_eq!(add_two(2, 2), 4);
+
+    // The process-wide guard can be closed, and is created again upon next use.
+    MinHook::uninitialize()?;
+    MinHook::uninitialize()?;
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+#![cfg(all(feature = "monitor", any(target_arch = "x86", target_arch = "x86_64")))]
+
+use minhook_detours_rs::{error::Result, guard::DetourGuard};
+use serial_test::serial;
+use winapi::um::libloaderapi::{GetModuleHandleW, GetProcAddress};
+
+#[test]
+#[serial]
+fn trace_kernel32() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    let trace = guard.trace_module("kernel32.dll", &["GetCurrentThreadId"])?;
+    assert!(trace.export("GetCurrentThreadId").is_none());
+
+    let export = trace.export("GetCurrentProcessId").unwrap();
+    let before = export.call_count();
+
+    // The call is counted, and forwarded untouched.
+    type FunctionType = unsafe extern "system" fn() -> u32;
+    let get_current_process_id: FunctionType = unsafe {
+        let module = GetModuleHandleW(
+            "kernel32.dll\0"
+                .encode_utf16()
+                .collect::<Vec<_>>()
+                .as_ptr(),
+        );
+        std::mem::transmute(GetProcAddress(module, c"GetCurrentProcessId".as_ptr()))
+    };
+    assert_eq!(unsafe { get_current_process_id() }, std::process::id());
+    assert!(export.call_count() > before);
+
+    guard.disable_hooks(&trace.targets())?;
+
+    Ok(())
+}
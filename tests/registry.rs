@@ -0,0 +1,42 @@
+#![cfg(all(feature = "registry", feature = "testing"))]
+
+use minhook_detours_rs::{
+    error::Result,
+    guard::DetourGuard,
+    registry::{HOOKS, RegisteredHook, distributed_slice},
+    testing::MockEngine,
+};
+use std::os::raw::c_void;
+
+const TARGET: *mut c_void = 0x1000 as _;
+const SECOND_TARGET: *mut c_void = 0x3000 as _;
+const DETOUR: *mut c_void = 0x2000 as _;
+
+#[distributed_slice(HOOKS)]
+#[linkme(crate = minhook_detours_rs::registry::linkme)]
+static FIRST: RegisteredHook = RegisteredHook {
+    name: "first",
+    register: |guard| guard.create_and_enable_hook::<*mut c_void>(TARGET, DETOUR).map(|_| ()),
+};
+
+#[distributed_slice(HOOKS)]
+#[linkme(crate = minhook_detours_rs::registry::linkme)]
+static SECOND: RegisteredHook = RegisteredHook {
+    name: "second",
+    register: |guard| guard.create_hook::<*mut c_void>(SECOND_TARGET, DETOUR).map(|_| ()),
+};
+
+#[test]
+fn install_registered_hooks() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    guard.install_registered_hooks()?;
+
+    // Both declarations were collected, wherever they were declared.
+    assert_eq!(HOOKS.len(), 2);
+    assert!(guard.hook_info(TARGET).unwrap().is_enabled());
+    assert!(!guard.hook_info(SECOND_TARGET).unwrap().is_enabled());
+
+    Ok(())
+}
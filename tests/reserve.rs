@@ -0,0 +1,81 @@
+use minhook_detours_rs::{error::Result, guard::DetourGuard};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    os::raw::c_void,
+};
+
+/// Counts the allocations made by the current thread, while it's counting.
+struct CountingAllocator;
+
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn record() {
+    if COUNTING.try_with(Cell::get).unwrap_or(false) {
+        let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record();
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record();
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Runs `f`, returning what it returns, along with the number of allocations it made.
+fn count<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    ALLOCATIONS.set(0);
+    COUNTING.set(true);
+    let result = f();
+    COUNTING.set(false);
+
+    (result, ALLOCATIONS.get())
+}
+
+#[inline(never)]
+fn first_target() -> u32 {
+    std::hint::black_box(1)
+}
+
+#[inline(never)]
+fn second_target() -> u32 {
+    std::hint::black_box(2)
+}
+
+fn detour() -> u32 {
+    0
+}
+
+#[test]
+fn create_hooks_without_allocating() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+    guard.reserve(2);
+
+    let (created, allocations) = count(|| -> Result<()> {
+        guard.create_hook::<*mut c_void>(first_target as _, detour as _)?;
+        guard.create_hook::<*mut c_void>(second_target as _, detour as _)?;
+        Ok(())
+    });
+    created?;
+
+    // The engine allocates from a heap of its own, which isn't counted.
+    assert_eq!(allocations, 0);
+
+    Ok(())
+}
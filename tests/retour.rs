@@ -0,0 +1,59 @@
+#![cfg(feature = "retour")]
+
+use minhook_detours_rs::{compat::retour::GenericDetour, error::Result, guard::DetourGuard};
+use serial_test::serial;
+
+#[test]
+#[serial]
+fn generic_detour() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    fn add_two(x: i32, y: i32) -> i64 {
+        (x + y) as i64
+    }
+
+    fn add_two_hook(x: i32, y: i32) -> i64 {
+        (x - y) as i64
+    }
+
+    type FunctionType = fn(i32, i32) -> i64;
+
+    // Function items are coerced to the function pointer types `retour` implements its traits for.
+    let detour = unsafe {
+        GenericDetour::<FunctionType>::new(&mut guard, add_two, add_two_hook as FunctionType)?
+    };
+
+    unsafe { detour.enable(&mut guard)? };
+    assert!(detour.is_enabled(&guard));
+    assert_eq!(add_two(2, 2), 0);
+    assert_eq!(detour.trampoline(&guard)?(2, 2), 4);
+
+    unsafe { detour.disable(&mut guard)? };
+    assert_eq!(add_two(2, 2), 4);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn hook_function() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    extern "C" fn multiply(x: i32, y: i32) -> i32 {
+        x * y
+    }
+
+    extern "C" fn multiply_hook(x: i32, y: i32) -> i32 {
+        x + y
+    }
+
+    type FunctionType = extern "C" fn(i32, i32) -> i32;
+
+    let original =
+        unsafe { guard.hook_function(multiply as FunctionType, multiply_hook as FunctionType)? };
+
+    assert_eq!(multiply(3, 4), 7);
+    assert_eq!(original(3, 4), 12);
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use minhook_detours_rs::{
+    ring::{LogRing, MESSAGE_CAPACITY},
+    ring_log,
+};
+use std::{panic::AssertUnwindSafe, sync::Arc, thread};
+
+#[test]
+fn push_and_drain() {
+    static LOG: LogRing<4> = LogRing::new();
+
+    assert!(LOG.push("first"));
+    assert!(ring_log!(LOG, "second: {}", 2));
+
+    let mut messages = Vec::new();
+    assert_eq!(LOG.drain(|message| messages.push(message.to_owned())), 2);
+    assert_eq!(messages, ["first", "second: 2"]);
+
+    // A full ring drops, rather than waits, and frees up once drained.
+    for _ in 0..4 {
+        assert!(LOG.push("filler"));
+    }
+    assert!(!LOG.push("dropped"));
+    assert_eq!(LOG.dropped(), 1);
+    assert_eq!(LOG.drain(|_| {}), 4);
+    assert!(LOG.push("wrapped around"));
+}
+
+#[test]
+fn drain_past_panicking_sink() {
+    let log = LogRing::<1>::new();
+    assert!(log.push("first"));
+
+    let drain = AssertUnwindSafe(|| log.drain(|_| panic!("sink failed")));
+    let panicked = std::panic::catch_unwind(drain);
+    assert!(panicked.is_err());
+
+    // The message was consumed, and its slot freed for the next one.
+    assert!(log.push("second"));
+    let mut messages = Vec::new();
+    assert_eq!(log.drain(|message| messages.push(message.to_owned())), 1);
+    assert_eq!(messages, ["second"]);
+}
+
+#[test]
+fn truncate_long_messages() {
+    let log = LogRing::<1>::new();
+
+    // Multi-byte characters straddling the capacity are left out whole.
+    let message = "é".repeat(MESSAGE_CAPACITY);
+    assert!(ring_log!(log, "{message}"));
+
+    log.drain(|message| {
+        assert_eq!(message.len(), MESSAGE_CAPACITY);
+        assert!(message.chars().all(|character| character == 'é'));
+    });
+}
+
+#[test]
+fn push_concurrently() {
+    let log = Arc::new(LogRing::<1024>::new());
+
+    let threads = (0..4)
+        .map(|thread| {
+            let log = log.clone();
+            thread::spawn(move || {
+                for index in 0..100 {
+                    ring_log!(log, "{thread}:{index}");
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    // Messages of a single thread keep their order.
+    let mut last = [None; 4];
+    let drained = log.drain(|message| {
+        let (thread, index) = message.split_once(':').unwrap();
+        let (thread, index) = (thread.parse::<usize>().unwrap(), index.parse::<u32>().unwrap());
+        assert!(last[thread] < Some(index));
+        last[thread] = Some(index);
+    });
+    assert_eq!(drained, 400);
+    assert_eq!(log.dropped(), 0);
+}
@@ -0,0 +1,74 @@
+use minhook_detours_rs::{
+    error::{Error, Result},
+    scan::{INSTRUCTION_ALIGNMENT, Pattern, scan_module},
+};
+use winapi::um::libloaderapi::{GetModuleHandleW, GetProcAddress};
+
+#[test]
+fn parse_pattern() {
+    let pattern = Pattern::new("48 8B ?? ? 57").unwrap();
+
+    // Wildcards should match any byte, while the rest should match exactly.
+    assert!(pattern.matches(&[0x48, 0x8B, 0x00, 0xFF, 0x57]));
+    assert!(!pattern.matches(&[0x48, 0x8B, 0x00, 0xFF, 0x58]));
+
+    // Shorter input can never match.
+    assert!(!pattern.matches(&[0x48, 0x8B]));
+
+    // Malformed patterns are rejected.
+    assert!(matches!(Pattern::new(""), Err(Error::InvalidPattern)));
+    assert!(matches!(Pattern::new("48 8"), Err(Error::InvalidPattern)));
+    assert!(matches!(Pattern::new("48 ZZ"), Err(Error::InvalidPattern)));
+}
+
+#[test]
+fn find_in_slice() -> Result<()> {
+    let pattern: Pattern = "AA ?? CC".parse()?;
+
+    let haystack = [0x00, 0xAA, 0xBB, 0xCC, 0xAA, 0x00, 0xCC];
+    let offsets = pattern.find_in(&haystack).collect::<Vec<_>>();
+
+    assert_eq!(offsets, [1, 4]);
+
+    Ok(())
+}
+
+#[test]
+fn scan_kernel32() -> Result<()> {
+    // Resolve a well-known export, and build a pattern out of its first bytes.
+    let target = unsafe {
+        let module = GetModuleHandleW(
+            "kernel32.dll\0"
+                .encode_utf16()
+                .collect::<Vec<_>>()
+                .as_ptr(),
+        );
+        GetProcAddress(module, c"GetCurrentProcessId".as_ptr()) as *const u8
+    };
+
+    let bytes = unsafe { std::slice::from_raw_parts(target, 8) };
+    let pattern = bytes
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    // The export itself must be one of the candidates.
+    let candidates = scan_module("kernel32.dll", &Pattern::new(&pattern)?)?;
+    assert!(candidates.contains(&(target as *mut _)));
+
+    // Matches are only reported where instructions may start.
+    assert!(
+        candidates
+            .iter()
+            .all(|candidate| candidate.addr() % INSTRUCTION_ALIGNMENT == 0)
+    );
+
+    // Unloaded modules are reported as such.
+    assert!(matches!(
+        scan_module("not-a-module.dll", &Pattern::new(&pattern)?),
+        Err(Error::InvalidModule)
+    ));
+
+    Ok(())
+}
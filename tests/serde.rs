@@ -0,0 +1,85 @@
+#![cfg(feature = "serde")]
+
+use minhook_detours_rs::{
+    error::Result,
+    guard::{DetourGuard, HookInfo, ThreadFreezeMethod},
+};
+use serial_test::serial;
+
+#[test]
+#[serial]
+fn hook_info_roundtrip() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    // The type of the hooked function, and of the detour.
+    type FunctionType = fn() -> u32;
+
+    fn return_number() -> u32 {
+        42
+    }
+
+    fn return_number_hook() -> u32 {
+        1337
+    }
+
+    let _ =
+        guard.create_and_enable_hook::<FunctionType>(return_number as _, return_number_hook as _)?;
+    guard.set_hook_group(return_number as _, "numbers")?;
+
+    let snapshot = serde_json::to_value(guard.hooks().collect::<Vec<_>>()).unwrap();
+
+    // Addresses are serialized as plain integers.
+    assert_eq!(snapshot[0]["target"], return_number as usize);
+    assert_eq!(snapshot[0]["enabled"], true);
+    assert_eq!(snapshot[0]["group"], "numbers");
+
+    let restored: Vec<HookInfo> = serde_json::from_value(snapshot).unwrap();
+    assert_eq!(restored.iter().collect::<Vec<_>>(), guard.hooks().collect::<Vec<_>>());
+
+    Ok(())
+}
+
+#[test]
+fn thread_freeze_method_roundtrip() {
+    let serialized = serde_json::to_string(&ThreadFreezeMethod::None).unwrap();
+
+    assert_eq!(
+        serde_json::from_str::<ThreadFreezeMethod>(&serialized).unwrap(),
+        ThreadFreezeMethod::None
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+#[serial]
+fn dump_state() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    // The type of the hooked function, and of the detour.
+    type FunctionType = fn() -> u32;
+
+    fn return_number() -> u32 {
+        42
+    }
+
+    fn return_number_hook() -> u32 {
+        1337
+    }
+
+    let _ =
+        guard.create_rebindable_hook::<FunctionType>(return_number as _, return_number_hook as _)?;
+    guard.set_hook_group(return_number as _, "numbers")?;
+
+    let state: serde_json::Value = serde_json::from_str(&guard.dump_state()).unwrap();
+
+    // The fields of the hook sit next to the guard's bookkeeping of it.
+    assert_eq!(state["state"], "Initialized");
+    assert_eq!(state["thread_freeze_method"], "Original");
+    assert_eq!(state["hooks"][0]["target"], return_number as usize);
+    assert_eq!(state["hooks"][0]["group"], "numbers");
+    assert_eq!(state["hooks"][0]["enabled"], false);
+    assert_eq!(state["hooks"][0]["rebindable"], true);
+    assert_eq!(state["hooks"][0]["switched_on"], true);
+
+    Ok(())
+}
@@ -0,0 +1,27 @@
+#![cfg(feature = "symbol")]
+
+use minhook_detours_rs::symbol::symbolize;
+use std::ptr::null;
+use winapi::um::libloaderapi::{GetModuleHandleA, GetProcAddress};
+
+#[test]
+fn symbolize_exports() {
+    let ntdll = unsafe { GetModuleHandleA(c"ntdll.dll".as_ptr()) };
+    let close = unsafe { GetProcAddress(ntdll, c"NtClose".as_ptr()) } as *const u8;
+
+    let symbol = symbolize(close.cast()).unwrap();
+    assert!(symbol.module.eq_ignore_ascii_case("ntdll.dll"));
+    assert_eq!(symbol.offset, 0);
+
+    // Exports sharing an address, e.g. `ZwClose`, may be picked instead.
+    let name = symbol.symbol.as_deref().unwrap();
+    assert!(name.ends_with("Close"));
+
+    // Addresses past an export are relative to it.
+    let inside = symbolize(close.wrapping_add(2).cast()).unwrap();
+    assert_eq!(inside.offset, 2);
+    assert_eq!(inside.to_string(), format!("{}!{name}+0x2", inside.module));
+
+    // Addresses outside of any module can't be symbolized.
+    assert!(symbolize(null()).is_none());
+}
@@ -0,0 +1,44 @@
+#![cfg(feature = "hook-table")]
+
+use minhook_detours_rs::{
+    error::Result,
+    guard::DetourGuard,
+    table::{HOOK_FLAG_ENABLED, MINHOOK_DETOURS_HOOK_TABLE},
+};
+use serial_test::serial;
+use std::sync::atomic::Ordering;
+
+#[test]
+#[serial]
+fn mirror_registry() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    // The type of the hooked function, and of the detour.
+    type FunctionType = fn() -> u32;
+
+    fn return_number() -> u32 {
+        42
+    }
+
+    fn return_number_hook() -> u32 {
+        1337
+    }
+
+    let _ =
+        guard.create_and_enable_hook::<FunctionType>(return_number as _, return_number_hook as _)?;
+
+    let table = &MINHOOK_DETOURS_HOOK_TABLE;
+    assert_eq!(&table.magic, b"MHDHOOKS");
+
+    let [entry] = table.entries() else {
+        panic!("Expected a single entry, got {}", table.entries().len());
+    };
+    assert_eq!(entry.target.load(Ordering::Relaxed), return_number as usize as u64);
+    assert_eq!(entry.flags.load(Ordering::Relaxed), HOOK_FLAG_ENABLED);
+
+    // Closing the guard empties the table.
+    guard.close()?;
+    assert!(table.entries().is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use minhook_detours_rs::{
+    error::{Error, Result},
+    target::Target,
+};
+use winapi::um::libloaderapi::{GetModuleHandleW, GetProcAddress};
+
+#[test]
+fn parse_target() -> Result<()> {
+    assert_eq!(
+        "kernel32.dll!CreateFileW+0x40".parse::<Target>()?,
+        Target::export("kernel32.dll", "CreateFileW").offset(0x40)
+    );
+    assert_eq!(
+        "kernel32.dll!CreateFileW".parse::<Target>()?,
+        Target::export("kernel32.dll", "CreateFileW")
+    );
+
+    // Module, and symbol are mandatory.
+    assert!(matches!(
+        "CreateFileW+0x40".parse::<Target>(),
+        Err(Error::InvalidTargetSpec)
+    ));
+    assert!(matches!(
+        "kernel32.dll!+0x40".parse::<Target>(),
+        Err(Error::InvalidTargetSpec)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn resolve_export_with_offset() -> Result<()> {
+    let export = Target::export("kernel32.dll", "GetCurrentProcessId").resolve()?;
+    let inner = Target::export("kernel32.dll", "GetCurrentProcessId")
+        .offset(2)
+        .resolve()?;
+
+    assert_eq!(inner, export.wrapping_byte_add(2));
+
+    // Offsets escaping the module's code are rejected.
+    assert!(matches!(
+        Target::export("kernel32.dll", "GetCurrentProcessId")
+            .offset(0x1000_0000)
+            .resolve(),
+        Err(Error::TargetOutOfBounds)
+    ));
+
+    // So are offsets overflowing the address space, including once added up.
+    assert!(matches!(
+        Target::export("kernel32.dll", "GetCurrentProcessId")
+            .offset(usize::MAX)
+            .resolve(),
+        Err(Error::TargetOutOfBounds)
+    ));
+    assert!(matches!(
+        Target::export("kernel32.dll", "GetCurrentProcessId")
+            .offset(2)
+            .offset(usize::MAX)
+            .resolve(),
+        Err(Error::TargetOutOfBounds)
+    ));
+
+    // Unknown exports are reported as such.
+    assert!(matches!(
+        Target::export("kernel32.dll", "NotAnExport").resolve(),
+        Err(Error::InvalidExport)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn mangled_export() -> Result<()> {
+    let target = "game.dll!?TakeDamage@Player@@QEAAXH@Z+0x10".parse::<Target>()?;
+    assert_eq!(
+        target,
+        Target::export("game.dll", "?TakeDamage@Player@@QEAAXH@Z").offset(0x10)
+    );
+    assert_eq!(target.to_string(), "game.dll!?TakeDamage@Player@@QEAAXH@Z+0x10");
+
+    #[cfg(feature = "demangle")]
+    {
+        let demangled = target.demangled_symbol().unwrap();
+        assert!(demangled.contains("Player::TakeDamage(int)"));
+
+        assert_eq!(
+            Target::export("kernel32.dll", "CreateFileW").demangled_symbol(),
+            None
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn resolve_ordinal() -> Result<()> {
+    assert_eq!(
+        "ws2_32.dll!#0x10+4".parse::<Target>()?,
+        Target::ordinal("ws2_32.dll", 0x10).offset(4)
+    );
+    assert_eq!(Target::ordinal("ws2_32.dll", 16).to_string(), "ws2_32.dll!#16");
+    assert!(matches!(
+        "ws2_32.dll!#70000".parse::<Target>(),
+        Err(Error::InvalidTargetSpec)
+    ));
+
+    // The ordinal of an export resolves to the same address as its name.
+    let ordinal = unsafe {
+        let module = GetModuleHandleW(
+            "kernel32.dll\0"
+                .encode_utf16()
+                .collect::<Vec<_>>()
+                .as_ptr(),
+        );
+        let address = GetProcAddress(module, c"GetCurrentProcessId".as_ptr());
+        (1..=u16::MAX)
+            .find(|ordinal| GetProcAddress(module, *ordinal as usize as _) == address)
+            .unwrap()
+    };
+
+    assert_eq!(
+        Target::ordinal("kernel32.dll", ordinal).resolve()?,
+        Target::export("kernel32.dll", "GetCurrentProcessId").resolve()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn resolve_forwarder() -> Result<()> {
+    let heap_alloc = Target::export("kernel32.dll", "HeapAlloc");
+
+    // The export is reported as forwarded, and resolves to the implementation.
+    assert_eq!(
+        heap_alloc.forwarder()?.as_deref(),
+        Some("NTDLL.RtlAllocateHeap")
+    );
+    assert_eq!(
+        heap_alloc.resolve()?,
+        Target::export("ntdll.dll", "RtlAllocateHeap").resolve()?
+    );
+
+    assert_eq!(
+        Target::export("kernel32.dll", "GetCurrentProcessId").forwarder()?,
+        None
+    );
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+#![cfg(feature = "test-support")]
+
+use minhook_detours_rs::{
+    error::Result,
+    guard::DetourGuard,
+    test_support::{add_c, multiply_system, sum_six},
+};
+use serial_test::serial;
+
+#[test]
+#[serial]
+fn hook_targets() -> Result<()> {
+    let mut guard = DetourGuard::new()?;
+
+    extern "C" fn add_c_hook(x: i32, y: i32) -> i64 {
+        (x - y) as i64
+    }
+
+    extern "C" fn sum_six_hook(_: u64, _: u64, _: u64, _: u64, _: u64, _: u64) -> u64 {
+        0
+    }
+
+    let _ = guard.create_and_enable_hook::<extern "C" fn(i32, i32) -> i64>(
+        add_c as _,
+        add_c_hook as _,
+    )?;
+    let original = guard.create_and_enable_hook::<extern "C" fn(u64, u64, u64, u64, u64, u64) -> u64>(
+        sum_six as _,
+        sum_six_hook as _,
+    )?;
+
+    assert_eq!(add_c(2, 2), 0);
+    assert_eq!(sum_six(1, 2, 3, 4, 5, 6), 0);
+    assert_eq!(original(1, 2, 3, 4, 5, 6), 21);
+
+    // Targets are distinct, so hooking one leaves the rest alone.
+    assert_eq!(multiply_system(2, 3), 6);
+
+    Ok(())
+}
@@ -0,0 +1,870 @@
+#![cfg(feature = "testing")]
+
+use minhook_detours_rs::{
+    error::{Error, Operation, Result},
+    guard::{
+        CriticalPolicy, DetourGuard, GuardState, HookConfig, HookProvider, RetryPolicy,
+        ThreadFreezeMethod, ThreadFreezer,
+    },
+    target::Target,
+    testing::{EngineCall, MockEngine},
+};
+use minhook_detours_sys::{
+    MH_ERROR_MEMORY_ALLOC, MH_ERROR_UNABLE_TO_UNINITIALIZE, MH_ERROR_UNSUPPORTED_FUNCTION,
+};
+use std::{
+    os::raw::c_void,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+// Mocked guards never touch MinHook, so they don't need to be serialized.
+
+const TARGET: *mut c_void = 0x1000 as _;
+const DETOUR: *mut c_void = 0x2000 as _;
+const SECOND_TARGET: *mut c_void = 0x3000 as _;
+const THIRD_TARGET: *mut c_void = 0x4000 as _;
+
+#[test]
+fn record_operations() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    // Nothing is patched, so the original is the target itself.
+    let original = guard.create_and_enable_hook::<*mut c_void>(TARGET, DETOUR)?;
+    assert_eq!(*original, TARGET);
+    assert!(engine.is_enabled(TARGET));
+
+    // The engine keeps MinHook's bookkeeping.
+    assert!(matches!(
+        guard.enable_hook(TARGET).unwrap_err().root(),
+        Error::Enabled
+    ));
+
+    guard.close()?;
+    assert!(!engine.is_created(TARGET));
+
+    assert_eq!(
+        engine.calls(),
+        [
+            EngineCall::Initialize,
+            EngineCall::CreateHook {
+                target: TARGET,
+                detour: DETOUR
+            },
+            EngineCall::EnableHook { target: TARGET },
+            EngineCall::EnableHook { target: TARGET },
+            EngineCall::QueueDisableHook { target: TARGET },
+            EngineCall::ApplyQueued,
+            EngineCall::RemoveHook { target: TARGET },
+            EngineCall::Uninitialize,
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn apply_config() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let _ = guard.create_hook::<*mut c_void>(TARGET, DETOUR)?;
+    guard.set_hook_name(TARGET, "target")?;
+    engine.clear_calls();
+
+    // Configurations are applied as a single transaction.
+    guard.apply_config(HookConfig::new().enable("target"))?;
+
+    assert!(engine.is_enabled(TARGET));
+    assert_eq!(
+        engine.calls(),
+        [
+            EngineCall::QueueEnableHook { target: TARGET },
+            EngineCall::ApplyQueued
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn inject_failures() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    // The second hook fails to be created, and is left out of the registry.
+    engine.fail_on(Operation::CreateHook, 2, MH_ERROR_MEMORY_ALLOC);
+
+    let _ = guard.create_hook::<*mut c_void>(TARGET, DETOUR)?;
+    let error = guard
+        .create_hook::<*mut c_void>(SECOND_TARGET, DETOUR)
+        .unwrap_err();
+
+    assert!(matches!(error.root(), Error::FailedAllocatingMemory));
+    assert!(guard.hook_info(SECOND_TARGET).is_none());
+    assert!(!engine.is_created(SECOND_TARGET));
+
+    // A failed transaction leaves every hook as it was.
+    engine.fail_next(Operation::ApplyQueued, MH_ERROR_UNSUPPORTED_FUNCTION);
+    guard.set_hook_name(TARGET, "target")?;
+
+    assert!(guard.apply_config(HookConfig::new().enable("target")).is_err());
+    assert!(!guard.hook_info(TARGET).unwrap().is_enabled());
+
+    Ok(())
+}
+
+#[test]
+fn override_thread_freeze_method() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let _ = guard.create_hook::<*mut c_void>(TARGET, DETOUR)?;
+
+    guard.set_thread_freeze_method(ThreadFreezeMethod::Fast)?;
+    engine.clear_calls();
+
+    // The method is restored afterwards.
+    guard.enable_hook_with(TARGET, ThreadFreezeMethod::None)?;
+    assert_eq!(guard.thread_freeze_method(), ThreadFreezeMethod::Fast);
+
+    assert_eq!(
+        engine.calls(),
+        [
+            EngineCall::SetThreadFreezeMethod(ThreadFreezeMethod::None),
+            EngineCall::EnableHook { target: TARGET },
+            EngineCall::SetThreadFreezeMethod(ThreadFreezeMethod::Fast),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn exclude_threads() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let _ = guard.create_hook::<*mut c_void>(TARGET, DETOUR)?;
+    guard.exclude_thread(42);
+    engine.clear_calls();
+
+    // Threads are suspended by the crate, so the engine mustn't suspend any.
+    guard.enable_hook(TARGET)?;
+
+    assert_eq!(guard.excluded_threads().collect::<Vec<_>>(), [42]);
+    assert_eq!(
+        engine.calls(),
+        [
+            EngineCall::SetThreadFreezeMethod(ThreadFreezeMethod::None),
+            EngineCall::EnableHook { target: TARGET },
+            EngineCall::SetThreadFreezeMethod(ThreadFreezeMethod::Original),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn custom_thread_freezer() -> Result<()> {
+    struct RecordingFreezer(Arc<Mutex<Vec<&'static str>>>);
+
+    impl ThreadFreezer for RecordingFreezer {
+        fn freeze(&mut self) {
+            self.0.lock().unwrap().push("freeze");
+        }
+
+        fn unfreeze(&mut self) {
+            self.0.lock().unwrap().push("unfreeze");
+        }
+    }
+
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    guard.set_thread_freezer(RecordingFreezer(events.clone()));
+
+    // Creating a hook patches nothing, so only enabling it freezes threads.
+    let _ = guard.create_and_enable_hook::<*mut c_void>(TARGET, DETOUR)?;
+
+    assert_eq!(*events.lock().unwrap(), ["freeze", "unfreeze"]);
+
+    Ok(())
+}
+
+#[test]
+fn freeze_free_hook() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    assert!(matches!(
+        guard.set_freeze_free(TARGET, true).unwrap_err(),
+        Error::NotCreated
+    ));
+
+    let _ = guard.create_hook::<*mut c_void>(TARGET, DETOUR)?;
+    guard.set_freeze_free(TARGET, true)?;
+    assert!(guard.is_freeze_free(TARGET));
+    engine.clear_calls();
+
+    // Threads are left running, and the method is restored afterwards.
+    guard.enable_hook(TARGET)?;
+    assert_eq!(guard.thread_freeze_method(), ThreadFreezeMethod::Original);
+
+    assert_eq!(
+        engine.calls(),
+        [
+            EngineCall::SetThreadFreezeMethod(ThreadFreezeMethod::None),
+            EngineCall::EnableHook { target: TARGET },
+            EngineCall::SetThreadFreezeMethod(ThreadFreezeMethod::Original),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn enable_hook_in_background() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let _ = guard.create_hook::<*mut c_void>(TARGET, DETOUR)?;
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    guard.enable_hook_async(TARGET, move |result| sender.send(result.is_ok()).unwrap())?;
+
+    assert!(receiver.recv_timeout(Duration::from_secs(5)).unwrap());
+    assert!(engine.is_enabled(TARGET));
+
+    // The registry catches up once the background toggles are waited for.
+    guard.set_thread_freeze_method(ThreadFreezeMethod::Fast)?;
+    assert!(guard.hooks().all(|hook| hook.enabled));
+
+    Ok(())
+}
+
+#[test]
+fn background_toggles_apply_in_order() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let _ = guard.create_hook::<*mut c_void>(TARGET, DETOUR)?;
+    engine.clear_calls();
+
+    // Disabling waits for the pending enable, rather than racing it.
+    guard.enable_hook_async(TARGET, |_| {})?;
+    guard.disable_hook(TARGET)?;
+
+    assert!(!engine.is_enabled(TARGET));
+    assert!(guard.hooks().all(|hook| !hook.enabled));
+    assert_eq!(
+        engine.calls(),
+        [
+            EngineCall::EnableHook { target: TARGET },
+            EngineCall::DisableHook { target: TARGET },
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn enable_hooks_at_once() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let _ = guard.create_hook::<*mut c_void>(TARGET, DETOUR)?;
+    let _ = guard.create_hook::<*mut c_void>(SECOND_TARGET, DETOUR)?;
+    engine.clear_calls();
+
+    guard.enable_hooks(&[TARGET, SECOND_TARGET])?;
+
+    assert!(engine.is_enabled(TARGET) && engine.is_enabled(SECOND_TARGET));
+    assert_eq!(
+        engine.calls(),
+        [
+            EngineCall::QueueEnableHook { target: TARGET },
+            EngineCall::QueueEnableHook {
+                target: SECOND_TARGET
+            },
+            EngineCall::ApplyQueued
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn create_and_enable_hooks_atomically() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    // The second entry fails, so the first one is rolled back.
+    engine.fail_on(Operation::CreateHook, 2, MH_ERROR_MEMORY_ALLOC);
+
+    let error = guard
+        .create_and_enable_hooks(&[(TARGET, DETOUR), (SECOND_TARGET, DETOUR)])
+        .unwrap_err();
+
+    assert!(matches!(error, Error::BatchFailed { index: Some(1), .. }));
+    assert!(matches!(error.root(), Error::FailedAllocatingMemory));
+    assert!(!engine.is_created(TARGET));
+    assert_eq!(guard.hooks().count(), 0);
+
+    let originals = guard.create_and_enable_hooks(&[(TARGET, DETOUR), (SECOND_TARGET, DETOUR)])?;
+
+    assert_eq!(originals, [TARGET, SECOND_TARGET]);
+    assert!(engine.is_enabled(TARGET) && engine.is_enabled(SECOND_TARGET));
+
+    Ok(())
+}
+
+#[test]
+fn reset() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let _ = guard.create_and_enable_hook::<*mut c_void>(TARGET, DETOUR)?;
+    guard.reset()?;
+
+    assert_eq!(guard.hooks().count(), 0);
+    assert!(!engine.is_created(TARGET));
+    assert!(!engine.calls().contains(&EngineCall::Uninitialize));
+
+    // Hooks can be created again.
+    let _ = guard.create_and_enable_hook::<*mut c_void>(TARGET, DETOUR)?;
+
+    Ok(())
+}
+
+#[test]
+fn reset_in_reverse_order() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let _ = guard.create_and_enable_hook::<*mut c_void>(TARGET, DETOUR)?;
+    let _ = guard.create_and_enable_hook::<*mut c_void>(SECOND_TARGET, DETOUR)?;
+    engine.clear_calls();
+
+    // The latest hook is torn down first.
+    guard.reset()?;
+
+    assert_eq!(
+        engine.calls(),
+        [
+            EngineCall::QueueDisableHook {
+                target: SECOND_TARGET
+            },
+            EngineCall::QueueDisableHook { target: TARGET },
+            EngineCall::ApplyQueued,
+            EngineCall::RemoveHook {
+                target: SECOND_TARGET
+            },
+            EngineCall::RemoveHook { target: TARGET },
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn respect_dependencies() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let _ = guard.create_hook::<*mut c_void>(SECOND_TARGET, DETOUR)?;
+    let _ = guard.create_hook::<*mut c_void>(TARGET, DETOUR)?;
+    guard.add_dependency(SECOND_TARGET, TARGET)?;
+
+    assert!(matches!(
+        guard.add_dependency(TARGET, SECOND_TARGET),
+        Err(Error::DependencyCycle)
+    ));
+
+    // A dependency that fails to be enabled keeps its dependent disabled.
+    engine.fail_next(Operation::EnableHook, MH_ERROR_UNSUPPORTED_FUNCTION);
+    assert!(guard.enable_hook(SECOND_TARGET).is_err());
+    assert!(!engine.is_enabled(SECOND_TARGET));
+
+    engine.clear_calls();
+    guard.enable_hook(SECOND_TARGET)?;
+    guard.disable_hook(TARGET)?;
+
+    assert_eq!(
+        engine.calls(),
+        [
+            EngineCall::EnableHook { target: TARGET },
+            EngineCall::EnableHook {
+                target: SECOND_TARGET
+            },
+            EngineCall::DisableHook {
+                target: SECOND_TARGET
+            },
+            EngineCall::DisableHook { target: TARGET },
+        ]
+    );
+
+    // The dependent is torn down first, even though it was created first.
+    engine.clear_calls();
+    guard.reset()?;
+
+    assert_eq!(
+        engine.calls(),
+        [
+            EngineCall::RemoveHook {
+                target: SECOND_TARGET
+            },
+            EngineCall::RemoveHook { target: TARGET },
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn reset_restores_patches() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let mut data = Box::new([1u8, 2, 3, 4]);
+    let address = data.as_mut_ptr() as *mut c_void;
+
+    // Overlapping patches are undone latest first.
+    unsafe {
+        guard.apply_patch(address, &[0xAA, 0xBB])?;
+        guard.apply_patch(address.wrapping_add(1), &[0xCC, 0xDD])?;
+    }
+    assert_eq!(*data, [0xAA, 0xCC, 0xDD, 4]);
+    assert_eq!(guard.patches().count(), 2);
+
+    guard.reset()?;
+    assert_eq!(*data, [1, 2, 3, 4]);
+    assert_eq!(guard.patches().count(), 0);
+
+    // Patches are looked up by address.
+    unsafe { guard.apply_patch(address, &[0xEE])? };
+    assert!(matches!(
+        guard.remove_patch(address.wrapping_add(1)),
+        Err(Error::UnknownPatch(_))
+    ));
+    guard.remove_patch(address)?;
+    assert_eq!(*data, [1, 2, 3, 4]);
+
+    Ok(())
+}
+
+#[test]
+fn lifecycle() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let _ = guard.create_and_enable_hook::<*mut c_void>(TARGET, DETOUR)?;
+
+    // A failed close poisons the guard.
+    engine.fail_next(Operation::Uninitialize, MH_ERROR_UNABLE_TO_UNINITIALIZE);
+    assert!(guard.try_close().is_err());
+    assert_eq!(guard.state(), GuardState::Poisoned);
+
+    // Meanwhile, operating on the engine is refused.
+    assert!(matches!(guard.disable_hook(TARGET), Err(Error::Poisoned)));
+
+    // Retrying closes it, after which the hooks are gone.
+    guard.try_close()?;
+    assert_eq!(guard.state(), GuardState::Closed);
+    assert_eq!(guard.hooks().count(), 0);
+
+    // A closed guard can be brought back.
+    guard.recover()?;
+    assert_eq!(guard.state(), GuardState::Initialized);
+    let _ = guard.create_and_enable_hook::<*mut c_void>(TARGET, DETOUR)?;
+
+    Ok(())
+}
+
+#[test]
+fn close_with_retry() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let _ = guard.create_and_enable_hook::<*mut c_void>(TARGET, DETOUR)?;
+
+    // The first two attempts fail.
+    engine.fail_on(Operation::Uninitialize, 1, MH_ERROR_UNABLE_TO_UNINITIALIZE);
+    engine.fail_on(Operation::Uninitialize, 2, MH_ERROR_UNABLE_TO_UNINITIALIZE);
+
+    guard.close_with_retry(&RetryPolicy {
+        delay: Duration::ZERO,
+        disable_first: Some(Duration::ZERO),
+        ..RetryPolicy::default()
+    })?;
+
+    assert_eq!(guard.state(), GuardState::Closed);
+    assert!(engine.calls().contains(&EngineCall::DisableHook {
+        target: std::ptr::null_mut()
+    }));
+
+    Ok(())
+}
+
+#[test]
+fn disable_scoped() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let _ = guard.create_and_enable_hook::<*mut c_void>(TARGET, DETOUR)?;
+
+    {
+        let scoped = guard.disable_scoped(TARGET)?;
+        assert!(!engine.is_enabled(TARGET));
+        assert!(!scoped.hook_info(TARGET).unwrap().is_enabled());
+    }
+
+    // Dropping the scope re-enabled it.
+    assert!(engine.is_enabled(TARGET));
+
+    // Hooks that were already disabled stay so.
+    guard.disable_hook(TARGET)?;
+    guard.disable_scoped(TARGET)?.restore()?;
+    assert!(!engine.is_enabled(TARGET));
+
+    assert!(matches!(guard.disable_scoped(DETOUR), Err(Error::NotCreated)));
+
+    Ok(())
+}
+
+#[test]
+fn with_hook_disabled() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let _ = guard.create_and_enable_hook::<*mut c_void>(TARGET, DETOUR)?;
+
+    let enabled = guard.with_hook_disabled(TARGET, || engine.is_enabled(TARGET))?;
+    assert!(!enabled);
+    assert!(engine.is_enabled(TARGET));
+
+    // Panics re-enable the hook as well.
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        guard.with_hook_disabled(TARGET, || panic!("maintenance failed"))
+    }));
+    assert!(result.is_err());
+    assert!(engine.is_enabled(TARGET));
+
+    Ok(())
+}
+
+#[test]
+fn suspend_and_resume() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let _ = guard.create_and_enable_hook::<*mut c_void>(TARGET, DETOUR)?;
+    let _ = guard.create_hook::<*mut c_void>(SECOND_TARGET, DETOUR)?;
+    engine.clear_calls();
+
+    guard.suspend_all()?;
+    assert!(guard.is_suspended());
+    assert!(!engine.is_enabled(TARGET));
+
+    // Only the hook that was enabled before comes back.
+    guard.resume_all()?;
+    assert!(!guard.is_suspended());
+    assert!(engine.is_enabled(TARGET));
+    assert!(!engine.is_enabled(SECOND_TARGET));
+
+    assert_eq!(
+        engine.calls(),
+        [
+            EngineCall::QueueDisableHook { target: TARGET },
+            EngineCall::ApplyQueued,
+            EngineCall::QueueEnableHook { target: TARGET },
+            EngineCall::ApplyQueued
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn describe_existing_hook() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let _ = guard.create_and_enable_hook::<*mut c_void>(TARGET, DETOUR)?;
+    guard.set_hook_name(TARGET, "target")?;
+
+    let error = guard
+        .create_hook::<*mut c_void>(TARGET, 0x3000 as _)
+        .unwrap_err();
+    assert!(matches!(error.root(), Error::AlreadyCreated));
+
+    // The error says who got there first.
+    let existing = error.context().unwrap().existing.as_ref().unwrap();
+    assert_eq!(existing.detour, DETOUR as usize);
+    assert!(existing.enabled);
+    assert_eq!(existing.name.as_deref(), Some("target"));
+    assert!(
+        error
+            .to_string()
+            .contains("already hooked as `target`, detouring to 0x2000, enabled")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn display_hook() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let _ = guard.create_and_enable_hook::<*mut c_void>(TARGET, DETOUR)?;
+    guard.set_hook_name(TARGET, "target")?;
+
+    // Addresses outside of any module stay raw, even with the `symbol` feature.
+    assert_eq!(
+        guard.hook_info(TARGET).unwrap().to_string(),
+        "`target`: 0x1000 -> 0x2000, enabled"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn create_or_get_hook() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let original = guard.create_or_get_hook::<*mut c_void>(TARGET, DETOUR)? as *const _;
+
+    // Running the same initialization again hands out the same original.
+    let again = guard.create_or_get_hook::<*mut c_void>(TARGET, DETOUR)? as *const _;
+    assert_eq!(original, again);
+    assert_eq!(
+        engine
+            .calls()
+            .iter()
+            .filter(|call| matches!(call, EngineCall::CreateHook { .. }))
+            .count(),
+        1
+    );
+
+    // A different detour is a conflict.
+    assert!(matches!(
+        guard
+            .create_or_get_hook::<*mut c_void>(TARGET, 0x3000 as _)
+            .unwrap_err()
+            .root(),
+        Error::AlreadyCreated
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn rebind_requires_indirection() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let _ = guard.create_hook::<*mut c_void>(TARGET, DETOUR)?;
+
+    assert!(matches!(guard.rebind(TARGET, 0x3000 as _), Err(Error::NotRebindable)));
+    assert!(matches!(guard.rebind(0x3000 as _, DETOUR), Err(Error::NotCreated)));
+
+    Ok(())
+}
+
+#[test]
+fn reserve_stubs() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    guard.reserve_stubs(2, None)?;
+    let _ = guard.create_rebindable_hook::<*mut c_void>(TARGET, DETOUR)?;
+    let _ = guard.create_rebindable_hook::<*mut c_void>(SECOND_TARGET, DETOUR)?;
+
+    // Reserved stubs are handed out back to back, from the same allocation.
+    let stubs = engine
+        .calls()
+        .into_iter()
+        .filter_map(|call| match call {
+            EngineCall::CreateHook { detour, .. } => Some(detour as usize),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(stubs.len(), 2);
+    assert_eq!(stubs[0].abs_diff(stubs[1]), 32);
+
+    Ok(())
+}
+
+#[test]
+fn leak_stubs_of_live_hooks() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    // One stub of its own, and one out of the pool.
+    let _ = guard.create_rebindable_hook::<*mut c_void>(TARGET, DETOUR)?;
+    guard.reserve_stubs(1, None)?;
+    let _ = guard.create_rebindable_hook::<*mut c_void>(SECOND_TARGET, DETOUR)?;
+
+    let stubs = engine
+        .calls()
+        .into_iter()
+        .filter_map(|call| match call {
+            EngineCall::CreateHook { detour, .. } => Some(detour),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    // The hooks outlive the guard, as closing it fails.
+    engine.fail_on(Operation::Uninitialize, 1, MH_ERROR_UNABLE_TO_UNINITIALIZE);
+    drop(guard);
+
+    assert!(
+        stubs
+            .iter()
+            .all(|stub| minhook_detours_rs::mem::is_accessible(*stub, 1))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn bypass_all() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let _ = guard.create_rebindable_hook::<*mut c_void>(TARGET, DETOUR)?;
+    let _ = guard.create_rebindable_hook::<*mut c_void>(SECOND_TARGET, DETOUR)?;
+    guard.switch(SECOND_TARGET)?.off();
+
+    guard.set_bypass_all(true);
+    assert!(guard.is_bypassing());
+    assert!(!guard.switch(TARGET)?.is_on());
+
+    // Hooks created meanwhile are bypassed as well.
+    let _ = guard.create_rebindable_hook::<*mut c_void>(THIRD_TARGET, DETOUR)?;
+    assert!(!guard.switch(THIRD_TARGET)?.is_on());
+
+    // Only the hooks switched off by the bypass are switched back on.
+    guard.set_bypass_all(false);
+    assert!(guard.switch(TARGET)?.is_on());
+    assert!(!guard.switch(SECOND_TARGET)?.is_on());
+    assert!(guard.switch(THIRD_TARGET)?.is_on());
+
+    Ok(())
+}
+
+#[test]
+fn stable_originals() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+    guard.reserve(8);
+
+    // Enough hooks to outgrow the reserved room several times over.
+    let targets = (1..=100).map(|index| (index * 0x1000) as *mut c_void);
+    let originals = targets
+        .clone()
+        .map(|target| guard.create_hook::<*mut c_void>(target, DETOUR))
+        .collect::<Result<Vec<_>>>()?;
+
+    // Entries never move, so the references handed out early still point at their original.
+    assert!(targets.zip(originals).all(|(target, original)| *original == target));
+
+    Ok(())
+}
+
+#[test]
+fn install_providers() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let _ = guard.create_hook::<*mut c_void>(TARGET, DETOUR)?;
+
+    // Providers don't know about each other, nor about the hooks created beforehand.
+    let overlay = |guard: &mut DetourGuard<'_>| {
+        guard.create_hook::<*mut c_void>(SECOND_TARGET, DETOUR).map(|_| ())
+    };
+    let input = |guard: &mut DetourGuard<'_>| {
+        guard.create_hook::<*mut c_void>(THIRD_TARGET, DETOUR)?;
+        guard.create_hook::<*mut c_void>(TARGET, DETOUR).map(|_| ())
+    };
+
+    let error = guard.install_providers(&[&overlay as &dyn HookProvider, &input]).unwrap_err();
+    assert!(matches!(error, Error::BatchFailed { index: Some(1), .. }));
+    assert!(matches!(error.root(), Error::AlreadyCreated));
+
+    // Only the hooks created by the providers are rolled back.
+    assert!(guard.hook_info(TARGET).is_some());
+    assert!(guard.hook_info(SECOND_TARGET).is_none());
+    assert!(guard.hook_info(THIRD_TARGET).is_none());
+
+    guard.install_providers(&[&overlay])?;
+    assert!(guard.hook_info(SECOND_TARGET).is_some());
+
+    Ok(())
+}
+
+#[test]
+fn deny_critical_functions() -> Result<()> {
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let virtual_protect = Target::export("kernel32.dll", "VirtualProtect").resolve()?;
+    assert_eq!(
+        DetourGuard::critical_function(virtual_protect).as_deref(),
+        Some("kernel32.dll!VirtualProtect")
+    );
+
+    // Forwarded exports are recognized by their implementation.
+    let heap_alloc = Target::export("kernel32.dll", "HeapAlloc").resolve()?;
+    assert!(DetourGuard::critical_function(heap_alloc).is_some());
+
+    guard.set_critical_policy(CriticalPolicy::Deny);
+    let error = guard.create_hook::<*mut c_void>(virtual_protect, DETOUR).unwrap_err();
+    assert!(matches!(error, Error::CriticalFunction(_)));
+    assert!(guard.hook_info(virtual_protect).is_none());
+
+    // Functions the crate doesn't rely on are unaffected.
+    assert!(DetourGuard::critical_function(TARGET).is_none());
+    let _ = guard.create_hook::<*mut c_void>(TARGET, DETOUR)?;
+
+    Ok(())
+}
+
+#[test]
+fn flush_through_worker() -> Result<()> {
+    use minhook_detours_rs::{guard::WorkerSink, ring::LogRing, ring_log};
+
+    static LOG: LogRing<16> = LogRing::new();
+
+    #[derive(Clone, Default)]
+    struct Sink(Arc<Mutex<Vec<String>>>);
+
+    impl WorkerSink for Sink {
+        fn log(&mut self, message: &str) {
+            self.0.lock().unwrap().push(message.to_owned());
+        }
+
+        fn shutdown(&mut self) {
+            self.0.lock().unwrap().push("shutdown".to_owned());
+        }
+    }
+
+    let engine = MockEngine::new();
+    let mut guard = DetourGuard::with_mock(&engine)?;
+
+    let sink = Sink::default();
+    guard.start_worker(&LOG, sink.clone(), Duration::from_secs(3600));
+    assert!(guard.is_worker_running());
+
+    ring_log!(LOG, "called {:#x}", TARGET as usize);
+
+    // Closing flushes one last time, long before the interval elapses.
+    guard.close()?;
+    assert_eq!(*sink.0.lock().unwrap(), ["called 0x1000", "shutdown"]);
+
+    Ok(())
+}
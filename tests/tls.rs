@@ -0,0 +1,17 @@
+use minhook_detours_rs::{error::Result, tls_callback};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+fn install() -> Result<()> {
+    INSTALLED.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+tls_callback!(install);
+
+#[test]
+fn run_before_main() {
+    // The loader ran the callback before the test harness got to run.
+    assert!(INSTALLED.load(Ordering::SeqCst));
+}
@@ -0,0 +1,97 @@
+#![cfg(feature = "window")]
+
+use minhook_detours_rs::{
+    error::{Error, Result},
+    guard::DetourGuard,
+    window::WndProcHook,
+};
+use serial_test::serial;
+use std::ptr::{null, null_mut};
+use winapi::{
+    shared::{
+        minwindef::{LPARAM, LRESULT, UINT, WPARAM},
+        windef::HWND,
+    },
+    um::winuser::{CreateWindowExW, DefWindowProcW, DestroyWindow, SendMessageW, WM_USER},
+};
+
+unsafe extern "system" fn window_procedure(
+    window: HWND,
+    message: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match message {
+        WM_USER => 42,
+        _ => unsafe { DefWindowProcW(window, message, wparam, lparam) },
+    }
+}
+
+fn create_window() -> HWND {
+    let class = "STATIC\0".encode_utf16().collect::<Vec<_>>();
+    let window = unsafe {
+        CreateWindowExW(
+            0,
+            class.as_ptr(),
+            null(),
+            0,
+            0,
+            0,
+            1,
+            1,
+            null_mut(),
+            null_mut(),
+            null_mut(),
+            null_mut(),
+        )
+    };
+    assert!(!window.is_null());
+
+    window
+}
+
+#[test]
+fn subclass_window() -> Result<()> {
+    let window = create_window();
+
+    {
+        let hook = unsafe { WndProcHook::new(window, window_procedure)? };
+        assert_eq!(unsafe { SendMessageW(window, WM_USER, 0, 0) }, 42);
+
+        // The original procedure doesn't know about the message.
+        assert_eq!(unsafe { hook.call_original(window, WM_USER, 0, 0) }, 0);
+    }
+
+    // Dropping the hook restored the original procedure.
+    assert_eq!(unsafe { SendMessageW(window, WM_USER, 0, 0) }, 0);
+
+    unsafe { DestroyWindow(window) };
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn subclass_window_through_guard() -> Result<()> {
+    let window = create_window();
+    let mut guard = DetourGuard::new()?;
+
+    let original = unsafe { guard.subclass_window(window, window_procedure)? };
+    assert_eq!(unsafe { SendMessageW(window, WM_USER, 0, 0) }, 42);
+    assert_eq!(unsafe { original.call(window, WM_USER, 0, 0) }, 0);
+    assert_eq!(guard.window_hooks().count(), 1);
+
+    // Resetting the guard restores the original procedure, like the rest of its patches.
+    guard.reset()?;
+    assert_eq!(unsafe { SendMessageW(window, WM_USER, 0, 0) }, 0);
+    assert!(matches!(guard.remove_subclass(window), Err(Error::NotCreated)));
+
+    // So does removing the subclass.
+    let _ = unsafe { guard.subclass_window(window, window_procedure)? };
+    guard.remove_subclass(window)?;
+    assert_eq!(unsafe { SendMessageW(window, WM_USER, 0, 0) }, 0);
+
+    unsafe { DestroyWindow(window) };
+
+    Ok(())
+}
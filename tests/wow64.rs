@@ -0,0 +1,41 @@
+use minhook_detours_rs::{
+    error::{Error, Result},
+    wow64::{Bitness, export_rva, is_wow64, module_bitness},
+};
+use std::ptr::null_mut;
+use winapi::um::libloaderapi::{GetModuleHandleW, GetProcAddress};
+
+#[test]
+fn native_module() -> Result<()> {
+    let kernel32 = unsafe {
+        GetModuleHandleW(
+            "kernel32.dll\0"
+                .encode_utf16()
+                .collect::<Vec<_>>()
+                .as_ptr(),
+        )
+    };
+
+    assert_eq!(unsafe { module_bitness(kernel32)? }, Bitness::native());
+
+    // Walking the export directory agrees with the loader.
+    let rva = unsafe { export_rva(kernel32, "GetCurrentProcessId")? };
+    let address = unsafe { GetProcAddress(kernel32, c"GetCurrentProcessId".as_ptr()) };
+    assert_eq!(kernel32 as usize + rva as usize, address as usize);
+
+    assert!(matches!(
+        unsafe { export_rva(kernel32, "NotAnExport") },
+        Err(Error::InvalidExport)
+    ));
+    assert!(matches!(
+        unsafe { module_bitness(null_mut()) },
+        Err(Error::InvalidModule)
+    ));
+
+    // Only 32-bit processes can run under WOW64.
+    if Bitness::native() == Bitness::Bits64 {
+        assert!(!is_wow64());
+    }
+
+    Ok(())
+}